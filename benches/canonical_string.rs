@@ -0,0 +1,83 @@
+//! Compare the allocations and the time of formatting the canonical address of a client by `ClientAddr::to_canonical_string`, which allocates a `String` every time, and by `ClientAddr::write_canonical`, which writes into a reused buffer.
+//!
+//! Run it with `cargo bench --bench canonical_string`.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use rocket_client_addr::ClientAddr;
+
+const ITERATIONS: usize = 1_000_000;
+
+/// The system allocator, counting the allocations.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    #[inline]
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+
+        System.alloc(layout)
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn bench(name: &str, client_addrs: &[ClientAddr], mut f: impl FnMut(&ClientAddr)) {
+    // warm up
+    for client_addr in client_addrs.iter().take(ITERATIONS / 10) {
+        f(client_addr);
+    }
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+
+    for client_addr in client_addrs {
+        f(client_addr);
+    }
+
+    let elapsed = start.elapsed() / ITERATIONS as u32;
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+
+    println!("{name:<40} {elapsed:>10.2?}/iter {allocations:>10} allocations");
+}
+
+fn main() {
+    let client_addrs: Vec<ClientAddr> = (0..ITERATIONS as u32)
+        .map(|i| {
+            let ip = if i % 4 == 0 {
+                format!("2606:4700::{:x}", i & 0xFFFF)
+            } else {
+                format!("::ffff:93.184.{}.{}", (i >> 8) & 0xFF, i & 0xFF)
+            };
+
+            ClientAddr {
+                ip: ip.parse().unwrap()
+            }
+        })
+        .collect();
+
+    bench("to_canonical_string", &client_addrs, |client_addr| {
+        black_box(client_addr.to_canonical_string());
+    });
+
+    let mut buffer = String::with_capacity(64);
+
+    bench("write_canonical (reused buffer)", &client_addrs, |client_addr| {
+        buffer.clear();
+        client_addr.write_canonical(&mut buffer).unwrap();
+        black_box(&buffer);
+    });
+}
@@ -0,0 +1,56 @@
+//! Measure the throughput of `classify_many` on a large batch of addresses, like the ones of a day of access logs.
+//!
+//! Run it with `cargo bench --bench classify`.
+
+use std::{
+    hint::black_box,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    time::Instant,
+};
+
+use rocket_client_addr::{classify_many, Classification};
+
+const BATCH_SIZE: usize = 1_000_000;
+
+const ROUNDS: u32 = 10;
+
+fn main() {
+    // a deterministic mix of IPv4 and IPv6 addresses from a linear congruential generator
+    let mut state = 0x2545_F491_4F6C_DD1Du64;
+
+    let ips: Vec<IpAddr> = (0..BATCH_SIZE)
+        .map(|i| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+
+            let bits = state >> 16;
+
+            if i % 4 == 0 {
+                IpAddr::V6(Ipv6Addr::from(u128::from(bits) << 64 | u128::from(state)))
+            } else {
+                IpAddr::V4(Ipv4Addr::from(bits as u32))
+            }
+        })
+        .collect();
+
+    // warm up
+    black_box(classify_many(black_box(&ips)));
+
+    let start = Instant::now();
+
+    let mut public = 0;
+
+    for _ in 0..ROUNDS {
+        let classifications = classify_many(black_box(&ips));
+
+        public += classifications.iter().filter(|c| **c == Classification::Public).count();
+    }
+
+    let elapsed = start.elapsed() / ROUNDS;
+
+    println!(
+        "{:<40} {elapsed:>10.2?}/batch of {BATCH_SIZE} ({:.2?}/address)",
+        "classify_many",
+        elapsed / BATCH_SIZE as u32
+    );
+    println!("{:.1}% public", public as f64 * 100.0 / (BATCH_SIZE as f64 * ROUNDS as f64));
+}
@@ -0,0 +1,84 @@
+//! Compare the cost of the `ClientAddr` and `&ClientAddr` request guards, both for the first extraction in a request, which resolves the address of the client, and for the later ones, which read it from the request-local cache (`CachedClientAddr`).
+//!
+//! Run it with `cargo bench --bench guards`.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use rocket::{
+    http::Header,
+    local::blocking::{Client, LocalRequest},
+    request::FromRequest,
+    tokio::runtime::{Builder, Runtime},
+};
+use rocket_client_addr::{ClientAddr, IpNetSet, Resolver};
+
+fn request(client: &Client) -> LocalRequest<'_> {
+    // a client behind a CDN and two load balancers, so the whole chain is walked
+    client.get("/").remote("34.120.0.3:8000".parse().unwrap()).header(Header::new(
+        "X-Forwarded-For",
+        "93.184.216.34, 34.120.0.1, 10.0.0.1, 34.120.0.2, 34.120.0.4",
+    ))
+}
+
+fn bench_first(c: &mut Criterion, client: &Client, runtime: &Runtime) {
+    let mut group = c.benchmark_group("first extraction");
+
+    group.bench_function("ClientAddr", |b| {
+        b.iter_batched_ref(
+            || request(client),
+            |request| black_box(runtime.block_on(ClientAddr::from_request(request.inner()))),
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.bench_function("&ClientAddr", |b| {
+        b.iter_batched_ref(
+            || request(client),
+            |request| {
+                let outcome = runtime.block_on(<&ClientAddr>::from_request(request.inner()));
+
+                black_box(outcome.succeeded().copied())
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+}
+
+fn bench_cached(c: &mut Criterion, client: &Client, runtime: &Runtime) {
+    let request = request(client);
+    let request = request.inner();
+
+    // populate the cache, like the first request guard does
+    runtime.block_on(ClientAddr::from_request(request)).unwrap();
+
+    let mut group = c.benchmark_group("cached extraction");
+
+    group.bench_function("ClientAddr", |b| {
+        b.iter(|| black_box(runtime.block_on(ClientAddr::from_request(black_box(request)))))
+    });
+
+    group.bench_function("&ClientAddr", |b| {
+        b.iter(|| black_box(runtime.block_on(<&ClientAddr>::from_request(black_box(request)))))
+    });
+
+    group.finish();
+}
+
+fn guards(c: &mut Criterion) {
+    let resolver = Resolver::builder()
+        .trust_proxies("34.120.0.0/16".parse().into_iter().collect::<IpNetSet>())
+        .build();
+
+    let client = Client::untracked(rocket::build().manage(resolver)).unwrap();
+
+    let runtime = Builder::new_current_thread().build().unwrap();
+
+    bench_first(c, &client, &runtime);
+    bench_cached(c, &client, &runtime);
+}
+
+criterion_group!(benches, guards);
+criterion_main!(benches);
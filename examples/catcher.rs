@@ -0,0 +1,27 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::Request;
+use rocket_client_addr::ClientAddr;
+
+#[get("/")]
+fn index(client_addr: &ClientAddr) -> String {
+    client_addr.to_string()
+}
+
+#[catch(404)]
+async fn not_found(request: &Request<'_>) -> String {
+    match ClientAddr::resolve(request).await {
+        Some(client_addr) => {
+            println!("{client_addr} requested a missing page: {}", request.uri());
+
+            format!("Sorry, {client_addr}, this page does not exist.")
+        },
+        None => String::from("Sorry, this page does not exist."),
+    }
+}
+
+#[launch]
+fn rocket() -> _ {
+    rocket::build().mount("/", routes![index]).register("/", catchers![not_found])
+}
@@ -0,0 +1,51 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::request::{self, FromRequest, Request};
+use rocket_client_addr::{client_addr_derived, ClientAddrError, IpNet};
+
+/// The `/24` or `/64` network of a client, derived once per request and shared by the request guards below.
+struct ClientNet(IpNet);
+
+async fn client_net<'r>(
+    request: &'r Request<'_>,
+) -> request::Outcome<&'r ClientNet, ClientAddrError> {
+    client_addr_derived(request, |client_addr| {
+        let prefix = if client_addr.get_ipv4().is_some() { 24 } else { 64 };
+
+        ClientNet(client_addr.to_net(prefix))
+    })
+    .await
+}
+
+struct RateLimitKey(String);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RateLimitKey {
+    type Error = ClientAddrError;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        client_net(request).await.map(|net| RateLimitKey(format!("rate-limit:{}", net.0)))
+    }
+}
+
+struct AuditTag(String);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AuditTag {
+    type Error = ClientAddrError;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        client_net(request).await.map(|net| AuditTag(format!("audit:{}", net.0)))
+    }
+}
+
+#[get("/")]
+fn index(rate_limit_key: RateLimitKey, audit_tag: AuditTag) -> String {
+    format!("{}\n{}", rate_limit_key.0, audit_tag.0)
+}
+
+#[launch]
+fn rocket() -> _ {
+    rocket::build().mount("/", routes![index])
+}
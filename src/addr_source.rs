@@ -0,0 +1,37 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::CdnHeader;
+
+/// A source of the IP address of a client.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum AddrSource {
+    /// The TCP peer (`Request::remote`).
+    Remote,
+    /// A CDN header.
+    CdnHeader(CdnHeader),
+    /// The source configured by `Config::custom_source`.
+    CustomSource,
+    /// The `X-Forwarded-For` header.
+    ForwardedFor,
+    /// The `Forwarded` header (RFC 7239).
+    Forwarded,
+    /// The header configured by Rocket's `ip_header` (`Request::real_ip`).
+    RealIp,
+    /// The address configured by `Config::fallback`.
+    Fallback,
+}
+
+impl Display for AddrSource {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AddrSource::Remote => f.write_str("remote"),
+            AddrSource::CdnHeader(cdn_header) => f.write_str(cdn_header.header_name()),
+            AddrSource::CustomSource => f.write_str("custom source"),
+            AddrSource::ForwardedFor => f.write_str("X-Forwarded-For"),
+            AddrSource::Forwarded => f.write_str("Forwarded"),
+            AddrSource::RealIp => f.write_str("ip_header"),
+            AddrSource::Fallback => f.write_str("fallback"),
+        }
+    }
+}
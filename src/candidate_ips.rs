@@ -0,0 +1,65 @@
+use std::{collections::HashSet, net::IpAddr};
+
+use rocket::request::Request;
+
+use crate::{
+    cdn_header, forwarded,
+    resolver::{parse_ip_token, real_ip_header},
+    AddrSource,
+};
+
+/// Get every address in a request which could be taken as the address of the client by some configuration, with its source, e.g. for a security review which looks for header injection. It is not the resolved address, and nothing in it is verified.
+///
+/// The addresses are listed in the following order, and a source which is absent or holds no valid IP address is omitted. Duplicate pairs of a source and an address are listed once.
+///
+/// 1. The TCP peer.
+/// 1. Every CDN header (see `CdnHeader`), regardless of which ones are trusted. The first entry of a list is used, except for `X-Forwarded`, whose rightmost element is used.
+/// 1. The header configured by Rocket's `ip_header`.
+/// 1. Every entry of every `X-Forwarded-For` header, from left to right.
+/// 1. Every `for` address of the `Forwarded` headers, from left to right.
+///
+/// ```rust
+/// use rocket::Request;
+/// use rocket_client_addr::candidate_ips;
+///
+/// fn audit(request: &Request<'_>) {
+///     for (source, ip) in candidate_ips(request) {
+///         println!("{source}: {ip}");
+///     }
+/// }
+/// ```
+pub fn candidate_ips(request: &Request<'_>) -> Vec<(AddrSource, IpAddr)> {
+    let mut candidates = Vec::new();
+
+    if let Some(addr) = request.remote() {
+        candidates.push((AddrSource::Remote, addr.ip()));
+    }
+
+    for cdn_header in cdn_header::ALL {
+        if let Some(ip) = cdn_header.get_ip(request, true) {
+            candidates.push((AddrSource::CdnHeader(cdn_header), ip));
+        }
+    }
+
+    if let Some(ip) = real_ip_header(request) {
+        candidates.push((AddrSource::RealIp, ip));
+    }
+
+    for value in request.headers().get("x-forwarded-for") {
+        for ip in value.split(',').filter_map(|token| parse_ip_token(token.trim())) {
+            candidates.push((AddrSource::ForwardedFor, ip));
+        }
+    }
+
+    for node in forwarded::parse_chain(request).into_iter().flatten() {
+        if let Some(ip) = node.addr() {
+            candidates.push((AddrSource::Forwarded, ip));
+        }
+    }
+
+    let mut seen = HashSet::new();
+
+    candidates.retain(|candidate| seen.insert(*candidate));
+
+    candidates
+}
@@ -0,0 +1,91 @@
+use std::{
+    fmt::{self, Debug, Display, Formatter},
+    ops::Deref,
+};
+
+use rocket::request::{self, FromRequest, Request};
+
+use crate::{client_addr_outcome, ClientAddr, ClientAddrError};
+
+/// A `ClientAddr` whose IP address is always canonical (see `ClientAddr::canonical_ip`), so that every accessor and `Display` treat an IPv4-mapped IPv6 address (`::ffff:a.b.c.d`) as its IPv4 address. The methods of `ClientAddr` are available through `Deref`.
+///
+/// It can also be used as a request guard, which is resolved like `ClientAddr`. To canonicalize the addresses of every request guard instead, see `Config::canonicalize_mapped`.
+///
+/// ```rust
+/// use rocket_client_addr::{Canonicalized, ClientAddr};
+///
+/// let client_addr = ClientAddr {
+///     ip: "::ffff:93.184.216.34".parse().unwrap(),
+/// };
+///
+/// assert_eq!("::ffff:93.184.216.34", client_addr.to_string());
+/// assert_eq!("93.184.216.34", client_addr.canonicalized().to_string());
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct Canonicalized(ClientAddr);
+
+impl Canonicalized {
+    /// Canonicalize a `ClientAddr`.
+    #[inline]
+    pub fn new(client_addr: ClientAddr) -> Canonicalized {
+        Canonicalized(ClientAddr {
+            ip: client_addr.canonical_ip()
+        })
+    }
+
+    /// Get the canonical `ClientAddr`.
+    #[inline]
+    pub fn into_inner(self) -> ClientAddr {
+        self.0
+    }
+}
+
+impl ClientAddr {
+    /// Get the canonical form of this `ClientAddr`. See `Canonicalized`.
+    #[inline]
+    pub fn canonicalized(&self) -> Canonicalized {
+        Canonicalized::new(*self)
+    }
+}
+
+impl From<ClientAddr> for Canonicalized {
+    #[inline]
+    fn from(client_addr: ClientAddr) -> Self {
+        Canonicalized::new(client_addr)
+    }
+}
+
+impl Deref for Canonicalized {
+    type Target = ClientAddr;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Debug for Canonicalized {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // Transparent
+        Debug::fmt(&self.0, f)
+    }
+}
+
+impl Display for Canonicalized {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // Transparent
+        Display::fmt(&self.0, f)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Canonicalized {
+    type Error = ClientAddrError;
+
+    #[inline]
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        client_addr_outcome(request).await.map(Canonicalized::new)
+    }
+}
@@ -0,0 +1,141 @@
+use std::net::{IpAddr, SocketAddr};
+
+use rocket::request::Request;
+
+use crate::{resolver::parse_ip_token, IpNetSet, ParsedForwarded};
+
+/// Headers set by CDNs and some other proxies which carry the IP address of a client.
+///
+/// None of them is trusted by default. Trust one only if the application is really behind that CDN or proxy, because any client can send these headers.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum CdnHeader {
+    /// `CF-Connecting-IP`, set by Cloudflare.
+    Cloudflare,
+    /// `Fastly-Client-IP`, set by Fastly.
+    Fastly,
+    /// `X-Azure-ClientIP`, set by Azure Front Door. It is the IP address of the client which Front Door determined, so it may be taken from a forwarding header sent by the client.
+    AzureClientIp,
+    /// `X-Azure-SocketIP`, set by Azure Front Door. It is the IP address of the TCP connection which Front Door received, so it cannot be overwritten by the client but it may be a proxy of the client.
+    AzureSocketIp,
+    /// `CloudFront-Viewer-Address`, set by Amazon CloudFront when it is included in an origin request policy. It holds the IP address and the port of the viewer, like `198.51.100.10:46532`.
+    CloudFront,
+    /// `X-ProxyUser-Ip`, set by Google App Engine and Cloud Endpoints. It holds a single IP address.
+    ProxyUserIp,
+    /// `X-Forwarded`, a non-standard header set by some Nginx configurations instead of `X-Forwarded-For`. It is either a chain like `X-Forwarded-For` (`203.0.113.5, 10.0.0.2`) or elements like the `Forwarded` header (`for=203.0.113.5;proto=https`), which is told by whether it has a `=`. The rightmost element of the last `X-Forwarded` header is used, because it is the one added by the proxy which sets the header, whether the proxy appends to the header or adds another one, and the others can be forged by the client. `Config::cdn_header_tolerate_list` does not apply to it.
+    XForwarded,
+}
+
+/// Every CDN header.
+pub(crate) const ALL: [CdnHeader; 7] = [
+    CdnHeader::Cloudflare,
+    CdnHeader::Fastly,
+    CdnHeader::AzureClientIp,
+    CdnHeader::AzureSocketIp,
+    CdnHeader::CloudFront,
+    CdnHeader::ProxyUserIp,
+    CdnHeader::XForwarded,
+];
+
+/// The CDN headers tried by `CdnHeader::detect`, in order.
+const DETECTION_ORDER: [CdnHeader; 4] =
+    [CdnHeader::Cloudflare, CdnHeader::Fastly, CdnHeader::CloudFront, CdnHeader::AzureSocketIp];
+
+impl CdnHeader {
+    /// Get the name of the header.
+    #[inline]
+    pub const fn header_name(&self) -> &'static str {
+        match self {
+            CdnHeader::Cloudflare => "CF-Connecting-IP",
+            CdnHeader::Fastly => "Fastly-Client-IP",
+            CdnHeader::AzureClientIp => "X-Azure-ClientIP",
+            CdnHeader::AzureSocketIp => "X-Azure-SocketIP",
+            CdnHeader::CloudFront => "CloudFront-Viewer-Address",
+            CdnHeader::ProxyUserIp => "X-ProxyUser-Ip",
+            CdnHeader::XForwarded => "X-Forwarded",
+        }
+    }
+
+    /// Get the CDN header with the given name, case-insensitively.
+    #[inline]
+    pub(crate) fn from_header_name(name: &str) -> Option<CdnHeader> {
+        ALL.into_iter().find(|cdn_header| cdn_header.header_name().eq_ignore_ascii_case(name))
+    }
+
+    /// Detect which CDN a request came through by the headers present in it. The headers are checked in the following order and the first present one is returned:
+    ///
+    /// 1. `CF-Connecting-IP` (`CdnHeader::Cloudflare`)
+    /// 1. `Fastly-Client-IP` (`CdnHeader::Fastly`)
+    /// 1. `CloudFront-Viewer-Address` (`CdnHeader::CloudFront`)
+    /// 1. `X-Azure-SocketIP` (`CdnHeader::AzureSocketIp`), which is preferred to `X-Azure-ClientIP` because Front Door does not take it from a forwarding header sent by the client
+    ///
+    /// Any client can send these headers, so the result only tells which header to read, not that the request really came through that CDN.
+    pub fn detect(request: &Request<'_>) -> Option<CdnHeader> {
+        let headers = request.headers();
+
+        DETECTION_ORDER.into_iter().find(|cdn_header| headers.contains(cdn_header.header_name()))
+    }
+
+    /// Parse the header from a request. The header should hold a single IP address, except `X-Forwarded` (see `CdnHeader::XForwarded`). If `tolerate_list` is `true`, a comma-separated list is also accepted and its first (leftmost) entry, which is the address seen by the outermost CDN, is used.
+    pub(crate) fn get_ip(&self, request: &Request<'_>, tolerate_list: bool) -> Option<IpAddr> {
+        if *self == CdnHeader::XForwarded {
+            let value = request.headers().get(self.header_name()).last()?;
+
+            return if value.contains('=') {
+                ParsedForwarded::parse([value]).entries.last()?.for_.as_ref()?.addr()
+            } else {
+                parse_ip_token(value.rsplit(',').next()?.trim())
+            };
+        }
+
+        let mut value = request.headers().get_one(self.header_name())?;
+
+        if tolerate_list {
+            if let Some((first, _)) = value.split_once(',') {
+                value = first;
+            }
+        }
+
+        let value = value.trim();
+
+        match self {
+            CdnHeader::CloudFront => {
+                // an IPv6 address is not enclosed in brackets, e.g. `2001:db8::1:46532`
+                match value.parse::<SocketAddr>() {
+                    Ok(addr) => Some(addr.ip()),
+                    Err(_) => value.rsplit_once(':')?.0.parse::<IpAddr>().ok(),
+                }
+            },
+            _ => value.parse::<IpAddr>().ok(),
+        }
+    }
+}
+
+/// A layer of CDN in front of the trusted proxies, like Cloudflare in front of an internal load balancer, whose header is trusted only for the requests coming from its own address ranges.
+///
+/// When the address selected from the TCP peer or the forwarded chain is in `ranges`, that address is an edge of the CDN rather than the client, so the address in `header` is used instead. If that address is in the ranges of another layer, the header of that layer is used in turn, so several CDNs can be chained. A request which bypasses the CDN cannot use a forged header, because the address before the trusted proxies is not in its ranges then.
+///
+/// ```rust
+/// use rocket_client_addr::{CdnHeader, CdnLayer, IpNetSet, Resolver};
+///
+/// let cloudflare = CdnLayer {
+///     header: CdnHeader::Cloudflare,
+///     ranges: ["173.245.48.0/20", "103.21.244.0/22"]
+///         .iter()
+///         .map(|net| net.parse().unwrap())
+///         .collect(),
+/// };
+///
+/// // trust X-Forwarded-For from the internal proxies, then CF-Connecting-IP from Cloudflare
+/// let resolver = Resolver::builder()
+///     .trust_proxies("10.0.0.0/8".parse().into_iter().collect::<IpNetSet>())
+///     .cdn_layer(cloudflare)
+///     .build();
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CdnLayer {
+    /// The header which the CDN sets to the address of its client.
+    pub header: CdnHeader,
+    /// The address ranges of the edges of the CDN, which connect to the next layer.
+    pub ranges: IpNetSet,
+}
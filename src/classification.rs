@@ -0,0 +1,70 @@
+use std::net::IpAddr;
+
+use crate::{client_addr::ipv6_scope, special_purpose, ClientAddr, Ipv6Scope};
+
+/// The classification of an IP address, e.g. for processing logged addresses offline. See `classify_many`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Classification {
+    /// A local address, which never identifies a client on the Internet, such as a private, loopback, link-local or documentation address. It is the same check as the one which decides whether the TCP peer or an entry of a forwarding header is skipped, applied to the canonical IP address: an IPv4-mapped IPv6 address is `Local` if its IPv4 address is, whereas the TCP peer and the entries of a forwarding header are checked as they are, so `::ffff:10.0.0.1` is not skipped there.
+    Local,
+    /// An address in the IANA special-purpose address registries which is not local, such as a shared address (`100.64.0.0/10`, used by carrier-grade NATs) or a 6to4 address. See `ClientAddr::is_special_purpose`.
+    SpecialPurpose,
+    /// Any other address, i.e. a public address.
+    Public,
+}
+
+impl Classification {
+    /// Classify an IP address. An IPv4-mapped IPv6 address is classified as its IPv4 address (see `Classification::Local`).
+    pub fn of(ip: &IpAddr) -> Classification {
+        let ip = ClientAddr {
+            ip: *ip
+        }
+        .canonical_ip();
+
+        if let IpAddr::V6(ipv6) = &ip {
+            if ipv6_scope(ipv6) != Ipv6Scope::Global {
+                return Classification::Local;
+            }
+        }
+
+        match special_purpose::lookup(&ip) {
+            Some(true) => Classification::Local,
+            Some(false) => Classification::SpecialPurpose,
+            None => Classification::Public,
+        }
+    }
+}
+
+impl ClientAddr {
+    /// Classify the IP address. See `Classification::of`.
+    #[inline]
+    pub fn classify(&self) -> Classification {
+        Classification::of(&self.ip)
+    }
+}
+
+/// Classify IP addresses in bulk, e.g. millions of addresses from logs. The registries are looked up in one pass per address, and nothing but the result is allocated.
+///
+/// ```rust
+/// use std::net::IpAddr;
+///
+/// use rocket_client_addr::{classify_many, Classification};
+///
+/// let ips: Vec<IpAddr> = ["10.0.0.1", "100.64.0.1", "93.184.216.34"]
+///     .iter()
+///     .map(|ip| ip.parse().unwrap())
+///     .collect();
+///
+/// assert_eq!(
+///     vec![
+///         Classification::Local,
+///         Classification::SpecialPurpose,
+///         Classification::Public
+///     ],
+///     classify_many(&ips)
+/// );
+/// ```
+#[inline]
+pub fn classify_many(ips: &[IpAddr]) -> Vec<Classification> {
+    ips.iter().map(Classification::of).collect()
+}
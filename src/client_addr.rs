@@ -1,4 +1,9 @@
-use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 use rocket::{
     http::Status,
@@ -6,147 +11,395 @@ use rocket::{
     request::{self, FromRequest, Request},
 };
 
+use crate::{
+    ip_net::mask_v4, resolver::Resolution, special_purpose, GeoDb, GeoInfo, IpNet, IpNetSet,
+    NoHeaderTrust, Octets, Resolver,
+};
+
 /// The request guard used for getting an IP address from a client.
+///
+/// The IP address is resolved by the `Resolver` in the managed state of Rocket, or by `Resolver::default()` if there is none. The resolution does not depend on the HTTP method, so CORS preflight (`OPTIONS`) requests are resolved like any other request.
+///
+/// Both `ClientAddr` and `&ClientAddr` can be used as a request guard. They resolve the IP address only once per request and then read it from `CachedClientAddr` (see `benches/guards.rs` for the cost of both), so either of them can be used in as many request guards as needed. `&ClientAddr` saves a copy.
 #[derive(Copy, Clone, Eq, PartialEq, Hash, PartialOrd, Ord)]
 pub struct ClientAddr {
     /// IP address from a client.
     pub ip: IpAddr,
 }
 
-fn is_local_ip(addr: &IpAddr) -> bool {
+/// Check whether an IP address is local, i.e. it never identifies a client on the Internet. It is a private, loopback, link-local, documentation, benchmarking or reserved address, an address of "this network" (`0.0.0.0/8`), or another block of the special-purpose address registries marked as local in `special_purpose`, or an IPv6 address which is not in the global scope. An IPv4-mapped IPv6 address (`::ffff:a.b.c.d`) is checked as it is, so it is never local; callers which want the rule of its IPv4 address pass the canonical IP address.
+pub(crate) fn is_local_ip(addr: &IpAddr) -> bool {
     match addr {
-        IpAddr::V4(addr) => {
-            let octets = addr.octets();
-
-            match octets {
-                // --- is_private ---
-                [10, ..] => true,
-                [172, b, ..] if (16..=31).contains(&b) => true,
-                [192, 168, ..] => true,
-                // --- is_loopback ---
-                [127, ..] => true,
-                // --- is_link_local ---
-                [169, 254, ..] => true,
-                // --- is_broadcast ---
-                [255, 255, 255, 255] => true,
-                // --- is_documentation ---
-                [192, 0, 2, _] => true,
-                [198, 51, 100, _] => true,
-                [203, 0, 113, _] => true,
-                // --- is_unspecified ---
-                [0, 0, 0, 0] => true,
-                _ => false,
-            }
+        IpAddr::V4(_) => special_purpose::is_local_special_purpose(addr),
+        IpAddr::V6(ipv6) => match ipv6_scope(ipv6) {
+            Ipv6Scope::Global => special_purpose::is_local_special_purpose(addr),
+            _ => true,
         },
-        IpAddr::V6(addr) => {
-            let segments = addr.segments();
-
-            let is_multicast = segments[0] & 0xFF00 == 0xFF00;
-
-            if is_multicast {
-                segments[0] & 0x000F != 14 // 14 means global
-            } else {
-                match segments {
-                    // --- is_loopback ---
-                    [0, 0, 0, 0, 0, 0, 0, 1] => true,
-                    // --- is_unspecified ---
-                    [0, 0, 0, 0, 0, 0, 0, 0] => true,
+    }
+}
+
+/// The scope of an IPv6 address.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Ipv6Scope {
+    /// The loopback address, `::1`.
+    Loopback,
+    /// The unspecified address, `::`.
+    Unspecified,
+    /// A unicast link-local address in `fe80::/10`, or a multicast address with the link-local scope.
+    LinkLocal,
+    /// A unicast site-local address in `fec0::/10` (deprecated), or a multicast address with the site-local scope.
+    SiteLocal,
+    /// A unique local address in `fc00::/7`.
+    UniqueLocal,
+    /// A multicast address with another non-global scope, such as the interface-local or the organization-local scope.
+    Multicast,
+    /// Any other address, including multicast addresses with the global scope.
+    Global,
+}
+
+/// Clear the second 16-bit segment of a unicast link-local address or an interface-local or link-local multicast address, where KAME-derived network stacks embed the scope ID.
+fn without_embedded_scope(addr: Ipv6Addr) -> Ipv6Addr {
+    let mut segments = addr.segments();
+
+    let is_scoped_multicast =
+        segments[0] & 0xFF00 == 0xFF00 && matches!(segments[0] & 0x000F, 1 | 2);
+
+    if segments[0] & 0xFFC0 == 0xFE80 || is_scoped_multicast {
+        segments[1] = 0;
+    }
+
+    Ipv6Addr::from(segments)
+}
+
+pub(crate) fn ipv6_scope(addr: &Ipv6Addr) -> Ipv6Scope {
+    let segments = addr.segments();
+
+    let is_multicast = segments[0] & 0xFF00 == 0xFF00;
+
+    if is_multicast {
+        match segments[0] & 0x000F {
+            2 => Ipv6Scope::LinkLocal,
+            5 => Ipv6Scope::SiteLocal,
+            14 => Ipv6Scope::Global, // 14 means global
+            _ => Ipv6Scope::Multicast,
+        }
+    } else {
+        match segments {
+            // --- is_loopback ---
+            [0, 0, 0, 0, 0, 0, 0, 1] => Ipv6Scope::Loopback,
+            // --- is_unspecified ---
+            [0, 0, 0, 0, 0, 0, 0, 0] => Ipv6Scope::Unspecified,
+            _ => {
+                match segments[0] & 0xFFC0 {
+                    // --- is_unicast_link_local ---
+                    0xFE80 => Ipv6Scope::LinkLocal,
+                    // --- is_unicast_site_local ---
+                    0xFEC0 => Ipv6Scope::SiteLocal,
                     _ => {
-                        match segments[0] & 0xFFC0 {
-                            // --- is_unicast_link_local ---
-                            0xFE80 => true,
-                            // --- is_unicast_site_local ---
-                            0xFEC0 => true,
-                            _ => {
-                                // --- is_unique_local ---
-                                if segments[0] & 0xFE00 == 0xFC00 {
-                                    true
-                                } else {
-                                    (segments[0] == 0x2001) && (segments[1] == 0xDB8)
-                                }
-                            },
+                        // --- is_unique_local ---
+                        if segments[0] & 0xFE00 == 0xFC00 {
+                            Ipv6Scope::UniqueLocal
+                        } else {
+                            Ipv6Scope::Global
                         }
                     },
                 }
-            }
-        },
+            },
+        }
     }
 }
 
-fn from_request(request: &Request<'_>) -> Option<ClientAddr> {
-    let remote_ip = if let Some(addr) = request.remote() {
-        let ip = addr.ip();
+/// Errors which can occur when resolving the IP address of a client. They are reported by `Resolver::try_resolve` and by the `ClientAddr` request guards as `Outcome::Error` with `Status::BadRequest` (or `Config::failure_status`). If no source simply holds a usable IP address, the request guards forward instead, unless `Config::fallback` is set.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum ClientAddrError {
+    /// A forwarding header is malformed, which is rejected by `Config::strict_parsing`.
+    MalformedHeader,
+    /// A header holds an IPv6 site-local address, which is rejected by `Config::reject_site_local_from_headers`.
+    SiteLocalAddress(Ipv6Addr),
+    /// The resolved address is local or private, which is rejected by `Config::reject_private_result`.
+    PrivateAddress(IpAddr),
+}
 
-        if !is_local_ip(&ip) {
-            return Some(ClientAddr { ip });
+impl Display for ClientAddrError {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientAddrError::MalformedHeader => f.write_str("malformed forwarding header"),
+            ClientAddrError::SiteLocalAddress(ip) => {
+                write!(f, "deprecated site-local address in a forwarding header: {ip}")
+            },
+            ClientAddrError::PrivateAddress(ip) => {
+                write!(f, "local or private address resolved as the client: {ip}")
+            },
         }
+    }
+}
 
-        Some(ip)
-    } else {
-        None
-    };
+impl Error for ClientAddrError {}
 
-    let Some(forwarded_for_ip) = request.headers().get("x-forwarded-for").next()
-    /* Only fetch the first one. */
-    else {
-        match request.real_ip() {
-            Some(real_ip) => return Some(ClientAddr { ip: real_ip }),
-            None => return remote_ip.map(|ip| ClientAddr { ip }),
-        };
-    };
+/// The `ClientAddr` of a request cached in the request-local state. It is populated by the first `ClientAddr` or `&ClientAddr` request guard or by `ClientAddrFairing`, so that the resolution runs only once per request.
+///
+/// Other fairings and request guards can fetch it by `CachedClientAddr::get_async(request)`, or by `CachedClientAddr::get(request)` where they cannot await. Once `NoHeaderTrust` has run, they get the address of the TCP peer instead, like the `ClientAddr` request guards.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct CachedClientAddr(Option<ClientAddr>);
+
+/// The error of the resolution cached along with `CachedClientAddr`.
+struct CachedClientAddrError(Option<ClientAddrError>);
 
-    let forwarded_for_ips = forwarded_for_ip.rsplit(',');
+/// The primary source and the trustworthiness of the address cached as `CachedClientAddr`, so that `TrustedClientAddr` does not resolve it again.
+struct CachedResolution(Option<Resolution>);
 
-    let mut last_ip = None;
+/// Whether `CachedClientAddr` has been populated, so that it can be checked without populating it.
+struct CachedClientAddrPopulated(AtomicBool);
 
-    for forwarded_for_ip in forwarded_for_ips {
-        let Ok(ip) = forwarded_for_ip.trim().parse::<IpAddr>() else { break };
+/// The `ClientAddr` resolved by `CachedClientAddr::get` without `Config::trust_checker` while it is set, cached apart from `CachedClientAddr` so that the request guards and `ClientAddrFairing` never see it.
+struct UncheckedClientAddr(Option<ClientAddr>);
 
-        last_ip = Some(ip);
+impl CachedClientAddr {
+    /// Get the resolved `ClientAddr`, or `None` if the resolution failed.
+    #[inline]
+    pub fn client_addr(&self) -> Option<ClientAddr> {
+        self.0
+    }
 
-        if !is_local_ip(&ip) {
-            break;
+    /// Resolve the `ClientAddr` of a request without touching the cache. `Config::trust_checker` is not used. See `CachedClientAddr::resolve_async`.
+    #[inline]
+    pub fn resolve(request: &Request<'_>) -> CachedClientAddr {
+        if NoHeaderTrust::is_disabled(request) {
+            return CachedClientAddr(NoHeaderTrust::peer_client_addr(request).copied());
         }
+
+        CachedClientAddr(Resolver::from_request(request).resolve(request))
     }
 
-    if let Some(ip) = last_ip {
-        return Some(ClientAddr { ip });
+    /// Resolve the `ClientAddr` of a request without touching the cache.
+    #[inline]
+    pub async fn resolve_async(request: &Request<'_>) -> CachedClientAddr {
+        if NoHeaderTrust::is_disabled(request) {
+            return CachedClientAddr(NoHeaderTrust::peer_client_addr(request).copied());
+        }
+
+        CachedClientAddr(Resolver::from_request(request).resolve_async(request).await)
     }
 
-    if let Some(real_ip) = request.real_ip() {
-        return Some(ClientAddr { ip: real_ip });
+    /// Get the cached `ClientAddr` of a request. If it has not been cached yet, resolve and cache it without using `Config::trust_checker`. See `CachedClientAddr::get_async`.
+    ///
+    /// If `Config::trust_checker` is set, the result of this resolution is cached separately and does not populate `CachedClientAddr`, so a later request guard or `CachedClientAddr::get_async` still awaits the checker.
+    #[inline]
+    pub fn get<'r>(request: &'r Request<'_>) -> Option<&'r ClientAddr> {
+        if NoHeaderTrust::is_disabled(request) {
+            return NoHeaderTrust::peer_client_addr(request);
+        }
+
+        let resolver = Resolver::from_request(request);
+
+        if resolver.config().trust_checker.is_some() && !CachedClientAddr::is_populated(request) {
+            return request
+                .local_cache(|| UncheckedClientAddr(resolver.resolve(request)))
+                .0
+                .as_ref();
+        }
+
+        request
+            .local_cache(|| {
+                let result = resolver.try_resolve_with_trust(request);
+
+                CachedClientAddr::from_result(request, result)
+            })
+            .0
+            .as_ref()
     }
 
-    remote_ip.map(|ip| ClientAddr { ip })
+    /// Get the cached `ClientAddr` of a request. If it has not been cached yet, resolve and cache it.
+    #[inline]
+    pub async fn get_async<'r>(request: &'r Request<'_>) -> Option<&'r ClientAddr> {
+        if NoHeaderTrust::is_disabled(request) {
+            return NoHeaderTrust::peer_client_addr(request);
+        }
+
+        request
+            .local_cache_async(async {
+                let result =
+                    Resolver::from_request(request).resolve_async_with_trust(request, false).await;
+
+                CachedClientAddr::from_result(request, result)
+            })
+            .await
+            .0
+            .as_ref()
+    }
+
+    /// Create the value to be cached from the result of a resolution. The error or the `Resolution`, if any, is cached separately.
+    #[inline]
+    pub(crate) fn from_result(
+        request: &Request<'_>,
+        result: Result<Option<Resolution>, ClientAddrError>,
+    ) -> CachedClientAddr {
+        request
+            .local_cache(|| CachedClientAddrPopulated(AtomicBool::new(false)))
+            .0
+            .store(true, Ordering::Relaxed);
+
+        match result {
+            Ok(resolution) => {
+                request.local_cache(|| CachedResolution(resolution));
+
+                CachedClientAddr(resolution.map(|resolution| resolution.client_addr))
+            },
+            Err(error) => {
+                request.local_cache(|| CachedClientAddrError(Some(error)));
+
+                CachedClientAddr(None)
+            },
+        }
+    }
+
+    /// Check whether `CachedClientAddr` has been populated for a request.
+    #[inline]
+    fn is_populated(request: &Request<'_>) -> bool {
+        request
+            .local_cache(|| CachedClientAddrPopulated(AtomicBool::new(false)))
+            .0
+            .load(Ordering::Relaxed)
+    }
+
+    /// Get the cached `Resolution` of a request, if the resolution has been run and succeeded.
+    #[inline]
+    pub(crate) fn get_resolution<'r>(request: &'r Request<'_>) -> Option<&'r Resolution> {
+        request.local_cache(|| CachedResolution(None)).0.as_ref()
+    }
+
+    /// Get the cached error of the resolution of a request, if the resolution has been run and failed with an error.
+    #[inline]
+    pub(crate) fn get_error<'r>(request: &'r Request<'_>) -> Option<&'r ClientAddrError> {
+        request.local_cache(|| CachedClientAddrError(None)).0.as_ref()
+    }
+}
+
+/// Get the outcome of the `ClientAddr` request guard for a request, so that a request guard of another crate can delegate to it.
+///
+/// It is the same as `request::Outcome<ClientAddr, ClientAddrError>`: a failed resolution is forwarded with `Status::BadRequest` (or `Config::failure_status`), and a header rejected by the configuration is an error with `Status::BadRequest` (or `Config::failure_status`). `NoHeaderTrust` and the cache are respected.
+///
+/// ```rust
+/// use rocket::request::{self, FromRequest, Request};
+/// use rocket_client_addr::{client_addr_outcome, ClientAddrError};
+///
+/// /// The key of a rate limiter, which is the `/24` or `/64` network of a client.
+/// pub struct RateLimitKey(pub String);
+///
+/// #[rocket::async_trait]
+/// impl<'r> FromRequest<'r> for RateLimitKey {
+///     type Error = ClientAddrError;
+///
+///     async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+///         client_addr_outcome(request).await.map(|client_addr| {
+///             let prefix = if client_addr.get_ipv4().is_some() { 24 } else { 64 };
+///
+///             RateLimitKey(client_addr.enclosing_net(prefix).to_string())
+///         })
+///     }
+/// }
+/// ```
+pub async fn client_addr_outcome(
+    request: &Request<'_>,
+) -> Outcome<ClientAddr, (Status, ClientAddrError), Status> {
+    let client_addr = if NoHeaderTrust::is_set(request) {
+        NoHeaderTrust::peer_client_addr(request).copied()
+    } else {
+        CachedClientAddr::get_async(request).await.copied()
+    };
+
+    to_outcome(request, client_addr)
+}
+
+/// Get a value derived from the `ClientAddr` of a request, which is computed by `derive` at most once per request and cached in the request-local state, keyed by its type `T`. It is for a request guard of another crate which needs the IP address of a client, so that several such request guards on one route share both the resolution and the derived value, e.g. a rate-limit key used by a throttling guard and by a logging guard.
+///
+/// `T` should be a public type defined by the crate of the request guards, such as a newtype, because the cache is shared by every user of the same type in a request. The outcome is the same as `client_addr_outcome`, and `derive` is not called if the resolution does not succeed.
+///
+/// See `examples/shared_guards.rs`, where a rate-limit key and an audit tag are built from the same derived network of a client.
+pub async fn client_addr_derived<'r, T: Send + Sync + 'static>(
+    request: &'r Request<'_>,
+    derive: impl FnOnce(&ClientAddr) -> T + Send,
+) -> Outcome<&'r T, (Status, ClientAddrError), Status> {
+    let derived: &'r Option<T> = request
+        .local_cache_async(async {
+            client_addr_outcome(request).await.succeeded().map(|client_addr| derive(&client_addr))
+        })
+        .await;
+
+    to_outcome(request, derived.as_ref())
+}
+
+#[inline]
+fn to_outcome<T>(
+    request: &Request<'_>,
+    client_addr: Option<T>,
+) -> request::Outcome<T, ClientAddrError> {
+    match client_addr {
+        Some(client_addr) => Outcome::Success(client_addr),
+        None => {
+            let status = Resolver::from_request(request)
+                .config()
+                .failure_status
+                .unwrap_or(Status::BadRequest);
+
+            match CachedClientAddr::get_error(request) {
+                Some(error) => Outcome::Error((status, error.clone())),
+                None => Outcome::Forward(status),
+            }
+        },
+    }
 }
 
 #[rocket::async_trait]
 impl<'r> FromRequest<'r> for ClientAddr {
-    type Error = ();
+    type Error = ClientAddrError;
 
+    #[inline]
     async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
-        match from_request(request) {
-            Some(client_addr) => Outcome::Success(client_addr),
-            None => Outcome::Forward(Status::BadRequest),
-        }
+        client_addr_outcome(request).await
     }
 }
 
 #[rocket::async_trait]
 impl<'r> FromRequest<'r> for &'r ClientAddr {
-    type Error = ();
+    type Error = ClientAddrError;
 
     async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
-        let cache: &Option<ClientAddr> = request.local_cache(|| from_request(request));
+        let client_addr = if NoHeaderTrust::is_set(request) {
+            NoHeaderTrust::peer_client_addr(request)
+        } else {
+            CachedClientAddr::get_async(request).await
+        };
 
-        match cache.as_ref() {
-            Some(client_addr) => Outcome::Success(client_addr),
-            None => Outcome::Forward(Status::BadRequest),
-        }
+        to_outcome(request, client_addr)
     }
 }
 
 impl ClientAddr {
+    /// Resolve the IP address of the client of a request with the `Resolver` in the managed state of Rocket, reusing the result cached by the request guard or `ClientAddrFairing` if there is one. Once `NoHeaderTrust` has run, it is the address of the TCP peer.
+    ///
+    /// It works anywhere a `&Request` is available, such as in an error catcher, where request guards are not run:
+    ///
+    /// ```rust
+    /// use rocket::{catch, Request};
+    /// use rocket_client_addr::ClientAddr;
+    ///
+    /// #[catch(404)]
+    /// async fn not_found(request: &Request<'_>) -> &'static str {
+    ///     if let Some(client_addr) = ClientAddr::resolve(request).await {
+    ///         println!("{client_addr} requested {}", request.uri());
+    ///     }
+    ///
+    ///     "Not Found"
+    /// }
+    /// ```
+    #[inline]
+    pub async fn resolve(request: &Request<'_>) -> Option<ClientAddr> {
+        CachedClientAddr::get_async(request).await.copied()
+    }
+
     /// Get an `Ipv4Addr` instance.
     pub fn get_ipv4(&self) -> Option<Ipv4Addr> {
         match &self.ip {
@@ -171,13 +424,342 @@ impl ClientAddr {
         }
     }
 
-    /// Get an IPv6 string.
+    /// Get an IPv6 string. It is in the lowercase form recommended by RFC 5952, no matter how the address was written in a header.
     pub fn get_ipv6_string(&self) -> String {
         match &self.ip {
             IpAddr::V4(ipv4) => ipv4.to_ipv6_mapped().to_string(),
             IpAddr::V6(ipv6) => ipv6.to_string(),
         }
     }
+
+    /// Get the canonical IP address, which is the IPv4 address if the IP address is an IPv4-mapped IPv6 address (`::ffff:a.b.c.d`), or the IP address itself otherwise.
+    #[inline]
+    pub fn canonical_ip(&self) -> IpAddr {
+        match &self.ip {
+            IpAddr::V4(_) => self.ip,
+            IpAddr::V6(ipv6) => match ipv6.to_ipv4_mapped() {
+                Some(ipv4) => IpAddr::V4(ipv4),
+                None => self.ip,
+            },
+        }
+    }
+
+    /// Get the IPv4 address embedded in the IP address if it is an IPv6 address with the NAT64 well-known prefix (`64:ff9b::/96`, RFC 6052), like `64:ff9b::5db8:d822` (`64:ff9b::93.184.216.34`), which is how an IPv4 client appears behind a stateless IPv4/IPv6 translator (e.g. SIIT-DC or 464XLAT). It is not applied by `ClientAddr::canonical_ip`, but by `Config::canonicalize_mapped`.
+    #[inline]
+    pub fn nat64_ipv4(&self) -> Option<Ipv4Addr> {
+        match &self.ip {
+            IpAddr::V6(ipv6) => match ipv6.octets() {
+                [0x00, 0x64, 0xFF, 0x9B, 0, 0, 0, 0, 0, 0, 0, 0, a, b, c, d] => {
+                    Some(Ipv4Addr::new(a, b, c, d))
+                },
+                _ => None,
+            },
+            IpAddr::V4(_) => None,
+        }
+    }
+
+    /// Get the IPv4 address of the client if it is effectively an IPv4 client, i.e. the IP address is one of the following:
+    ///
+    /// * An IPv4 address.
+    /// * An IPv4-mapped IPv6 address (`::ffff:a.b.c.d`), like `ClientAddr::canonical_ip`.
+    /// * An IPv6 address with the NAT64 well-known prefix (`64:ff9b::/96`), like `ClientAddr::nat64_ipv4`.
+    /// * A 6to4 address (`2002::/16`, RFC 3056), whose next 32 bits are the IPv4 address of the 6to4 site, like `2002:5db8:d822::1`.
+    /// * A Teredo address (`2001::/32`, RFC 4380), whose last 32 bits are the external IPv4 address of the Teredo client with every bit inverted, like `2001:0:4136:e378:8000:63bf:a247:27dd` (`93.184.216.34`).
+    ///
+    /// Unlike `ClientAddr::get_ipv4`, the deprecated IPv4-compatible IPv6 address (`::a.b.c.d`) is not unwrapped, because `::1` would become `0.0.0.1`. Other NAT64 prefixes cannot be recognized, because they are chosen by the network.
+    pub fn effective_ipv4(&self) -> Option<Ipv4Addr> {
+        let ipv6 = match self.canonical_ip() {
+            IpAddr::V4(ipv4) => return Some(ipv4),
+            IpAddr::V6(ipv6) => ipv6,
+        };
+
+        if let Some(ipv4) = self.nat64_ipv4() {
+            return Some(ipv4);
+        }
+
+        let o = ipv6.octets();
+
+        match [o[0], o[1], o[2], o[3]] {
+            [0x20, 0x02, ..] => Some(Ipv4Addr::new(o[2], o[3], o[4], o[5])),
+            [0x20, 0x01, 0x00, 0x00] => Some(Ipv4Addr::new(!o[12], !o[13], !o[14], !o[15])),
+            _ => None,
+        }
+    }
+
+    /// Get the string of the canonical IP address. An IPv6 address is in the lowercase form recommended by RFC 5952, so the string can be compared with other canonical strings directly.
+    #[inline]
+    pub fn to_canonical_string(&self) -> String {
+        self.canonical_ip().to_string()
+    }
+
+    /// Write the string of the canonical IP address (see `ClientAddr::to_canonical_string`) into `w` without allocating a `String`, e.g. into a buffer which is reused for every line of a log in a hot path (see `benches/canonical_string.rs`).
+    ///
+    /// ```rust
+    /// use rocket_client_addr::ClientAddr;
+    ///
+    /// let client_addr = ClientAddr {
+    ///     ip: "::ffff:93.184.216.34".parse().unwrap(),
+    /// };
+    ///
+    /// let mut line = String::with_capacity(64);
+    ///
+    /// line.push_str("client=");
+    /// client_addr.write_canonical(&mut line).unwrap();
+    ///
+    /// assert_eq!("client=93.184.216.34", line);
+    /// ```
+    #[inline]
+    pub fn write_canonical<W: fmt::Write + ?Sized>(&self, w: &mut W) -> fmt::Result {
+        write!(w, "{}", self.canonical_ip())
+    }
+
+    /// Get the `src` field of a CEF (Common Event Format) record for a SIEM, like `src=93.184.216.34`. The canonical IP address is used, and an IPv6 address is not enclosed in brackets, like `src=2606:4700::1111`.
+    #[inline]
+    pub fn to_cef_field(&self) -> String {
+        format!("src={}", self.canonical_ip())
+    }
+
+    /// Get a key scoped to the client, like `93.184.216.34:<key>` or `[2606:4700::1111]:<key>`, e.g. for an idempotency key which is unique per client rather than globally. The canonical IP address is used, so an IPv4 client and its IPv4-mapped IPv6 address get the same scope.
+    ///
+    /// An IPv6 address is enclosed in brackets, so the address ends at the first `:` (IPv4) or `]:` (IPv6) and two different pairs of a client and a key never get the same scoped key, even if `key` contains colons.
+    #[inline]
+    pub fn scoped_key(&self, key: &str) -> String {
+        match self.canonical_ip() {
+            IpAddr::V4(ip) => format!("{ip}:{key}"),
+            IpAddr::V6(ip) => format!("[{ip}]:{key}"),
+        }
+    }
+
+    /// Get the raw octets of the IP address.
+    #[inline]
+    pub fn octets(&self) -> Octets {
+        Octets::from(self.ip)
+    }
+
+    /// Get the 16 octets of the IP address for fixed-width storage. An IPv4 address is stored as its IPv4-mapped IPv6 address (`::ffff:a.b.c.d`).
+    #[inline]
+    pub fn to_bytes_16(&self) -> [u8; 16] {
+        self.get_ipv6().octets()
+    }
+
+    /// Get the IP address as a `u128` key for sharding, indexing or range comparisons. An IPv4 address `a.b.c.d` is mapped into the IPv6 space as its IPv4-mapped IPv6 address (`::ffff:a.b.c.d`), which is `0xFFFF_0000_0000 | u32::from(ipv4)`, so IPv4 addresses keep their order and occupy one contiguous range.
+    #[inline]
+    pub fn to_u128(&self) -> u128 {
+        u128::from(self.get_ipv6())
+    }
+
+    /// Restore a `ClientAddr` from a key created by `ClientAddr::to_u128`. If `was_v4` is `true` and `value` is an IPv4-mapped IPv6 address, the IPv4 address is restored. Otherwise, the IPv6 address is restored.
+    #[inline]
+    pub fn from_u128(value: u128, was_v4: bool) -> ClientAddr {
+        let ipv6 = Ipv6Addr::from(value);
+
+        let ip = match ipv6.to_ipv4_mapped() {
+            Some(ipv4) if was_v4 => IpAddr::V4(ipv4),
+            _ => IpAddr::V6(ipv6),
+        };
+
+        ClientAddr { ip }
+    }
+
+    /// Get the network with the given prefix length which contains the IP address, such as the `/24` or the `/48` of a client. It can be used directly as an entry of an `IpNetSet`, e.g. for banning a subnet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prefix_len` is greater than the bit length of the IP address.
+    #[inline]
+    pub fn enclosing_net(&self, prefix_len: u8) -> IpNet {
+        IpNet::new(self.ip, prefix_len)
+    }
+
+    /// Get the broadcast address of the IPv4 network with the given prefix length which contains the IP address, e.g. `192.168.1.255` for `192.168.1.20` and `24`, for LAN tooling like Wake-on-LAN. An IPv4-mapped IPv6 address is treated as its IPv4 address. A `prefix` greater than `32` is treated as `32`, whose broadcast address is the IP address itself.
+    ///
+    /// It returns `None` for an IPv6 address, because IPv6 has no broadcast.
+    #[inline]
+    pub fn broadcast_for(&self, prefix: u8) -> Option<Ipv4Addr> {
+        match self.canonical_ip() {
+            IpAddr::V4(ipv4) => Some(Ipv4Addr::from(u32::from(ipv4) | !mask_v4(prefix.min(32)))),
+            IpAddr::V6(_) => None,
+        }
+    }
+
+    /// Get the network with the given prefix length which contains the canonical IP address, as the canonical entry of a ban list. Unlike `ClientAddr::enclosing_net`, an IPv4-mapped IPv6 address is treated as its IPv4 address, so `prefix` is applied to the IPv4 address, and a `prefix` greater than the bit length of the address is treated as the bit length instead of panicking.
+    ///
+    /// ```rust
+    /// use rocket_client_addr::{ClientAddr, IpNetSet};
+    ///
+    /// let client_addr = ClientAddr { ip: "::ffff:93.184.216.34".parse().unwrap() };
+    ///
+    /// let bans: IpNetSet = [client_addr.to_net(24)].into_iter().collect();
+    ///
+    /// assert!(bans.contains(&"93.184.216.200".parse().unwrap()));
+    /// ```
+    #[inline]
+    pub fn to_net(&self, prefix: u8) -> IpNet {
+        match self.canonical_ip() {
+            IpAddr::V4(ipv4) => IpNet::new_v4(ipv4, prefix.min(32)),
+            IpAddr::V6(ipv6) => IpNet::new_v6(ipv6, prefix.min(128)),
+        }
+    }
+
+    /// Check whether the client is in the published IP ranges of a sender, e.g. for verifying that a webhook comes from its provider. An IPv4-mapped IPv6 address is treated as its IPv4 address.
+    ///
+    /// The check is only as strong as the resolution, so the webhook route should either receive requests directly with `NoHeaderTrust`, or be behind proxies which are all trusted, so that the address cannot be forged by a header.
+    ///
+    /// ```rust
+    /// use std::net::Ipv4Addr;
+    ///
+    /// use rocket::{http::Status, post};
+    /// use rocket_client_addr::{ClientAddr, IpNet, IpNetSet, NoHeaderTrust};
+    ///
+    /// static PROVIDER_RANGES: IpNetSet =
+    ///     IpNetSet::from_static(&[IpNet::new_v4(Ipv4Addr::new(192, 30, 252, 0), 22)]);
+    ///
+    /// #[post("/webhook")]
+    /// fn webhook(_no_header_trust: NoHeaderTrust, client_addr: &ClientAddr) -> Status {
+    ///     if !client_addr.matches_published(&PROVIDER_RANGES) {
+    ///         return Status::Forbidden;
+    ///     }
+    ///
+    ///     Status::NoContent
+    /// }
+    /// ```
+    #[inline]
+    pub fn matches_published(&self, ranges: &IpNetSet) -> bool {
+        ranges.contains(&self.ip)
+    }
+
+    /// Check whether the IP address is likely shared by many users behind a carrier-grade NAT, i.e. it is in the shared address space (`100.64.0.0/10`, RFC 6598) or the IPv4 service continuity prefix of DS-Lite (`192.0.0.0/29`, RFC 7335). An IPv4-mapped IPv6 address is treated as its IPv4 address.
+    ///
+    /// It is a heuristic for weighting abuse decisions, e.g. being lenient with rate limiting, not an authoritative answer: a public address can also be shared by a NAT which this crate cannot see. See also `ClientAddrInfo::is_likely_shared`, which takes the forwarded chain into account.
+    #[inline]
+    pub fn is_likely_shared(&self) -> bool {
+        match self.canonical_ip() {
+            IpAddr::V4(ipv4) => matches!(ipv4.octets(), [100, 64..=127, ..] | [192, 0, 0, 0..=7]),
+            IpAddr::V6(_) => false,
+        }
+    }
+
+    /// Check whether the IP address is in the private address space (`10.0.0.0/8`, `172.16.0.0/12` and `192.168.0.0/16`, RFC 1918) or the shared address space of carrier-grade NATs (`100.64.0.0/10`, RFC 6598), i.e. whether the client is behind a NAT or inside an internal network. An IPv4-mapped IPv6 address is treated as its IPv4 address, and it is always `false` for an IPv6 address.
+    ///
+    /// See `ClientAddr::is_likely_shared` for the shared address space alone.
+    #[inline]
+    pub fn is_nat_space(&self) -> bool {
+        match self.canonical_ip() {
+            IpAddr::V4(ipv4) => matches!(
+                ipv4.octets(),
+                [10, ..] | [172, 16..=31, ..] | [192, 168, ..] | [100, 64..=127, ..]
+            ),
+            IpAddr::V6(_) => false,
+        }
+    }
+
+    /// Check whether the IP address is in a block of the IANA IPv4 or IPv6 Special-Purpose Address Registry (RFC 6890), such as a private, shared (carrier-grade NAT), documentation, 6to4 or TEREDO address. An IPv4-mapped IPv6 address is always in the registry.
+    ///
+    /// Some of the blocks hold addresses which are routable on the Internet, so it is stricter than the check used for the TCP peer and the forwarding headers, which only treats the blocks that never identify a client on the Internet as local.
+    #[inline]
+    pub fn is_special_purpose(&self) -> bool {
+        special_purpose::is_special_purpose(&self.ip)
+    }
+
+    /// Check whether the IP address is in a documentation range (`192.0.2.0/24`, `198.51.100.0/24` and `203.0.113.0/24` of RFC 5737, `2001:db8::/32` of RFC 3849 and `3fff::/20` of RFC 9637), which is reserved for examples, e.g. for flagging example data which leaked into production logs. An IPv4-mapped IPv6 address is treated as its IPv4 address.
+    ///
+    /// These ranges are also local (see `Classification::Local`).
+    #[inline]
+    pub fn is_documentation(&self) -> bool {
+        special_purpose::is_documentation(&self.canonical_ip())
+    }
+
+    /// Look up the location of the client in a geolocation database, such as an IP2Location BIN file or a MaxMind DB (see `GeoDb`).
+    ///
+    /// An IPv4-mapped IPv6 address is looked up as its IPv4 address. A local address (a private, loopback, link-local or documentation address, etc.) is never looked up and returns `None`.
+    #[inline]
+    pub fn geo<D: GeoDb + ?Sized>(&self, db: &D) -> Option<GeoInfo> {
+        let ip = self.canonical_ip();
+
+        if is_local_ip(&ip) {
+            return None;
+        }
+
+        db.lookup(ip)
+    }
+
+    /// Check whether the IP addresses of two clients are in the same network with the given prefix length, e.g. the same `/64`, so that an IPv6 client which rotates the low 64 bits of its address (privacy extensions) can still be recognized.
+    ///
+    /// An IPv4 address is compared with the prefix length applied to the IPv4 address itself, so `prefix` should be at most `32` for IPv4 clients. An IPv4-mapped IPv6 address is treated as its IPv4 address. An IPv4 address and an IPv6 address are never in the same network. A `prefix` greater than the bit length of the addresses is treated as the bit length.
+    pub fn same_prefix(&self, other: &ClientAddr, prefix: u8) -> bool {
+        match (self.canonical_ip(), other.canonical_ip()) {
+            (IpAddr::V4(ip), IpAddr::V4(other_ip)) => {
+                IpNet::new_v4(ip, prefix.min(32)).contains(&IpAddr::V4(other_ip))
+            },
+            (IpAddr::V6(ip), IpAddr::V6(other_ip)) => {
+                IpNet::new_v6(ip, prefix.min(128)).contains(&IpAddr::V6(other_ip))
+            },
+            _ => false,
+        }
+    }
+
+    /// Check whether the IP address is the same as `other`, ignoring the scope of a link-local address, e.g. for matching a client on a link-local network against an address from another source.
+    ///
+    /// `Ipv6Addr` has no zone identifier, and the one in a forwarding header (`fe80::1%eth0`) is already stripped when it is parsed, so two link-local addresses from different interfaces are equal by `==`. However, some systems (KAME-derived network stacks, such as the BSDs) embed the scope ID of a link-local address in its second 16-bit segment (`fe80:2::1` for `fe80::1%2`), which is ignored here for a unicast link-local address (`fe80::/10`) and a multicast address with the interface-local or the link-local scope. An IPv4-mapped IPv6 address is treated as its IPv4 address.
+    pub fn eq_ignoring_scope(&self, other: &IpAddr) -> bool {
+        match (self.canonical_ip(), ClientAddr { ip: *other }.canonical_ip()) {
+            (IpAddr::V6(ip), IpAddr::V6(other_ip)) => {
+                without_embedded_scope(ip) == without_embedded_scope(other_ip)
+            },
+            (ip, other_ip) => ip == other_ip,
+        }
+    }
+
+    /// Get the scope of the IP address if it is an IPv6 address.
+    #[inline]
+    pub fn ipv6_scope(&self) -> Option<Ipv6Scope> {
+        match &self.ip {
+            IpAddr::V4(_) => None,
+            IpAddr::V6(ipv6) => Some(ipv6_scope(ipv6)),
+        }
+    }
+
+    /// Get a salted hash (HMAC-SHA-256, keyed by `salt`) of the IP address, which can be used for correlating requests without retaining the address. An IPv4 address and its IPv4-mapped IPv6 address produce the same hash.
+    ///
+    /// The salt must be kept secret and rotated according to your retention policy, otherwise the hash can be reversed by hashing every possible address.
+    #[cfg(feature = "salted-hash")]
+    pub fn salted_hash(&self, salt: &[u8]) -> [u8; 32] {
+        use hmac::{Hmac, Mac};
+        use sha2::Sha256;
+
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(salt).expect("HMAC accepts keys of any length");
+
+        mac.update(&self.get_ipv6().octets());
+
+        mac.finalize().into_bytes().into()
+    }
+}
+
+/// Serialize the IP address transparently, e.g. as a string like `"93.184.216.34"` in a human-readable format, so a `ClientAddr` can be put into a template context (e.g. `context! { client_ip: client_addr }` of `rocket_dyn_templates`) or a JSON response directly:
+///
+/// ```rust
+/// use rocket_client_addr::ClientAddr;
+///
+/// let client_addr = ClientAddr { ip: "93.184.216.34".parse().unwrap() };
+///
+/// assert_eq!(r#""93.184.216.34""#, serde_json::to_string(&client_addr).unwrap());
+/// assert_eq!(client_addr, serde_json::from_str(r#""93.184.216.34""#).unwrap());
+/// ```
+#[cfg(feature = "serde")]
+impl serde::Serialize for ClientAddr {
+    #[inline]
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.ip.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ClientAddr {
+    #[inline]
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(ClientAddr { ip: IpAddr::deserialize(deserializer)? })
+    }
 }
 
 impl std::fmt::Debug for ClientAddr {
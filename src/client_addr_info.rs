@@ -0,0 +1,111 @@
+use std::{collections::HashSet, net::IpAddr};
+
+use rocket::{
+    outcome::Outcome,
+    request::{self, FromRequest, Request},
+};
+
+use crate::{resolver::forwarded_chain, CachedClientAddr, ClientAddr, NoHeaderTrust, Resolver};
+
+/// The request guard used for getting diagnostic information about the resolution of `ClientAddr`. It never fails.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct ClientAddrInfo {
+    /// The resolved IP address of the client, the same as the one of the `&ClientAddr` request guard, i.e. the address of the TCP peer after `NoHeaderTrust`.
+    pub client_addr: Option<ClientAddr>,
+    /// The number of entries in the forwarded chain (the first `X-Forwarded-For` header, or the `Forwarded` headers if there is no `X-Forwarded-For` header), including invalid ones. Consecutive identical entries are counted once if `Config::collapse_duplicate_hops` is enabled.
+    pub hops:        usize,
+    /// The number of intermediaries listed in the `Via` headers, or `None` if there is no `Via` header.
+    ///
+    /// It is advisory and never used for selecting the IP address of a client. Since most proxies add an entry to `Via` as well as to the forwarded chain, a mismatch between `via_hops` and `hops` (e.g. `Via` shows 3 hops but `X-Forwarded-For` shows 1) can reveal a misconfigured or bypassed proxy. Note that some proxies do not add `Via` at all.
+    pub via_hops:    Option<usize>,
+}
+
+impl ClientAddrInfo {
+    /// Get the entries of the forwarded chain of a request counted by `hops`, from the leftmost (the farthest) to the rightmost (the nearest). An entry which is not an address is `None`.
+    pub fn chain(request: &Request<'_>) -> Vec<Option<IpAddr>> {
+        match forwarded_chain(request, false) {
+            Ok(mut chain) => {
+                if Resolver::from_request(request).config().collapse_duplicate_hops {
+                    chain.dedup();
+                }
+
+                chain
+            },
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Count the distinct IP addresses in the forwarded chain of a request (see `ClientAddrInfo::chain`). Entries which are not addresses are not counted.
+    ///
+    /// A chain padded with the same address, like `X-Forwarded-For: 1.1.1.1, 1.1.1.1, 1.1.1.1`, has 3 `hops` but only 1 distinct hop, which can reveal a client spoofing its chain.
+    pub fn distinct_hops(request: &Request<'_>) -> usize {
+        ClientAddrInfo::chain(request).iter().flatten().collect::<HashSet<_>>().len()
+    }
+
+    /// Check whether the client is likely shared by many users, i.e. `ClientAddr::is_likely_shared`, or there is more than one entry in the forwarded chain, which may mean the client is behind a proxy of its own, e.g. a corporate proxy.
+    ///
+    /// It is a heuristic, not an authoritative answer. Note that the proxies of your own deployment count as entries of the forwarded chain, so it is meaningful only when their number is known.
+    #[inline]
+    pub fn is_likely_shared(&self) -> bool {
+        self.client_addr.map(|client_addr| client_addr.is_likely_shared()).unwrap_or(false)
+            || self.hops > 1
+    }
+}
+
+/// Count the entries of the `Via` headers of a request, like `1.0 fred, 1.1 p.example.net (Apache/1.1)`. A comma in a comment is not a separator.
+fn count_via_hops(request: &Request<'_>) -> Option<usize> {
+    let mut values = request.headers().get("via").peekable();
+
+    values.peek()?;
+
+    let mut hops = 0;
+
+    for value in values {
+        let mut depth = 0usize;
+        let mut is_empty = true;
+
+        for c in value.chars() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth = depth.saturating_sub(1),
+                ',' if depth == 0 => {
+                    if !is_empty {
+                        hops += 1;
+                    }
+
+                    is_empty = true;
+
+                    continue;
+                },
+                _ => (),
+            }
+
+            if depth == 0 && !c.is_whitespace() {
+                is_empty = false;
+            }
+        }
+
+        if !is_empty {
+            hops += 1;
+        }
+    }
+
+    Some(hops)
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ClientAddrInfo {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        Outcome::Success(ClientAddrInfo {
+            client_addr: if NoHeaderTrust::is_set(request) {
+                NoHeaderTrust::peer_client_addr(request).copied()
+            } else {
+                CachedClientAddr::get_async(request).await.copied()
+            },
+            hops: ClientAddrInfo::chain(request).len(),
+            via_hops: count_via_hops(request),
+        })
+    }
+}
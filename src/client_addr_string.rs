@@ -0,0 +1,19 @@
+use rocket::request::{self, FromRequest, Request};
+
+use crate::{ClientAddr, ClientAddrError};
+
+/// The request guard used for getting the IP address of a client as a string, e.g. for logging. It is resolved like `&ClientAddr` and formatted by `ClientAddr::to_canonical_string`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct ClientAddrString(pub String);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ClientAddrString {
+    type Error = ClientAddrError;
+
+    #[inline]
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        <&ClientAddr>::from_request(request)
+            .await
+            .map(|client_addr| ClientAddrString(client_addr.to_canonical_string()))
+    }
+}
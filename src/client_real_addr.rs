@@ -4,37 +4,39 @@ use rocket::{
     http::Status,
     outcome::Outcome,
     request::{self, FromRequest, Request},
+    tokio::sync::OnceCell,
 };
 
+use crate::{ClientAddr, Config, Resolver, Strategy};
+
 /// The request guard used for getting an IP address from a client.
+///
+/// It is resolved in the same order as `ClientAddr` (see `Resolver`), but every TCP peer is trusted and the leftmost address of the forwarded chain is used, so any client can forge it. The `Resolver` in the managed state of Rocket is not used.
 #[derive(Debug, Clone)]
 pub struct ClientRealAddr {
     /// IP address from a client.
     pub ip: IpAddr,
 }
 
-fn from_request(request: &Request<'_>) -> Option<ClientRealAddr> {
-    if let Some(ip) = request.real_ip() {
-        return Some(ClientRealAddr { ip });
-    }
-
-    let Some(forwarded_for_ip) = request.headers().get("x-forwarded-for").next()
-    /* Only fetch the first one. */
-    else {
-        return request.remote().map(|addr| ClientRealAddr { ip: addr.ip() });
-    };
-
-    let Some(forwarded_for_ip) = forwarded_for_ip.split(',').next()
-    /* Only fetch the first one. */
-    else {
-        return request.remote().map(|addr| ClientRealAddr { ip: addr.ip() });
-    };
-
-    if let Ok(ip) = forwarded_for_ip.trim().parse::<IpAddr>() {
-        return Some(ClientRealAddr { ip });
-    }
-
-    request.remote().map(|addr| ClientRealAddr { ip: addr.ip() })
+/// The `Resolver` of `ClientRealAddr`, which is built once.
+static RESOLVER: OnceCell<Resolver> = OnceCell::const_new();
+
+async fn from_request(request: &Request<'_>) -> Option<ClientRealAddr> {
+    let resolver = RESOLVER
+        .get_or_init(|| async {
+            Resolver::new(
+                Config {
+                    strategy: Strategy::Leftmost {
+                        verify_trusted_suffix: false,
+                    },
+                    ..Config::default()
+                }
+                .trusted_if(|_| true),
+            )
+        })
+        .await;
+
+    resolver.resolve(request).map(|client_addr| ClientRealAddr { ip: client_addr.ip })
 }
 
 #[rocket::async_trait]
@@ -42,7 +44,7 @@ impl<'r> FromRequest<'r> for ClientRealAddr {
     type Error = ();
 
     async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
-        match from_request(request) {
+        match from_request(request).await {
             Some(client_addr) => Outcome::Success(client_addr),
             None => Outcome::Forward(Status::BadRequest),
         }
@@ -54,7 +56,8 @@ impl<'r> FromRequest<'r> for &'r ClientRealAddr {
     type Error = ();
 
     async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
-        let cache: &Option<ClientRealAddr> = request.local_cache(|| from_request(request));
+        let cache: &Option<ClientRealAddr> =
+            request.local_cache_async(async { from_request(request).await }).await;
 
         match cache.as_ref() {
             Some(client_addr) => Outcome::Success(client_addr),
@@ -96,3 +99,19 @@ impl ClientRealAddr {
         }
     }
 }
+
+impl From<ClientAddr> for ClientRealAddr {
+    /// Transfer the IP address without running the resolution again.
+    #[inline]
+    fn from(client_addr: ClientAddr) -> Self {
+        ClientRealAddr { ip: client_addr.ip }
+    }
+}
+
+impl From<ClientRealAddr> for ClientAddr {
+    /// Transfer the IP address without running the resolution again, so it is still the address which any client can forge, not one resolved by the `Resolver`.
+    #[inline]
+    fn from(client_addr: ClientRealAddr) -> Self {
+        ClientAddr { ip: client_addr.ip }
+    }
+}
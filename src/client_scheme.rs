@@ -0,0 +1,100 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    net::IpAddr,
+};
+
+use rocket::request::Request;
+
+use crate::{
+    resolver::{is_trusted_hop, parse_ip_token, rightmost_untrusted_index},
+    ParsedForwarded, Resolver,
+};
+
+/// The scheme used by a client.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Scheme {
+    Http,
+    Https,
+}
+
+impl Scheme {
+    /// Parse a scheme case-insensitively. Other schemes are not supported.
+    #[inline]
+    fn parse(s: &str) -> Option<Scheme> {
+        let s = s.trim();
+
+        if s.eq_ignore_ascii_case("http") {
+            Some(Scheme::Http)
+        } else if s.eq_ignore_ascii_case("https") {
+            Some(Scheme::Https)
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for Scheme {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Scheme::Http => f.write_str("http"),
+            Scheme::Https => f.write_str("https"),
+        }
+    }
+}
+
+/// Get the scheme used by the client of a request, e.g. to know whether the original request was HTTPS even though a proxy terminates TLS and connects to Rocket over HTTP.
+///
+/// It is read from the first `X-Forwarded-Proto` header, or from the `proto` parameter of the `Forwarded` headers if there is no `X-Forwarded-Proto` header. A proxy which appends to these headers keeps the values sent by the client, so the entry is selected like `Strategy::RightmostUntrusted` selects an address: it is the entry added by the nearest trusted proxy, i.e. the one which received the request from the rightmost address which is neither a local address nor a trusted proxy (`Config::trusted_proxies`). For `Forwarded`, this is the element of that address, or the rightmost element if it has no address. For `X-Forwarded-Proto`, which has no addresses, it is the entry at the position of that address in the first `X-Forwarded-For` header if both have the same number of entries, or the rightmost entry, which is added by the TCP peer, otherwise.
+///
+/// The same trust considerations as the IP address apply, since any client can send these headers. They are read only if the TCP peer is a local address or trusted by `Config::trusted_proxies` or `Config::trusted_if` of the `Resolver` in the managed state of Rocket (`Config::trust_checker` is not used), or if the TCP peer is unknown. If `Config::always_use_headers` is enabled, the TCP peer is not checked, so any client which connects to Rocket directly can forge the scheme.
+///
+/// It returns `None` if the headers are absent, not trusted, or the selected entry holds no scheme or an unsupported one. In that case, the client is connected to Rocket directly, and the scheme is HTTPS if `rocket::Config::tls_enabled()` is `true`.
+pub fn client_scheme(request: &Request<'_>) -> Option<Scheme> {
+    let resolver = Resolver::from_request(request);
+
+    if !resolver.is_trusted_peer(request) {
+        return None;
+    }
+
+    let is_trusted = |ip: &IpAddr| is_trusted_hop(resolver.config(), ip);
+
+    match request.headers().get("x-forwarded-proto").find(|v| !v.trim().is_empty()) {
+        Some(value) => {
+            let protos: Vec<&str> =
+                value.split(',').map(str::trim).filter(|v| !v.is_empty()).collect();
+
+            let chain: Vec<Option<IpAddr>> = request
+                .headers()
+                .get("x-forwarded-for")
+                .next()
+                .into_iter()
+                .flat_map(|value| value.split(','))
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(parse_ip_token)
+                .collect();
+
+            let index = if chain.len() == protos.len() {
+                rightmost_untrusted_index(&chain, is_trusted)
+            } else {
+                protos.len().checked_sub(1)
+            };
+
+            index.and_then(|i| Scheme::parse(protos[i]))
+        },
+        None => {
+            let entries = ParsedForwarded::from_headers(request).entries;
+
+            let chain: Vec<Option<IpAddr>> = entries
+                .iter()
+                .map(|entry| entry.for_.as_ref().and_then(|for_| for_.addr()))
+                .collect();
+
+            rightmost_untrusted_index(&chain, is_trusted)
+                .or_else(|| entries.len().checked_sub(1))
+                .and_then(|i| entries[i].proto.as_deref())
+                .and_then(Scheme::parse)
+        },
+    }
+}
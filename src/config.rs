@@ -0,0 +1,217 @@
+use std::{
+    fmt::{self, Debug, Formatter},
+    net::IpAddr,
+    sync::Arc,
+};
+
+use rocket::{http::Status, request::Request};
+
+use crate::{CdnHeader, CdnLayer, CustomSource, IpNetSet, SourceKind};
+
+/// The configuration of a `Resolver`.
+///
+/// Convert it into a `Resolver` and put that into the managed state of Rocket (`rocket.manage(Resolver::from(config))`) to change the default behavior, or use `ResolverBuilder` instead.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    /// The sources of the IP address of a client, in order. `None` means `SourceKind::DEFAULT_ORDER`, which is documented on `Resolver`. `None` by default.
+    pub sources:                         Option<Vec<SourceKind>>,
+    /// CDN headers to trust. They are checked in the given order, before the TCP peer and any other forwarding header, and the first one which holds a valid IP address is used. If `Config::trusted_proxies`, `Config::trusted_if` or `Config::trust_checker` is set, they are ignored when the TCP peer is known but not trusted. Empty by default.
+    pub trusted_cdn_headers:             Vec<CdnHeader>,
+    /// Whether to accept a comma-separated list in a CDN header, which can be caused by misconfiguration or chained CDNs. The first (leftmost) entry is used. Otherwise, such a header is ignored. `false` by default.
+    pub cdn_header_tolerate_list:        bool,
+    /// CDN layers in front of the trusted proxies, whose headers are trusted only when the address selected from the TCP peer or the forwarded chain is in their ranges. See `CdnLayer`. Empty by default.
+    pub cdn_layers:                      Vec<CdnLayer>,
+    /// Proxies to trust in addition to local addresses. A trusted TCP peer or `X-Forwarded-For` entry is skipped so that the address of the client behind it can be used. Empty by default.
+    pub trusted_proxies:                 IpNetSet,
+    /// A custom source which is consulted before the `X-Forwarded-For` header when the TCP peer is trusted. It is lower-trust, see `CustomSource`. `None` by default.
+    pub custom_source:                   Option<CustomSource>,
+    /// How to select the address of the client from the `X-Forwarded-For` header. `Strategy::RightmostUntrusted` by default.
+    pub strategy:                        Strategy,
+    /// The family to prefer when the forwarded chain has addresses of both families, e.g. a dual-stack client whose IPv4 address and global IPv6 address are both in the chain. If the address selected by `Config::strategy` is of the other family, the nearest address of the preferred family which is globally routable (not local, see `Classification::Local`) at or to the left of it is used instead, if any. The addresses to the left of the selected one are sent by the client or its proxies, so it suits something like analytics rather than security decisions. `None` by default.
+    pub prefer_family:                   Option<AddrFamily>,
+    /// Whether to fall back to `Strategy::RightmostUntrusted` when the suffix verification of `Strategy::Leftmost` fails. Otherwise, the resolution fails. `false` by default.
+    pub untrusted_suffix_fallback:       bool,
+    /// Whether to convert the resolved address to an IPv4 address if it is an IPv4-mapped IPv6 address (`::ffff:a.b.c.d`), which is common when Rocket listens on a dual-stack socket, or an IPv4-embedded IPv6 address with the NAT64 well-known prefix (`64:ff9b::a.b.c.d`, RFC 6052), which is how IPv4 clients appear behind a stateless IPv4/IPv6 translator (see `ClientAddr::nat64_ipv4`). `false` by default.
+    pub canonicalize_mapped:             bool,
+    /// Whether to reject an `X-Forwarded-For` header which has an entry that is not a valid IP address, or a `Forwarded` header which is syntactically malformed or has an invalid `for` or `by` parameter, which may be a sign of tampering. The resolution fails with `ClientAddrError::MalformedHeader` then. Otherwise, the walk of the chain stops at such an entry. An empty or whitespace-only header is treated as absent either way. `false` by default.
+    pub strict_parsing:                  bool,
+    /// Whether to collapse consecutive identical entries of the forwarded chain (`X-Forwarded-For` or `Forwarded`) into one before selecting the address of the client, because some misbehaving proxies append the same address twice. It also affects `ClientAddrInfo::hops`. `false` by default.
+    pub collapse_duplicate_hops:         bool,
+    /// Whether to discard an IPv6 link-local (`fe80::/10`) or unique local (`fc00::/7`) address taken from a header (a CDN header, `Config::custom_source`, `X-Forwarded-For`, `Forwarded` or `X-Real-IP`) and fall back to the next source, because such an address cannot have crossed the routed boundary between the client and a proxy. `false` by default.
+    pub reject_nonroutable_from_headers: bool,
+    /// Whether to fail the resolution with `ClientAddrError::SiteLocalAddress` when an address taken from a header is an IPv6 site-local address (`fec0::/10`), which is deprecated by RFC 3879, so it likely means a misconfigured network rather than a client. It is checked before `Config::reject_nonroutable_from_headers`, which only falls back to the next source. `false` by default.
+    pub reject_site_local_from_headers:  bool,
+    /// Whether to fail the resolution with `ClientAddrError::PrivateAddress` when the resolved address is local (a private, loopback, link-local or documentation address, etc., see `Classification::Local`), after every source and `Config::fallback` have been tried, because an API which serves only clients on the Internet never sees such an address unless the trusted proxies are misconfigured. An IPv4-mapped IPv6 address is checked as its IPv4 address. `NoHeaderTrust` is not affected. `false` by default.
+    pub reject_private_result:           bool,
+    /// Whether to use the TCP peer directly and ignore every header when Rocket itself terminates TLS (`rocket::Config::tls_enabled()`). In that case, the TCP peer is the end of the TLS connection, so it is more trustworthy than any header. Disable it if a proxy re-encrypts requests to Rocket. It requires the `tls` feature, which enables the `tls` feature of Rocket. `false` by default.
+    #[cfg(feature = "tls")]
+    pub prefer_tls_peer:                 bool,
+    /// Pairs of a header name and a sentinel value which make the resolver ignore every header and use the TCP peer when the header with that name equals the value (trimmed, case-insensitively, with multiple lines of the header combined by `, `, so a sentinel line sent by the client is not honored once a proxy appends its own line), e.g. `("X-Forwarded-For", "unknown")` sent by a health check probe or an intentionally anonymized request. They are checked before any header is parsed, so `Config::strict_parsing` does not reject such a header. Empty by default.
+    pub remote_sentinels:                Vec<(String, String)>,
+    /// How to use the header configured by Rocket's `ip_header` (`X-Real-IP` by default, see `Request::real_ip`). It is read from the configuration of Rocket, so it may not be `X-Real-IP`, and it is not used at all if `ip_header` is disabled. `RealIpMode::Fallback` by default.
+    pub real_ip_mode:                    RealIpMode,
+    /// An address which is used as the address of the client when no source holds a usable IP address, e.g. a sentinel like `0.0.0.0` or the public IP address of the server for internal tools, so that the request guards succeed instead of forwarding. A header rejected by `Config::strict_parsing` is still an error. `None` by default.
+    pub fallback:                        Option<IpAddr>,
+    /// The status with which the `ClientAddr` request guards forward or fail when the resolution fails, e.g. `Status::Forbidden`. `None` means `Status::BadRequest`. `None` by default.
+    pub failure_status:                  Option<Status>,
+    /// Whether to use the forwarding headers even if the TCP peer is neither a local address nor a trusted proxy, as if every TCP peer were trusted, e.g. behind a proxy with a public address which changes too often to be listed in `Config::trusted_proxies`. Any client which can connect to Rocket directly can forge its address then. With the `tracing` feature, a hint is emitted once when a public TCP peer sends a forwarding header which is ignored because this is disabled. `false` by default.
+    pub always_use_headers:              bool,
+    /// A predicate which decides whether the TCP peer is trusted, in addition to local addresses and `trusted_proxies`. It can be set by `Config::trusted_if`. `None` by default.
+    pub trusted_if:                      Option<TrustPredicate>,
+    /// An asynchronous checker which decides whether the TCP peer is trusted, in addition to local addresses and `trusted_proxies`. It is awaited only by asynchronous resolution (the request guards, `ClientAddrFairing` and `Resolver::resolve_async`). `None` by default.
+    pub trust_checker:                   Option<Arc<dyn TrustChecker>>,
+}
+
+/// Strategies for selecting the address of the client from a forwarded chain like `X-Forwarded-For: client, proxy1, proxy2`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub enum Strategy {
+    /// Walk the chain from right to left, skipping local addresses and trusted proxies, and select the first remaining address. Since every proxy appends the address of its peer, this is the closest address which cannot be forged by the client.
+    #[default]
+    RightmostUntrusted,
+    /// Select the leftmost address, which is the original address claimed by the client.
+    ///
+    /// If `verify_trusted_suffix` is `true`, every other address in the chain must be a local address or a trusted proxy, otherwise the chain is considered tampered with. See `Config::untrusted_suffix_fallback`.
+    ///
+    /// If `verify_trusted_suffix` is `false`, any client can forge the selected address.
+    Leftmost {
+        verify_trusted_suffix: bool,
+    },
+    /// Select the address which is `skip` entries away from the right end of the chain, regardless of whether any address is trusted. It suits a load balancer which always appends a fixed number of entries, including ones which are not addresses of its peers (see `Preset`).
+    ///
+    /// If the chain is too short, the next source is tried. Any client can forge the selected address unless every request passes through such a load balancer.
+    Rightmost {
+        skip: usize,
+    },
+    /// Walk the chain from left to right and select the first address which is not local (see `Classification::Local`), skipping invalid entries. If there is none, the next source is tried. Each entry is checked as it is, so an IPv4-mapped IPv6 entry like `::ffff:10.0.0.1` is selected even though it is classified as `Classification::Local`.
+    ///
+    /// It is weaker than `Strategy::RightmostUntrusted`, because any client can prepend a fake public address, so it is acceptable only when the trusted proxies are unknown and the address is used for something like analytics rather than security decisions. Unlike `Strategy::Leftmost`, it skips the private addresses of the networks behind which the client sits, e.g. `X-Forwarded-For: 10.0.0.1, 8.8.8.8, 1.1.1.1` selects `8.8.8.8`.
+    FirstPublic,
+}
+
+/// How a `Resolver` uses the header configured by Rocket's `ip_header` (`Request::real_ip`), so that it is not a second source of truth which disagrees with the sources of this crate.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub enum RealIpMode {
+    /// Use it after the forwarded chain, before the TCP peer, when the TCP peer is trusted.
+    #[default]
+    Fallback,
+    /// Defer to Rocket entirely: use it, or the TCP peer if it is absent, like `Request::client_ip`. Every other source and the trust of the TCP peer are ignored, so any client can forge the address unless every request passes through a proxy which overwrites the header.
+    Defer,
+    /// Never use it, so that only the sources configured in this crate are used.
+    Ignore,
+}
+
+/// The family of an IP address. An IPv4-mapped IPv6 address (`::ffff:a.b.c.d`) belongs to IPv4.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum AddrFamily {
+    V4,
+    V6,
+}
+
+impl AddrFamily {
+    /// Get the family of an IP address.
+    #[inline]
+    pub fn of(ip: &IpAddr) -> AddrFamily {
+        match ip {
+            IpAddr::V4(_) => AddrFamily::V4,
+            IpAddr::V6(ipv6) if ipv6.to_ipv4_mapped().is_some() => AddrFamily::V4,
+            IpAddr::V6(_) => AddrFamily::V6,
+        }
+    }
+}
+
+/// An asynchronous check of whether the TCP peer is a trusted proxy, e.g. against a database whose content changes at runtime.
+///
+/// It is awaited once for every request whose TCP peer is neither a local address nor in `Config::trusted_proxies`, so an implementation should cache its answers instead of querying a database every time.
+///
+/// ```rust
+/// use std::{collections::HashSet, net::IpAddr, sync::Arc};
+///
+/// use rocket::tokio::sync::RwLock;
+/// use rocket_client_addr::{Resolver, TrustChecker};
+///
+/// struct CachedProxyList {
+///     // refreshed from the database periodically
+///     proxies: RwLock<HashSet<IpAddr>>,
+/// }
+///
+/// #[rocket::async_trait]
+/// impl TrustChecker for CachedProxyList {
+///     async fn is_trusted(&self, ip: IpAddr) -> bool {
+///         self.proxies.read().await.contains(&ip)
+///     }
+/// }
+///
+/// let checker = CachedProxyList {
+///     proxies: RwLock::new(HashSet::new())
+/// };
+///
+/// let resolver = Resolver::builder().trust_checker(Arc::new(checker)).build();
+/// ```
+#[rocket::async_trait]
+pub trait TrustChecker: Send + Sync + 'static {
+    /// Check whether the TCP peer whose IP address is `ip` is trusted.
+    async fn is_trusted(&self, ip: IpAddr) -> bool;
+}
+
+impl Debug for dyn TrustChecker {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("TrustChecker")
+    }
+}
+
+/// A predicate which decides whether the TCP peer of a request is a trusted proxy.
+#[derive(Clone)]
+pub struct TrustPredicate(Arc<dyn Fn(&Request<'_>) -> bool + Send + Sync>);
+
+impl TrustPredicate {
+    /// Create a predicate.
+    #[inline]
+    pub fn new<F: Fn(&Request<'_>) -> bool + Send + Sync + 'static>(
+        predicate: F,
+    ) -> TrustPredicate {
+        TrustPredicate(Arc::new(predicate))
+    }
+
+    /// Check whether the TCP peer of `request` is trusted.
+    #[inline]
+    pub fn test(&self, request: &Request<'_>) -> bool {
+        (self.0)(request)
+    }
+}
+
+impl Debug for TrustPredicate {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("TrustPredicate")
+    }
+}
+
+impl Config {
+    /// Trust the TCP peer (and therefore the forwarding headers it sends) when `predicate` returns `true`. This generalizes `trusted_proxies` to arbitrary checks on the request.
+    ///
+    /// For example, when a TLS layer verifies the client certificate of the proxy and passes its fingerprint in a header:
+    ///
+    /// ```rust
+    /// use rocket_client_addr::{Config, Resolver};
+    ///
+    /// const PROXY_CERT_FINGERPRINT: &str = "5E:6F:27:3A:...";
+    ///
+    /// let config = Config::default().trusted_if(|request| {
+    ///     request.headers().get_one("X-Client-Cert-Fingerprint") == Some(PROXY_CERT_FINGERPRINT)
+    /// });
+    ///
+    /// let rocket = rocket::build().manage(Resolver::from(config));
+    /// ```
+    ///
+    /// The header must be set (and overwritten) by the TLS layer, otherwise anyone can send it.
+    #[inline]
+    pub fn trusted_if<F: Fn(&Request<'_>) -> bool + Send + Sync + 'static>(
+        mut self,
+        predicate: F,
+    ) -> Config {
+        self.trusted_if = Some(TrustPredicate::new(predicate));
+
+        self
+    }
+}
@@ -0,0 +1,44 @@
+use std::net::{IpAddr, SocketAddr};
+
+use rocket::{
+    http::Status,
+    outcome::Outcome,
+    request::{self, FromRequest, Request},
+};
+
+/// The request guard used for getting both ends of the TCP connection, which helps debug NAT or hairpin issues.
+///
+/// Like `UpstreamAddr`, it is never derived from a header. It forwards when the address of the peer is unknown.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ConnectionAddrs {
+    /// IP address of the TCP peer (`request.remote()`).
+    pub client: IpAddr,
+    /// The local address which Rocket is bound to.
+    ///
+    /// Rocket 0.5 does not expose the local address on which each connection is received, so it is taken from the `address` and `port` of the configuration of Rocket. It is `None` if that address is unspecified (e.g. `0.0.0.0` or `::`, which means every interface) or that port is `0` (which means a random port), because the actual address is unknown then. It is also `None` in the local client of Rocket's testing module if the configuration is not set like that. Rocket 0.6 exposes the local address of each connection.
+    pub local:  Option<SocketAddr>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ConnectionAddrs {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let Some(addr) = request.remote() else {
+            return Outcome::Forward(Status::BadRequest);
+        };
+
+        let config = request.rocket().config();
+
+        let local = if config.address.is_unspecified() || config.port == 0 {
+            None
+        } else {
+            Some(SocketAddr::new(config.address, config.port))
+        };
+
+        Outcome::Success(ConnectionAddrs {
+            client: addr.ip(),
+            local,
+        })
+    }
+}
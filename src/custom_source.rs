@@ -0,0 +1,27 @@
+use std::net::IpAddr;
+
+use rocket::request::Request;
+
+/// A custom place of a request which holds the IP address of a client, set by a proxy of a specific deployment.
+///
+/// It is a lower-trust source than the forwarding headers, because a client can set any header, cookie or query parameter by itself. Use it only if the proxy always overwrites the value.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum CustomSource {
+    /// A header with the given name which holds a single IP address.
+    Header(String),
+    /// A cookie with the given name which holds a single IP address.
+    Cookie(String),
+    /// A query parameter with the given name which holds a single IP address.
+    Query(String),
+}
+
+impl CustomSource {
+    /// Get the IP address from a request.
+    pub(crate) fn get_ip(&self, request: &Request<'_>) -> Option<IpAddr> {
+        match self {
+            CustomSource::Header(name) => request.headers().get_one(name)?.trim().parse().ok(),
+            CustomSource::Cookie(name) => request.cookies().get(name)?.value().trim().parse().ok(),
+            CustomSource::Query(name) => request.query_value::<IpAddr>(name)?.ok(),
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use rocket::{
+    error,
+    fairing::{self, Fairing, Info, Kind},
+    Build, Data, Request, Rocket,
+};
+
+use crate::{CachedClientAddr, CdnHeader, IpNetSet, Resolver};
+
+/// The key of the trusted proxies in the configuration of Rocket.
+const TRUSTED_PROXIES_KEY: &str = "client_addr.trusted_proxies";
+
+/// The environment variable which overrides `TRUSTED_PROXIES_KEY`.
+const TRUSTED_PROXIES_ENV: &str = "ROCKET_CLIENT_ADDR_TRUSTED_PROXIES";
+
+/// The key of the trusted CDN headers in the configuration of Rocket.
+const TRUSTED_HEADERS_KEY: &str = "client_addr.trusted_headers";
+
+/// The environment variable which overrides `TRUSTED_HEADERS_KEY`.
+const TRUSTED_HEADERS_ENV: &str = "ROCKET_CLIENT_ADDR_TRUSTED_HEADERS";
+
+/// Read a list from the environment variable `env` as comma-separated entries, or from `key` of the configuration of Rocket as an array of strings if the variable is unset or blank, along with the name of the source it is read from (`env` or `key`), for error messages. `Ok(None)` if neither is set. `Err(())` if the configuration is not an array of strings, which is logged.
+fn read_list(
+    rocket: &Rocket<Build>,
+    key: &'static str,
+    env: &'static str,
+) -> Result<Option<(&'static str, Vec<String>)>, ()> {
+    if let Ok(value) = std::env::var(env) {
+        if !value.trim().is_empty() {
+            let entries = value.split(',').map(str::trim).filter(|entry| !entry.is_empty());
+
+            return Ok(Some((env, entries.map(String::from).collect())));
+        }
+    }
+
+    let Ok(value) = rocket.figment().find_value(key) else {
+        return Ok(None);
+    };
+
+    match value.deserialize::<Vec<String>>() {
+        Ok(entries) => Ok(Some((key, entries))),
+        Err(error) => {
+            error!("`{key}` must be an array of strings: {error}");
+
+            Err(())
+        },
+    }
+}
+
+/// A fairing which resolves the `ClientAddr` of every incoming request and caches it as `CachedClientAddr`, so that other fairings can read it without running the resolution again.
+///
+/// It is optional. Without it, the cache is populated by the first `ClientAddr` or `&ClientAddr` request guard.
+///
+/// It runs before the request is routed, so it cannot know whether the route has `NoHeaderTrust`. On such a route, the request guards, `CachedClientAddr` and `ClientAddr::resolve` ignore the cached address and use the TCP peer.
+///
+/// It also pins the address at the earliest hook of a request. Rocket runs the `on_request` callbacks of fairings in the order they are attached, so every fairing attached after it, every request guard and every catcher sees the same `ClientAddr` even if a fairing rewrites the forwarding headers. Attach it first to get this guarantee. Request guards which read the headers themselves, such as `ClientAddrInfo` and `ParsedForwarded`, still see the rewritten headers.
+///
+/// ```rust
+/// use rocket_client_addr::ClientAddrFairing;
+///
+/// let rocket = rocket::build().attach(ClientAddrFairing::new().auto_detect(true));
+/// ```
+///
+/// It also reads the trusted proxies from the configuration of Rocket (e.g. `Rocket.toml`) at ignition, and puts a `Resolver` which trusts them into the managed state.
+///
+/// ```toml
+/// [default.client_addr]
+/// trusted_proxies = ["10.0.0.0/8", "2001:db8::/32"]
+/// ```
+///
+/// The trusted CDN headers (see `CdnHeader`) can be set by their names in the same way:
+///
+/// ```toml
+/// [default.client_addr]
+/// trusted_headers = ["CF-Connecting-IP"]
+/// ```
+///
+/// For containerized deployments, the environment variables `ROCKET_CLIENT_ADDR_TRUSTED_PROXIES` and `ROCKET_CLIENT_ADDR_TRUSTED_HEADERS` hold the same lists as comma-separated entries, like `10.0.0.0/8, 2001:db8::/32`. Each of them overrides the corresponding key of the configuration of Rocket (from `Rocket.toml` or any other provider) entirely, rather than being merged with it. A variable which is unset or blank is ignored.
+///
+/// The entries of the trusted proxies are parsed by `IpNetSet::parse`. If any entry is invalid, including an unsupported header name, or if a `Resolver` is already managed, the ignition fails, so that a misconfigured list does not silently trust nothing.
+#[derive(Debug, Default)]
+pub struct ClientAddrFairing {
+    auto_detect: bool,
+}
+
+impl ClientAddrFairing {
+    /// Create a fairing with the default options.
+    #[inline]
+    pub fn new() -> ClientAddrFairing {
+        ClientAddrFairing::default()
+    }
+
+    /// Detect the CDN of every request by its headers (see `CdnHeader::detect` for the detection order) and trust the header of that CDN, unless `Config::trusted_cdn_headers` is set. `false` by default.
+    ///
+    /// The detected header takes the place of `Config::trusted_cdn_headers` in the resolution, so every other source keeps the precedence documented on `Resolver`, e.g. `Config::remote_sentinels` and `RealIpMode::Defer` still take precedence over it.
+    ///
+    /// Any client can send a CDN header, so when the request did not come through that CDN, the address can be spoofed. To prevent this, the detected header is used only if the TCP peer is trusted by `Config::trusted_proxies`, `Config::trusted_if` or `Config::trust_checker` (e.g. the published address ranges of the CDN), whenever any of them is set. Without them, the detected header is always used, so enable this mode only if the application cannot be reached without going through a CDN.
+    ///
+    /// The result is put into `CachedClientAddr`, so it is seen by the `ClientAddr` request guards, `CachedClientAddr::get` and `ClientAddr::resolve`, except on a route with `NoHeaderTrust`.
+    #[inline]
+    pub fn auto_detect(mut self, auto_detect: bool) -> Self {
+        self.auto_detect = auto_detect;
+
+        self
+    }
+}
+
+#[rocket::async_trait]
+impl Fairing for ClientAddrFairing {
+    #[inline]
+    fn info(&self) -> Info {
+        Info {
+            name: "Client Address",
+            kind: Kind::Ignite | Kind::Request,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> fairing::Result {
+        let Ok(trusted_proxies) = read_list(&rocket, TRUSTED_PROXIES_KEY, TRUSTED_PROXIES_ENV)
+        else {
+            return Err(rocket);
+        };
+
+        let Ok(trusted_headers) = read_list(&rocket, TRUSTED_HEADERS_KEY, TRUSTED_HEADERS_ENV)
+        else {
+            return Err(rocket);
+        };
+
+        if trusted_proxies.is_none() && trusted_headers.is_none() {
+            return Ok(rocket);
+        }
+
+        if rocket.state::<Resolver>().is_some() {
+            let sources = trusted_proxies
+                .iter()
+                .chain(trusted_headers.iter())
+                .map(|(source, _)| format!("`{source}`"))
+                .collect::<Vec<String>>()
+                .join(" and ");
+
+            error!(
+                "{sources} cannot be used with a managed `Resolver`. Set the trusted proxies and \
+                 headers of that `Resolver` instead."
+            );
+
+            return Err(rocket);
+        }
+
+        let mut builder = Resolver::builder();
+
+        if let Some((source, entries)) = trusted_proxies {
+            let entries = entries.iter().map(|entry| entry.as_str()).collect::<Vec<&str>>();
+
+            match IpNetSet::parse(&entries) {
+                Ok(trusted_proxies) => builder = builder.trust_proxies(trusted_proxies),
+                Err(error) => {
+                    error!("`{source}`: {error}");
+
+                    return Err(rocket);
+                },
+            }
+        }
+
+        if let Some((source, names)) = trusted_headers {
+            for name in names {
+                match CdnHeader::from_header_name(&name) {
+                    Some(cdn_header) => builder = builder.trust_cdn_header(cdn_header),
+                    None => {
+                        error!("`{source}`: {name:?} is not a supported CDN header");
+
+                        return Err(rocket);
+                    },
+                }
+            }
+        }
+
+        Ok(rocket.manage(builder.build()))
+    }
+
+    #[inline]
+    async fn on_request(&self, request: &mut Request<'_>, _data: &mut Data<'_>) {
+        if self.auto_detect {
+            let request = &*request;
+
+            request
+                .local_cache_async(async {
+                    let resolver = Resolver::from_request(request);
+
+                    let result = resolver.resolve_async_with_trust(request, true).await;
+
+                    CachedClientAddr::from_result(request, result)
+                })
+                .await;
+        } else {
+            CachedClientAddr::get_async(request).await;
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use std::{
+    borrow::Cow,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+use rocket::{
+    outcome::Outcome,
+    request::{self, FromRequest, Request},
+};
+
+/// The node of a `for` or `by` parameter in the `Forwarded` header (RFC 7239).
+///
+/// A proxy can hide the identity of a hop with `for=unknown` or an obfuscated identifier like `for=_hidden`. They are kept in the chain so that it can be seen that a hop deliberately hid its identity, but they are never selected as the IP address of a client.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum ForwardedFor {
+    /// An IP address. The port, if any, is dropped.
+    Addr(IpAddr),
+    /// `unknown`, which means the identity of the hop is not known to the proxy.
+    Unknown,
+    /// An obfuscated identifier, which starts with an underscore, such as `_hidden`.
+    Obfuscated(String),
+}
+
+impl ForwardedFor {
+    /// Get the IP address if this node is `ForwardedFor::Addr`.
+    #[inline]
+    pub fn addr(&self) -> Option<IpAddr> {
+        match self {
+            ForwardedFor::Addr(ip) => Some(*ip),
+            _ => None,
+        }
+    }
+
+    /// Get the `for` nodes of the `Forwarded` headers of a request, from the leftmost (the farthest) hop to the rightmost (the nearest) hop. The elements without a valid `for` parameter are omitted.
+    pub fn chain(request: &Request<'_>) -> Vec<ForwardedFor> {
+        parse_chain(request).into_iter().flatten().collect()
+    }
+
+    /// Parse a node like `192.0.2.43:47011`, `"[2001:db8:cafe::17]"`, `unknown` or `_hidden`. A quoted node is unescaped first, and a malformed quoted string is invalid.
+    pub(crate) fn parse(node: &str) -> Option<ForwardedFor> {
+        let node = unquote(node.trim())?;
+        let node = node.as_ref();
+
+        if node.eq_ignore_ascii_case("unknown") {
+            return Some(ForwardedFor::Unknown);
+        }
+
+        if is_obfuscated(node) {
+            return Some(ForwardedFor::Obfuscated(node.to_string()));
+        }
+
+        let ip = match node.strip_prefix('[') {
+            Some(node) => {
+                let (ip, port) = node.split_once(']')?;
+
+                if !port.is_empty() && !is_port(port.strip_prefix(':')?) {
+                    return None;
+                }
+
+                IpAddr::V6(ip.parse::<Ipv6Addr>().ok()?)
+            },
+            None => {
+                let ip = match node.split_once(':') {
+                    Some((ip, port)) if is_port(port) => ip,
+                    Some(_) => return None,
+                    None => node,
+                };
+
+                IpAddr::V4(ip.parse::<Ipv4Addr>().ok()?)
+            },
+        };
+
+        Some(ForwardedFor::Addr(ip))
+    }
+}
+
+/// `obfnode = "_" 1*( ALPHA / DIGIT / "." / "_" / "-")`, which is also the form of an obfuscated port.
+#[inline]
+fn is_obfuscated(s: &str) -> bool {
+    match s.strip_prefix('_') {
+        Some(rest) => {
+            let is_obfchar = |b: u8| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'-');
+
+            !rest.is_empty() && rest.bytes().all(is_obfchar)
+        },
+        None => false,
+    }
+}
+
+#[inline]
+fn is_port(s: &str) -> bool {
+    ((1..=5).contains(&s.len()) && s.bytes().all(|b| b.is_ascii_digit())) || is_obfuscated(s)
+}
+
+/// Unescape a quoted string (`quoted-string` of RFC 7230), in which a backslash escapes the next character (`quoted-pair`). A value which is not quoted is returned as it is. `None` if the quoted string is malformed, e.g. unterminated, or if an unquoted value has a quote.
+fn unquote(value: &str) -> Option<Cow<'_, str>> {
+    let Some(quoted) = value.strip_prefix('"') else {
+        return if value.contains('"') { None } else { Some(Cow::Borrowed(value)) };
+    };
+
+    let mut unescaped = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.push(chars.next()?),
+            // the closing quote must be the last character
+            '"' => {
+                return if chars.as_str().is_empty() { Some(Cow::Owned(unescaped)) } else { None }
+            },
+            _ => unescaped.push(c),
+        }
+    }
+
+    None
+}
+
+/// Split `value` by `separator`, except inside a quoted string, where a backslash escapes the next character.
+fn split_unquoted(value: &str, separator: char) -> impl Iterator<Item = &str> {
+    let mut in_quotes = false;
+    let mut is_escaped = false;
+
+    value.split(move |c: char| {
+        if is_escaped {
+            is_escaped = false;
+
+            return false;
+        }
+
+        match c {
+            '\\' if in_quotes => is_escaped = true,
+            '"' => in_quotes = !in_quotes,
+            _ => return c == separator && !in_quotes,
+        }
+
+        false
+    })
+}
+
+/// An element of the `Forwarded` header (RFC 7239), which is added by one proxy, like `for=192.0.2.60;proto=http;by=203.0.113.43`.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct ForwardedEntry {
+    /// The `for` parameter, which identifies the client of the proxy. `None` if it is absent or invalid.
+    pub for_:  Option<ForwardedFor>,
+    /// The `by` parameter, which identifies the interface on which the proxy received the request. `None` if it is absent or invalid.
+    pub by:    Option<ForwardedFor>,
+    /// The `host` parameter, which is the `Host` header received by the proxy.
+    pub host:  Option<String>,
+    /// The `proto` parameter, which is the scheme used by the client of the proxy, like `http` or `https`.
+    pub proto: Option<String>,
+}
+
+impl ForwardedEntry {
+    /// Parse an element. Unknown parameters are ignored. If a parameter occurs more than once, the first one is used.
+    fn parse(element: &str) -> ForwardedEntry {
+        let mut entry = ForwardedEntry::default();
+
+        for pair in split_unquoted(element, ';') {
+            let Some((name, value)) = pair.split_once('=') else {
+                continue;
+            };
+
+            let name = name.trim();
+
+            if name.eq_ignore_ascii_case("for") {
+                if entry.for_.is_none() {
+                    entry.for_ = ForwardedFor::parse(value);
+                }
+            } else if name.eq_ignore_ascii_case("by") {
+                if entry.by.is_none() {
+                    entry.by = ForwardedFor::parse(value);
+                }
+            } else if name.eq_ignore_ascii_case("host") {
+                if entry.host.is_none() {
+                    entry.host = unquote(value.trim()).map(Cow::into_owned);
+                }
+            } else if name.eq_ignore_ascii_case("proto") && entry.proto.is_none() {
+                entry.proto = unquote(value.trim()).map(Cow::into_owned);
+            }
+        }
+
+        entry
+    }
+}
+
+/// The request guard used for getting the full structure of the `Forwarded` headers (RFC 7239), e.g. for reconstructing the URL requested by the client or for auditing. It never fails.
+///
+/// It is only parsed, not verified, so any client can forge its entries except the ones added by trusted proxies.
+///
+/// ```rust
+/// use rocket::get;
+/// use rocket_client_addr::ParsedForwarded;
+///
+/// #[get("/")]
+/// fn index(forwarded: ParsedForwarded) -> String {
+///     let proto = forwarded.entries.first().and_then(|entry| entry.proto.as_deref());
+///
+///     proto.unwrap_or("unknown").to_string()
+/// }
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct ParsedForwarded {
+    /// The elements of every `Forwarded` header, from the leftmost (the farthest) to the rightmost (the nearest). Empty elements are omitted.
+    pub entries: Vec<ForwardedEntry>,
+}
+
+impl ParsedForwarded {
+    /// Parse the values of `Forwarded` headers in order.
+    pub fn parse<'a, I: IntoIterator<Item = &'a str>>(values: I) -> ParsedForwarded {
+        let entries = values
+            .into_iter()
+            .flat_map(|value| split_unquoted(value, ','))
+            .filter(|element| !element.trim().is_empty())
+            .map(ForwardedEntry::parse)
+            .collect();
+
+        ParsedForwarded { entries }
+    }
+
+    /// Parse the `Forwarded` headers of a request.
+    #[inline]
+    pub fn from_headers(request: &Request<'_>) -> ParsedForwarded {
+        ParsedForwarded::parse(request.headers().get("forwarded"))
+    }
+
+    /// Get the IP address of the `by` parameter of the rightmost (the nearest) element, which identifies the interface of the proxy that forwarded the request to this server, e.g. for auditing which proxy handled it. `None` if the parameter is absent or invalid, or if it is `unknown` or an obfuscated identifier.
+    #[inline]
+    pub fn by(&self) -> Option<IpAddr> {
+        self.entries.last()?.by.as_ref()?.addr()
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ParsedForwarded {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        Outcome::Success(ParsedForwarded::from_headers(request))
+    }
+}
+
+/// Check whether a value of the `Forwarded` header is well-formed, which is required by `Config::strict_parsing`. Every pair must be `name=value` with a token as the name and a valid quoted string if the value is quoted, and a `for` or `by` parameter must be a valid node (see `ForwardedFor::parse`). Like in any list of HTTP, empty elements and empty pairs are allowed, so an empty or whitespace-only value is well-formed.
+pub(crate) fn is_well_formed(value: &str) -> bool {
+    // an unterminated quoted string swallows the rest of the value, which is then malformed
+    split_unquoted(value, ',').flat_map(|element| split_unquoted(element, ';')).all(|pair| {
+        let pair = pair.trim();
+
+        if pair.is_empty() {
+            return true;
+        }
+
+        let Some((name, value)) = pair.split_once('=') else {
+            return false;
+        };
+
+        let (name, value) = (name.trim(), value.trim());
+
+        if !is_token(name) {
+            return false;
+        }
+
+        if name.eq_ignore_ascii_case("for") || name.eq_ignore_ascii_case("by") {
+            ForwardedFor::parse(value).is_some()
+        } else {
+            !value.is_empty() && unquote(value).is_some()
+        }
+    })
+}
+
+/// `token = 1*tchar` of RFC 7230.
+#[inline]
+fn is_token(s: &str) -> bool {
+    let is_tchar = |b: u8| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b);
+
+    !s.is_empty() && s.bytes().all(is_tchar)
+}
+
+/// Parse the `for` nodes of the `Forwarded` headers of a request. An element without a valid `for` parameter is `None`.
+pub(crate) fn parse_chain(request: &Request<'_>) -> Vec<Option<ForwardedFor>> {
+    ParsedForwarded::from_headers(request).entries.into_iter().map(|entry| entry.for_).collect()
+}
@@ -0,0 +1,94 @@
+use std::net::IpAddr;
+
+/// The location of an IP address in a geolocation database.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct GeoInfo {
+    /// The country, e.g. an ISO 3166-1 code like `US`.
+    pub country: Option<String>,
+    /// The region, e.g. a state or a province.
+    pub region:  Option<String>,
+    /// The city.
+    pub city:    Option<String>,
+}
+
+/// A geolocation database used by `ClientAddr::geo`.
+///
+/// This crate does not depend on any database by default. Implement this trait for the reader of the backend you pick, such as a MaxMind DB, which is opened by your application, or enable the `ip2location` feature for `Ip2LocationDb`.
+///
+/// ```rust
+/// use std::net::IpAddr;
+///
+/// use rocket_client_addr::{GeoDb, GeoInfo};
+///
+/// struct CountryTable(Vec<(IpAddr, &'static str)>);
+///
+/// impl GeoDb for CountryTable {
+///     fn lookup(&self, ip: IpAddr) -> Option<GeoInfo> {
+///         let (_, country) = self.0.iter().find(|(entry, _)| *entry == ip)?;
+///
+///         Some(GeoInfo { country: Some(country.to_string()), ..GeoInfo::default() })
+///     }
+/// }
+/// ```
+pub trait GeoDb {
+    /// Look up an IP address. It is never called with a local address (see `ClientAddr::geo`).
+    fn lookup(&self, ip: IpAddr) -> Option<GeoInfo>;
+}
+
+/// A `GeoDb` over an IP2Location BIN file (e.g. `IP2LOCATION-LITE-DB3.IPV6.BIN`), read by the `ip2location` crate. The file is opened by your application.
+///
+/// ```rust,no_run
+/// use ip2location::LocationDB;
+/// use rocket_client_addr::{ClientAddr, Ip2LocationDb};
+///
+/// let db = Ip2LocationDb::new(LocationDB::from_file("IP2LOCATION-LITE-DB3.IPV6.BIN").unwrap());
+///
+/// let client_addr = ClientAddr { ip: "93.184.216.34".parse().unwrap() };
+///
+/// println!("{:?}", client_addr.geo(&db));
+/// ```
+///
+/// A field which is not in the database, or is `-` (unknown) in it, is `None`.
+#[cfg(feature = "ip2location")]
+#[derive(Debug)]
+pub struct Ip2LocationDb(ip2location::LocationDB);
+
+#[cfg(feature = "ip2location")]
+impl Ip2LocationDb {
+    /// Wrap an opened IP2Location database.
+    #[inline]
+    pub fn new(db: ip2location::LocationDB) -> Self {
+        Ip2LocationDb(db)
+    }
+
+    /// Get the underlying database.
+    #[inline]
+    pub fn inner(&self) -> &ip2location::LocationDB {
+        &self.0
+    }
+}
+
+#[cfg(feature = "ip2location")]
+impl From<ip2location::LocationDB> for Ip2LocationDb {
+    #[inline]
+    fn from(db: ip2location::LocationDB) -> Self {
+        Ip2LocationDb::new(db)
+    }
+}
+
+#[cfg(feature = "ip2location")]
+impl GeoDb for Ip2LocationDb {
+    fn lookup(&self, ip: IpAddr) -> Option<GeoInfo> {
+        let known = |value: &str| (!value.is_empty() && value != "-").then(|| value.to_string());
+
+        let record = self.0.ip_lookup(ip).ok()?;
+
+        let info = GeoInfo {
+            country: record.country.and_then(|country| known(&country.short_name)),
+            region:  record.region.and_then(|region| known(&region)),
+            city:    record.city.and_then(|city| known(&city)),
+        };
+
+        (info != GeoInfo::default()).then_some(info)
+    }
+}
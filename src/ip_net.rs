@@ -0,0 +1,473 @@
+use std::{
+    borrow::Cow,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+};
+
+/// An IP network, which is an IP address with a prefix length, such as `10.0.0.0/8`.
+///
+/// The host bits of the address are always cleared.
+///
+/// ```rust
+/// use std::net::{Ipv4Addr, Ipv6Addr};
+///
+/// use rocket_client_addr::IpNet;
+///
+/// const PRIVATE_NETWORK: IpNet = IpNet::new_v4(Ipv4Addr::new(10, 0, 0, 0), 8);
+/// const UNIQUE_LOCAL_NETWORK: IpNet = IpNet::new_v6(Ipv6Addr::new(0xFC00, 0, 0, 0, 0, 0, 0, 0), 7);
+///
+/// assert!(PRIVATE_NETWORK.contains(&"10.1.2.3".parse().unwrap()));
+/// assert!(UNIQUE_LOCAL_NETWORK.contains(&"fd00::1".parse().unwrap()));
+/// assert_eq!(PRIVATE_NETWORK, "10.0.0.0/8".parse().unwrap());
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct IpNet {
+    addr:       IpAddr,
+    prefix_len: u8,
+}
+
+/// The maximum number of host bits of a network which `IpNet::hosts` iterates, i.e. 65536 addresses.
+const MAX_HOST_BITS: u8 = 16;
+
+#[inline]
+pub(crate) const fn mask_v4(prefix_len: u8) -> u32 {
+    if prefix_len == 0 {
+        0
+    } else {
+        u32::MAX << (32 - prefix_len)
+    }
+}
+
+#[inline]
+const fn mask_v6(prefix_len: u8) -> u128 {
+    if prefix_len == 0 {
+        0
+    } else {
+        u128::MAX << (128 - prefix_len)
+    }
+}
+
+#[inline]
+const fn ipv4_from_bits(bits: u32) -> Ipv4Addr {
+    let [a, b, c, d] = bits.to_be_bytes();
+
+    Ipv4Addr::new(a, b, c, d)
+}
+
+#[inline]
+const fn ipv6_from_bits(bits: u128) -> Ipv6Addr {
+    let b = bits.to_be_bytes();
+
+    Ipv6Addr::new(
+        u16::from_be_bytes([b[0], b[1]]),
+        u16::from_be_bytes([b[2], b[3]]),
+        u16::from_be_bytes([b[4], b[5]]),
+        u16::from_be_bytes([b[6], b[7]]),
+        u16::from_be_bytes([b[8], b[9]]),
+        u16::from_be_bytes([b[10], b[11]]),
+        u16::from_be_bytes([b[12], b[13]]),
+        u16::from_be_bytes([b[14], b[15]]),
+    )
+}
+
+impl IpNet {
+    /// Create an IPv4 network.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prefix_len` is greater than `32`. When it is used in a `const` or `static` item, the panic becomes a compile error.
+    #[inline]
+    pub const fn new_v4(addr: Ipv4Addr, prefix_len: u8) -> IpNet {
+        assert!(prefix_len <= 32, "the prefix length of an IPv4 network cannot exceed 32");
+
+        let bits = u32::from_be_bytes(addr.octets()) & mask_v4(prefix_len);
+
+        IpNet { addr: IpAddr::V4(ipv4_from_bits(bits)), prefix_len }
+    }
+
+    /// Create an IPv6 network.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prefix_len` is greater than `128`. When it is used in a `const` or `static` item, the panic becomes a compile error.
+    #[inline]
+    pub const fn new_v6(addr: Ipv6Addr, prefix_len: u8) -> IpNet {
+        assert!(prefix_len <= 128, "the prefix length of an IPv6 network cannot exceed 128");
+
+        let bits = u128::from_be_bytes(addr.octets()) & mask_v6(prefix_len);
+
+        IpNet { addr: IpAddr::V6(ipv6_from_bits(bits)), prefix_len }
+    }
+
+    /// Create an IP network.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prefix_len` is greater than the bit length of `addr`.
+    #[inline]
+    pub const fn new(addr: IpAddr, prefix_len: u8) -> IpNet {
+        match addr {
+            IpAddr::V4(addr) => IpNet::new_v4(addr, prefix_len),
+            IpAddr::V6(addr) => IpNet::new_v6(addr, prefix_len),
+        }
+    }
+
+    /// Get the network address.
+    #[inline]
+    pub const fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// Get the prefix length.
+    #[inline]
+    pub const fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Check whether `ip` is in this network. An IPv4-mapped IPv6 address is treated as its IPv4 address, and an IPv6 network (even `::/0`) never contains an IPv4 address, so the entries of a forwarded chain are compared within their own family.
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(addr), IpAddr::V4(ip)) => {
+                u32::from(*ip) & mask_v4(self.prefix_len) == u32::from(addr)
+            },
+            (IpAddr::V4(_), IpAddr::V6(ip)) => match ip.to_ipv4_mapped() {
+                Some(ip) => self.contains(&IpAddr::V4(ip)),
+                None => false,
+            },
+            (IpAddr::V6(addr), IpAddr::V6(ip)) => {
+                u128::from(*ip) & mask_v6(self.prefix_len) == u128::from(addr)
+            },
+            (IpAddr::V6(_), IpAddr::V4(_)) => false,
+        }
+    }
+
+    /// Summarize networks into the fewest networks which cover exactly the same addresses, e.g. for compacting a ban list which has collected many `/32` entries. A network contained by another one is dropped, and two adjacent networks which are the halves of a larger network are merged repeatedly, so `10.0.0.0/26`, `10.0.0.64/26`, `10.0.0.128/26` and `10.0.0.192/26` become `10.0.0.0/24`.
+    ///
+    /// The result is sorted, IPv4 networks first. IPv4 and IPv6 networks are never merged with each other.
+    pub fn aggregate(nets: &[IpNet]) -> Vec<IpNet> {
+        let mut nets = nets.to_vec();
+
+        nets.sort_unstable_by_key(|net| (net.addr, net.prefix_len));
+
+        let mut aggregated: Vec<IpNet> = Vec::with_capacity(nets.len());
+
+        for net in nets {
+            // a larger network precedes the ones it contains
+            if aggregated.last().map_or(false, |last| last.contains_net(&net)) {
+                continue;
+            }
+
+            aggregated.push(net);
+
+            while let [.., lower, upper] = aggregated[..] {
+                if lower.prefix_len != upper.prefix_len || lower.prefix_len == 0 {
+                    break;
+                }
+
+                let parent = IpNet::new(lower.addr, lower.prefix_len - 1);
+
+                if parent.addr != lower.addr || !parent.contains_net(&upper) {
+                    break;
+                }
+
+                aggregated.truncate(aggregated.len() - 2);
+                aggregated.push(parent);
+            }
+        }
+
+        aggregated
+    }
+
+    /// Check whether `net` is a subnet of this network within the same family.
+    #[inline]
+    fn contains_net(&self, net: &IpNet) -> bool {
+        self.addr.is_ipv4() == net.addr.is_ipv4()
+            && self.prefix_len <= net.prefix_len
+            && self.contains(&net.addr)
+    }
+
+    /// Iterate every address in this network in ascending order, including the network address and the broadcast address of an IPv4 network, e.g. for generating the entries of an allowlist from a small network like `10.0.0.0/28`.
+    ///
+    /// To guard against iterating an absurdly large network (an IPv6 `/64` has 2^64 addresses), it yields nothing if the network has more than 65536 addresses, i.e. its prefix length is less than 16 for IPv4 or less than 112 for IPv6.
+    pub fn hosts(&self) -> impl Iterator<Item = IpAddr> {
+        let (first, host_bits) = match self.addr {
+            IpAddr::V4(addr) => (u128::from(u32::from(addr)), 32 - self.prefix_len),
+            IpAddr::V6(addr) => (u128::from(addr), 128 - self.prefix_len),
+        };
+
+        let count = if host_bits > MAX_HOST_BITS { 0 } else { 1u128 << host_bits };
+        let is_ipv4 = self.addr.is_ipv4();
+
+        // the last address of an IPv6 network may be `u128::MAX`, so the end cannot be computed
+        (0..count).map(move |i| {
+            let bits = first + i;
+
+            if is_ipv4 {
+                IpAddr::V4(ipv4_from_bits(bits as u32))
+            } else {
+                IpAddr::V6(ipv6_from_bits(bits))
+            }
+        })
+    }
+}
+
+impl From<IpAddr> for IpNet {
+    /// Create a network which contains only `ip`.
+    #[inline]
+    fn from(ip: IpAddr) -> Self {
+        match ip {
+            IpAddr::V4(_) => IpNet::new(ip, 32),
+            IpAddr::V6(_) => IpNet::new(ip, 128),
+        }
+    }
+}
+
+impl TryFrom<(IpAddr, u8)> for IpNet {
+    type Error = IpNetError;
+
+    /// Create a network from an address and a prefix length. The network types of the `ipnetwork` and `cidr` crates can also be converted directly with the features of the same names.
+    #[inline]
+    fn try_from((addr, prefix_len): (IpAddr, u8)) -> Result<Self, Self::Error> {
+        let max_prefix_len = if addr.is_ipv4() { 32 } else { 128 };
+
+        if prefix_len > max_prefix_len {
+            return Err(IpNetError::InvalidPrefixLen);
+        }
+
+        Ok(IpNet::new(addr, prefix_len))
+    }
+}
+
+impl From<IpNet> for (IpAddr, u8) {
+    #[inline]
+    fn from(net: IpNet) -> Self {
+        (net.addr, net.prefix_len)
+    }
+}
+
+#[cfg(feature = "ipnetwork")]
+impl From<ipnetwork::IpNetwork> for IpNet {
+    /// Create a network from an `IpNetwork`, whose host bits are cleared.
+    #[inline]
+    fn from(network: ipnetwork::IpNetwork) -> Self {
+        IpNet::new(network.ip(), network.prefix())
+    }
+}
+
+#[cfg(feature = "ipnetwork")]
+impl From<IpNet> for ipnetwork::IpNetwork {
+    #[inline]
+    fn from(net: IpNet) -> Self {
+        // the prefix length of an `IpNet` is never too large for its address
+        ipnetwork::IpNetwork::new(net.addr, net.prefix_len).unwrap()
+    }
+}
+
+#[cfg(feature = "cidr")]
+impl From<cidr::IpCidr> for IpNet {
+    #[inline]
+    fn from(cidr: cidr::IpCidr) -> Self {
+        IpNet::new(cidr.first_address(), cidr.network_length())
+    }
+}
+
+#[cfg(feature = "cidr")]
+impl From<IpNet> for cidr::IpCidr {
+    #[inline]
+    fn from(net: IpNet) -> Self {
+        // the host bits of an `IpNet` are always cleared, which is required by `IpCidr`
+        cidr::IpCidr::new(net.addr, net.prefix_len).unwrap()
+    }
+}
+
+impl Display for IpNet {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+/// Errors which can occur when parsing an `IpNet`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum IpNetError {
+    /// The address part is not a valid IP address.
+    InvalidAddr,
+    /// The prefix length part is not a number or too large for the address.
+    InvalidPrefixLen,
+}
+
+impl Display for IpNetError {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            IpNetError::InvalidAddr => f.write_str("invalid IP address"),
+            IpNetError::InvalidPrefixLen => f.write_str("invalid prefix length"),
+        }
+    }
+}
+
+impl Error for IpNetError {}
+
+/// Errors which can occur when parsing an `IpNetSet` by `IpNetSet::parse`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum IpNetSetError {
+    /// Some entries are invalid. Each offending string is paired with the reason.
+    InvalidEntries(Vec<(String, IpNetError)>),
+}
+
+impl Display for IpNetSetError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            IpNetSetError::InvalidEntries(invalid_entries) => {
+                f.write_str("invalid entries: ")?;
+
+                for (i, (entry, error)) in invalid_entries.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+
+                    write!(f, "{entry:?} ({error})")?;
+                }
+
+                Ok(())
+            },
+        }
+    }
+}
+
+impl Error for IpNetSetError {}
+
+impl FromStr for IpNet {
+    type Err = IpNetError;
+
+    /// Parse a string like `10.0.0.0/8` or `2001:db8::/32`. An address without a prefix length becomes a network which contains only that address.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        let (addr, prefix_len) = match s.split_once('/') {
+            Some((addr, prefix_len)) => (addr, Some(prefix_len)),
+            None => (s, None),
+        };
+
+        let addr = addr.parse::<IpAddr>().map_err(|_| IpNetError::InvalidAddr)?;
+
+        let Some(prefix_len) = prefix_len else {
+            return Ok(IpNet::from(addr));
+        };
+
+        let prefix_len = prefix_len.parse::<u8>().map_err(|_| IpNetError::InvalidPrefixLen)?;
+
+        IpNet::try_from((addr, prefix_len))
+    }
+}
+
+/// A set of IP networks, such as the trusted proxies.
+///
+/// It can be built in a `static` item without any runtime parsing.
+///
+/// ```rust
+/// use std::net::Ipv4Addr;
+///
+/// use rocket_client_addr::{IpNet, IpNetSet};
+///
+/// static TRUSTED_PROXIES: IpNetSet = IpNetSet::from_static(&[
+///     IpNet::new_v4(Ipv4Addr::new(198, 51, 100, 0), 24),
+///     IpNet::new_v4(Ipv4Addr::new(203, 0, 113, 7), 32),
+/// ]);
+///
+/// assert!(TRUSTED_PROXIES.contains(&"198.51.100.20".parse().unwrap()));
+/// assert!(!TRUSTED_PROXIES.contains(&"203.0.113.8".parse().unwrap()));
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct IpNetSet {
+    nets: Cow<'static, [IpNet]>,
+}
+
+impl IpNetSet {
+    /// Create an empty set.
+    #[inline]
+    pub const fn new() -> IpNetSet {
+        IpNetSet { nets: Cow::Borrowed(&[]) }
+    }
+
+    /// Create a set from a static slice of networks.
+    #[inline]
+    pub const fn from_static(nets: &'static [IpNet]) -> IpNetSet {
+        IpNetSet { nets: Cow::Borrowed(nets) }
+    }
+
+    /// Add a network into this set.
+    #[inline]
+    pub fn push(&mut self, net: IpNet) {
+        self.nets.to_mut().push(net);
+    }
+
+    /// Check whether `ip` is in any network of this set.
+    #[inline]
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        self.nets.iter().any(|net| net.contains(ip))
+    }
+
+    /// Get the networks of this set.
+    #[inline]
+    pub fn as_slice(&self) -> &[IpNet] {
+        &self.nets
+    }
+
+    /// Check whether this set is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.nets.is_empty()
+    }
+
+    /// Parse every entry like `10.0.0.0/8` or `2001:db8::1` (see `IpNet::from_str`) into a set, e.g. the trusted proxies read from a configuration. Unlike collecting the results of `str::parse`, no invalid entry is silently ignored. If any entry is invalid, every invalid entry is returned in `IpNetSetError::InvalidEntries` with the offending string.
+    ///
+    /// ```rust
+    /// use rocket_client_addr::{IpNetError, IpNetSet, IpNetSetError};
+    ///
+    /// let trusted_proxies = IpNetSet::parse(&["10.0.0.0/8", "2001:db8::/32"]).unwrap();
+    ///
+    /// assert!(trusted_proxies.contains(&"10.1.2.3".parse().unwrap()));
+    ///
+    /// assert_eq!(
+    ///     Err(IpNetSetError::InvalidEntries(vec![(
+    ///         "10.0.0.0/33".to_string(),
+    ///         IpNetError::InvalidPrefixLen
+    ///     )])),
+    ///     IpNetSet::parse(&["127.0.0.1", "10.0.0.0/33"])
+    /// );
+    /// ```
+    pub fn parse(entries: &[&str]) -> Result<IpNetSet, IpNetSetError> {
+        let mut nets = Vec::with_capacity(entries.len());
+        let mut invalid_entries = Vec::new();
+
+        for entry in entries {
+            match entry.parse::<IpNet>() {
+                Ok(net) => nets.push(net),
+                Err(error) => invalid_entries.push((entry.to_string(), error)),
+            }
+        }
+
+        if invalid_entries.is_empty() {
+            Ok(IpNetSet::from(nets))
+        } else {
+            Err(IpNetSetError::InvalidEntries(invalid_entries))
+        }
+    }
+}
+
+impl From<Vec<IpNet>> for IpNetSet {
+    #[inline]
+    fn from(nets: Vec<IpNet>) -> Self {
+        IpNetSet { nets: Cow::Owned(nets) }
+    }
+}
+
+impl FromIterator<IpNet> for IpNetSet {
+    #[inline]
+    fn from_iter<T: IntoIterator<Item = IpNet>>(iter: T) -> Self {
+        IpNetSet::from(iter.into_iter().collect::<Vec<IpNet>>())
+    }
+}
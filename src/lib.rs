@@ -3,11 +3,132 @@
 
 This crate provides two request guards used for getting an IP address from a client.
 
+`LocalhostOnly` is another request guard which only accepts clients connected from the loopback interface, ignoring any forwarding header.
+
+`UpstreamAddr` is another request guard which gets the IP address of the TCP peer (e.g. the last proxy), ignoring any forwarding header. `ConnectionAddrs` also gets the local address which Rocket is bound to.
+
+The behavior of `ClientAddr` can be adjusted by putting a `Resolver` into the managed state of Rocket, which can be built from a `Preset` for some cloud load balancers. The trusted proxies can also be set in the configuration of Rocket (`client_addr.trusted_proxies`) or by the environment variable `ROCKET_CLIENT_ADDR_TRUSTED_PROXIES`, which are read by `ClientAddrFairing`. Put `NoHeaderTrust` before `ClientAddr` in a route to ignore every header on that route.
+
+`client_addr_outcome` gets the outcome of the `ClientAddr` request guard, for building other request guards on top of it. `client_addr_derived` also caches a value derived from it, so that several request guards of another crate resolve the IP address and derive the value only once per request (see `examples/shared_guards.rs`).
+
+`TrustedClientAddr` is another request guard which always gets an IP address, with a flag telling whether it is resolved from a trusted source.
+
+`NativeIpv6ClientAddr` is another request guard which only accepts clients connected over IPv6, rejecting IPv4 clients even if they appear as IPv4-mapped IPv6 addresses.
+
+`SessionBoundAddr` is another request guard which binds a session to the network of a client, with the `session-binding` feature.
+
+`candidate_ips` lists every address in a request which could be taken as the client, for auditing header injection.
+
+`client_scheme` gets the scheme (HTTP or HTTPS) used by a client behind trusted proxies.
+
+The resolved `ClientAddr` is cached in the request-local state as `CachedClientAddr`, which other fairings and request guards can reuse. Attach `ClientAddrFairing` to resolve it for every request up front, optionally detecting the CDN of each request by `ClientAddrFairing::auto_detect`.
+
+## Features
+
+* `cidr`: Implement the conversions between `IpNet` and `IpCidr` of the `cidr` crate.
+* `ip2location`: Enable `Ip2LocationDb`, a `GeoDb` for `ClientAddr::geo` over an IP2Location BIN file read by the `ip2location` crate.
+* `ipnetwork`: Implement the conversions between `IpNet` and `IpNetwork` of the `ipnetwork` crate.
+* `salted-hash`: Enable `ClientAddr::salted_hash` for privacy-preserving logging.
+* `serde`: Implement `Serialize` and `Deserialize` of `serde` for `ClientAddr`, e.g. for putting it into a template context or a JSON response. It is the same `serde` as `rocket::serde`.
+* `session-binding`: Enable `SessionBoundAddr`, which stores the network of a client in a private (encrypted and authenticated) cookie. It enables the `secrets` feature of Rocket, so `secret_key` must be configured in release builds.
+* `tls`: Enable `Config::prefer_tls_peer`, which uses the TCP peer when Rocket terminates TLS. It enables the `tls` feature of Rocket.
+* `tracing`: Emit a `tracing` warning with the TCP peer and the header value when an untrusted TCP peer sends a forwarding header (`X-Forwarded-For` or `Forwarded`) while trusted proxies are configured, which is likely a spoof attempt. Without trusted proxies, a hint about `Config::always_use_headers` is emitted once instead. Every resolution is also wrapped in a `resolve_client_addr` span at the debug level, which records the total length of the forwarding headers (`header_bytes`) and the duration of the resolution (`elapsed_us`), for detecting slow resolutions caused by oversized headers.
+
+## Testing
+
+The resolution can be tested with the local client of Rocket, whose requests have no TCP peer unless `LocalRequest::remote` sets one.
+
+```rust
+use rocket::{get, http::Header, local::blocking::Client, routes};
+use rocket_client_addr::ClientAddr;
+
+#[get("/")]
+fn index(client_addr: &ClientAddr) -> String {
+    client_addr.to_string()
+}
+
+let client = Client::untracked(rocket::build().mount("/", routes![index])).unwrap();
+
+let response = client
+    .get("/")
+    .remote("10.0.0.1:8000".parse().unwrap()) // a local proxy
+    .header(Header::new("X-Forwarded-For", "93.184.216.34, 10.0.0.2"))
+    .dispatch();
+
+assert_eq!("93.184.216.34", response.into_string().unwrap());
+```
+
+## MSRV
+
+The minimum supported Rust version is 1.69, on the stable toolchain. The classifications of IP addresses which are still unstable in `std` (the `ip` feature) are implemented by this crate, and any nightly feature is rejected by `#![forbid(unstable_features)]`.
+
 See `examples`.
 */
 
+#![forbid(unstable_features)]
+
+mod addr_source;
+mod candidate_ips;
+mod canonicalized;
+mod cdn_header;
+mod classification;
 mod client_addr;
+mod client_addr_info;
+mod client_addr_string;
 mod client_real_addr;
+mod client_scheme;
+mod config;
+mod connection_addrs;
+mod custom_source;
+mod fairing;
+mod forwarded;
+mod geo;
+mod ip_net;
+mod localhost_only;
+mod native_ipv6_client_addr;
+mod no_header_trust;
+mod octets;
+mod preset;
+mod resolution_step;
+mod resolver;
+#[cfg(feature = "session-binding")]
+mod session_bound_addr;
+mod source_kind;
+mod special_purpose;
+mod trusted_client_addr;
+mod upstream_addr;
 
-pub use client_addr::ClientAddr;
+pub use addr_source::AddrSource;
+pub use candidate_ips::candidate_ips;
+pub use canonicalized::Canonicalized;
+pub use cdn_header::{CdnHeader, CdnLayer};
+pub use classification::{classify_many, Classification};
+pub use client_addr::{
+    client_addr_derived, client_addr_outcome, CachedClientAddr, ClientAddr, ClientAddrError,
+    Ipv6Scope,
+};
+pub use client_addr_info::ClientAddrInfo;
+pub use client_addr_string::ClientAddrString;
 pub use client_real_addr::ClientRealAddr;
+pub use client_scheme::{client_scheme, Scheme};
+pub use config::{AddrFamily, Config, RealIpMode, Strategy, TrustChecker, TrustPredicate};
+pub use connection_addrs::ConnectionAddrs;
+pub use custom_source::CustomSource;
+pub use fairing::ClientAddrFairing;
+pub use forwarded::{ForwardedEntry, ForwardedFor, ParsedForwarded};
+pub use geo::{GeoDb, GeoInfo};
+#[cfg(feature = "ip2location")]
+pub use geo::Ip2LocationDb;
+pub use ip_net::{IpNet, IpNetError, IpNetSet, IpNetSetError};
+pub use localhost_only::LocalhostOnly;
+pub use native_ipv6_client_addr::{NativeIpv6ClientAddr, NativeIpv6ClientAddrError};
+pub use no_header_trust::NoHeaderTrust;
+pub use octets::Octets;
+pub use preset::Preset;
+pub use resolution_step::{ResolutionStep, StepVerdict};
+pub use resolver::{Resolver, ResolverBuilder};
+#[cfg(feature = "session-binding")]
+pub use session_bound_addr::{SessionBinding, SessionBoundAddr, SessionBoundAddrError};
+pub use source_kind::SourceKind;
+pub use trusted_client_addr::TrustedClientAddr;
+pub use upstream_addr::UpstreamAddr;
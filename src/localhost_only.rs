@@ -0,0 +1,41 @@
+use std::net::IpAddr;
+
+use rocket::{
+    http::Status,
+    outcome::Outcome,
+    request::{self, FromRequest, Request},
+};
+
+/// The request guard used for allowing only clients connected from the loopback interface.
+///
+/// Unlike `ClientAddr` and `ClientRealAddr`, this guard intentionally uses the address of the TCP peer (`request.remote()`) and ignores every forwarding header (`X-Forwarded-For`, `X-Real-IP`, ...), so it cannot be spoofed by a client. It is suitable for gating debug or admin endpoints.
+///
+/// Note that if the application runs behind a reverse proxy on the same machine, every request appears to come from the loopback interface.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct LocalhostOnly {
+    /// IP address of the TCP peer.
+    pub ip: IpAddr,
+}
+
+#[inline]
+fn is_loopback(addr: &IpAddr) -> bool {
+    match addr {
+        IpAddr::V4(addr) => addr.is_loopback(),
+        IpAddr::V6(addr) => match addr.to_ipv4_mapped() {
+            Some(addr) => addr.is_loopback(),
+            None => addr.is_loopback(),
+        },
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for LocalhostOnly {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        match request.remote().map(|addr| addr.ip()) {
+            Some(ip) if is_loopback(&ip) => Outcome::Success(LocalhostOnly { ip }),
+            _ => Outcome::Forward(Status::Forbidden),
+        }
+    }
+}
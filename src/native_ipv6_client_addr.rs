@@ -0,0 +1,81 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    net::{IpAddr, Ipv6Addr},
+};
+
+use rocket::{
+    http::Status,
+    outcome::Outcome,
+    request::{self, FromRequest, Request},
+};
+
+use crate::{client_addr_outcome, ClientAddrError, Resolver};
+
+/// Errors of the `NativeIpv6ClientAddr` request guard.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum NativeIpv6ClientAddrError {
+    /// The IP address of the client cannot be resolved. See `ClientAddrError`.
+    ClientAddr(ClientAddrError),
+    /// The client is an IPv4 client, including one which appears as an IPv4-mapped IPv6 address.
+    NotNativeIpv6(IpAddr),
+}
+
+impl Display for NativeIpv6ClientAddrError {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            NativeIpv6ClientAddrError::ClientAddr(error) => Display::fmt(error, f),
+            NativeIpv6ClientAddrError::NotNativeIpv6(ip) => {
+                write!(f, "the client is not a native IPv6 client: {ip}")
+            },
+        }
+    }
+}
+
+impl Error for NativeIpv6ClientAddrError {}
+
+/// The request guard used for getting the IPv6 address of a client which really connects over IPv6, e.g. for IPv6-only routes. It is resolved like `ClientAddr`.
+///
+/// An IPv4 client can also appear as an IPv4-mapped IPv6 address (`::ffff:a.b.c.d`), e.g. when Rocket listens on a dual-stack socket, which is an IPv4 client rather than a native IPv6 client. Such a client and a plain IPv4 client fail with `Status::Forbidden` (or `Config::failure_status`) and `NativeIpv6ClientAddrError::NotNativeIpv6`. An address with the NAT64 prefix (`64:ff9b::a.b.c.d`) is a native IPv6 address, because the client really connects over IPv6, unless `Config::canonicalize_mapped` converts it.
+///
+/// ```rust
+/// use rocket::get;
+/// use rocket_client_addr::NativeIpv6ClientAddr;
+///
+/// #[get("/experimental")]
+/// fn experimental(client_addr: NativeIpv6ClientAddr) -> String {
+///     client_addr.0.to_string()
+/// }
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct NativeIpv6ClientAddr(pub Ipv6Addr);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for NativeIpv6ClientAddr {
+    type Error = NativeIpv6ClientAddrError;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let client_addr = match client_addr_outcome(request).await {
+            Outcome::Success(client_addr) => client_addr,
+            Outcome::Error((status, error)) => {
+                return Outcome::Error((status, NativeIpv6ClientAddrError::ClientAddr(error)));
+            },
+            Outcome::Forward(status) => return Outcome::Forward(status),
+        };
+
+        match client_addr.ip {
+            IpAddr::V6(ipv6) if ipv6.to_ipv4_mapped().is_none() => {
+                Outcome::Success(NativeIpv6ClientAddr(ipv6))
+            },
+            ip => {
+                let status = Resolver::from_request(request)
+                    .config()
+                    .failure_status
+                    .unwrap_or(Status::Forbidden);
+
+                Outcome::Error((status, NativeIpv6ClientAddrError::NotNativeIpv6(ip)))
+            },
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use rocket::{
+    error,
+    http::Status,
+    outcome::Outcome,
+    request::{self, FromRequest, Request},
+};
+
+use crate::{ClientAddr, Resolver};
+
+/// The request guard used for disabling header trust of the `ClientAddr` request guards on a route, e.g. a webhook which is called directly rather than through the proxy. The `ClientAddr` request guards after it get the address of the TCP peer and ignore every header, no matter how the `Resolver` is configured.
+///
+/// It must come before the `ClientAddr` request guards, because request guards are run from left to right. If a `ClientAddr` request guard has already been run, it fails with `Status::InternalServerError` instead of being ignored silently.
+///
+/// ```rust
+/// use rocket::post;
+/// use rocket_client_addr::{ClientAddr, NoHeaderTrust};
+///
+/// #[post("/webhook")]
+/// fn webhook(_no_header_trust: NoHeaderTrust, client_addr: &ClientAddr) -> String {
+///     client_addr.to_string()
+/// }
+/// ```
+///
+/// It is also the way to short-circuit the resolution for a health check route of a load balancer, which gets the address of the TCP peer unconditionally, even if the `Resolver` is configured to reject some headers (e.g. `Config::strict_parsing`):
+///
+/// ```rust
+/// use rocket::get;
+/// use rocket_client_addr::{ClientAddr, NoHeaderTrust};
+///
+/// #[get("/health")]
+/// fn health(_no_header_trust: NoHeaderTrust, client_addr: &ClientAddr) -> String {
+///     format!("OK {client_addr}")
+/// }
+/// ```
+///
+/// After it has run, `CachedClientAddr`, `ClientAddr::resolve` and `ClientAddrInfo` get the address of the TCP peer as well. `ClientAddrFairing` runs before any request guard, so the address it has cached is not used on a route with `NoHeaderTrust`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct NoHeaderTrust;
+
+/// Whether the `ClientAddr` request guards trust headers, decided by the first one of them or `NoHeaderTrust`. It is one of `UNDECIDED`, `TRUSTED` and `DISABLED`.
+struct HeaderTrust(AtomicU8);
+
+const UNDECIDED: u8 = 0;
+const TRUSTED: u8 = 1;
+const DISABLED: u8 = 2;
+
+/// The `ClientAddr` of the TCP peer, used when header trust is disabled.
+struct PeerClientAddr(Option<ClientAddr>);
+
+impl NoHeaderTrust {
+    /// Get the header trust state of a request.
+    #[inline]
+    fn state<'r>(request: &'r Request<'_>) -> &'r AtomicU8 {
+        &request.local_cache(|| HeaderTrust(AtomicU8::new(UNDECIDED))).0
+    }
+
+    /// Check whether header trust is disabled for a request. After it is called, header trust cannot be disabled anymore.
+    #[inline]
+    pub(crate) fn is_set(request: &Request<'_>) -> bool {
+        match Self::state(request).compare_exchange(
+            UNDECIDED,
+            TRUSTED,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        ) {
+            Ok(_) => false,
+            Err(state) => state == DISABLED,
+        }
+    }
+
+    /// Check whether header trust has been disabled for a request, without deciding it, so that header trust can still be disabled afterwards.
+    #[inline]
+    pub(crate) fn is_disabled(request: &Request<'_>) -> bool {
+        Self::state(request).load(Ordering::Relaxed) == DISABLED
+    }
+
+    /// Get the `ClientAddr` of the TCP peer of a request.
+    #[inline]
+    pub(crate) fn peer_client_addr<'r>(request: &'r Request<'_>) -> Option<&'r ClientAddr> {
+        request
+            .local_cache(|| {
+                PeerClientAddr(request.remote().map(|addr| {
+                    Resolver::from_request(request).finish(ClientAddr { ip: addr.ip() })
+                }))
+            })
+            .0
+            .as_ref()
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for NoHeaderTrust {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        if let Err(TRUSTED) = Self::state(request).compare_exchange(
+            UNDECIDED,
+            DISABLED,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        ) {
+            error!("`NoHeaderTrust` must come before the `ClientAddr` request guards.");
+
+            return Outcome::Error((Status::InternalServerError, ()));
+        }
+
+        Outcome::Success(NoHeaderTrust)
+    }
+}
@@ -0,0 +1,41 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// The raw octets of an IP address.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Octets {
+    /// The 4 octets of an IPv4 address.
+    V4([u8; 4]),
+    /// The 16 octets of an IPv6 address, including an IPv4-mapped IPv6 address.
+    V6([u8; 16]),
+}
+
+impl Octets {
+    /// Get the octets as a slice, which has 4 or 16 bytes.
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        match self {
+            Octets::V4(octets) => octets,
+            Octets::V6(octets) => octets,
+        }
+    }
+}
+
+impl From<IpAddr> for Octets {
+    #[inline]
+    fn from(ip: IpAddr) -> Self {
+        match ip {
+            IpAddr::V4(ipv4) => Octets::V4(ipv4.octets()),
+            IpAddr::V6(ipv6) => Octets::V6(ipv6.octets()),
+        }
+    }
+}
+
+impl From<Octets> for IpAddr {
+    #[inline]
+    fn from(octets: Octets) -> Self {
+        match octets {
+            Octets::V4(octets) => IpAddr::V4(Ipv4Addr::from(octets)),
+            Octets::V6(octets) => IpAddr::V6(Ipv6Addr::from(octets)),
+        }
+    }
+}
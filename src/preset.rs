@@ -0,0 +1,91 @@
+use std::net::Ipv4Addr;
+
+use crate::{Config, IpNet, IpNetSet, RealIpMode, Resolver, ResolverBuilder, SourceKind, Strategy};
+
+/// The source ranges of the Google Front Ends, from which Google Cloud load balancers connect to backends.
+const GCP_FRONT_END_RANGES: IpNetSet = IpNetSet::from_static(&[
+    IpNet::new_v4(Ipv4Addr::new(35, 191, 0, 0), 16),
+    IpNet::new_v4(Ipv4Addr::new(130, 211, 0, 0), 22),
+]);
+
+/// Configurations for platforms whose load balancers have a known layout of `X-Forwarded-For`, so that the right strategy can be picked without knowing how the platform builds the header.
+///
+/// Every preset ignores `X-Real-IP` (see `RealIpMode::Ignore`), which these load balancers pass through from the client, and uses the TCP peer if it is not the load balancer. The application must be reachable only through the load balancer, otherwise a client inside the private network can forge the address.
+///
+/// ```rust
+/// use rocket_client_addr::Preset;
+///
+/// let resolver =
+///     Preset::GcpHttpLb.builder().canonicalize_mapped(true).build();
+///
+/// let rocket = rocket::build().manage(resolver);
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Preset {
+    /// An AWS Application Load Balancer, which appends the address of its peer to `X-Forwarded-For` (`X-Forwarded-For: <supplied>, <client>`) and connects to targets from its private address in the VPC. The rightmost entry is used.
+    AwsAlb,
+    /// A Google Cloud external HTTP(S) load balancer, which appends the address of its peer and then its own address to `X-Forwarded-For` (`X-Forwarded-For: <supplied>, <client>, <load balancer>`) and connects to backends from the Google Front End ranges (`35.191.0.0/16` and `130.211.0.0/22`), which are trusted. The second rightmost entry is used.
+    GcpHttpLb,
+    /// A single reverse proxy like nginx on the same host or in the private network, which is the only hop, so the leftmost entry of `X-Forwarded-For` is used. The TCP peer is trusted only if it is a local address.
+    ///
+    /// It is safe only if the proxy overwrites `X-Forwarded-For` with the address of its peer (`proxy_set_header X-Forwarded-For $remote_addr;` in nginx). If the proxy appends to the header instead (`$proxy_add_x_forwarded_for`), a client can prepend any address and it is used, so use `Strategy::RightmostUntrusted` (the default `Resolver`) in that case.
+    SingleProxy,
+}
+
+impl Preset {
+    /// Get the configuration of this preset.
+    pub fn config(self) -> Config {
+        let (strategy, trusted_proxies) = match self {
+            Preset::AwsAlb => (
+                Strategy::Rightmost {
+                    skip: 0,
+                },
+                IpNetSet::new(),
+            ),
+            Preset::GcpHttpLb => (
+                Strategy::Rightmost {
+                    skip: 1,
+                },
+                GCP_FRONT_END_RANGES,
+            ),
+            Preset::SingleProxy => (
+                Strategy::Leftmost {
+                    verify_trusted_suffix: false,
+                },
+                IpNetSet::new(),
+            ),
+        };
+
+        Config {
+            sources: Some(vec![
+                SourceKind::UntrustedRemote,
+                SourceKind::ForwardedChain,
+                SourceKind::Remote,
+            ]),
+            trusted_proxies,
+            strategy,
+            real_ip_mode: RealIpMode::Ignore,
+            ..Config::default()
+        }
+    }
+
+    /// Create a builder of a resolver from this preset, for adjusting other options.
+    #[inline]
+    pub fn builder(self) -> ResolverBuilder {
+        ResolverBuilder::from(self.config())
+    }
+}
+
+impl From<Preset> for Config {
+    #[inline]
+    fn from(preset: Preset) -> Self {
+        preset.config()
+    }
+}
+
+impl From<Preset> for Resolver {
+    #[inline]
+    fn from(preset: Preset) -> Self {
+        Resolver::new(preset.config())
+    }
+}
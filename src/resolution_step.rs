@@ -0,0 +1,83 @@
+use std::net::IpAddr;
+
+use crate::AddrSource;
+
+/// What happened to a source during a resolution.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum StepVerdict {
+    /// The address of the source is the IP address of the client.
+    Accepted,
+    /// The source holds no usable address, so the next source is tried.
+    Absent,
+    /// The TCP peer is a local address or a trusted proxy, so the headers are tried.
+    Trusted,
+    /// The address was discarded by `Config::reject_nonroutable_from_headers`, so the next source is tried.
+    NonRoutable,
+    /// The address is an IPv6 site-local address rejected by `Config::reject_site_local_from_headers`, which fails the resolution.
+    SiteLocal,
+    /// The header was rejected by `Config::strict_parsing`, which fails the resolution.
+    Malformed,
+    /// The suffix verification of `Strategy::Leftmost` failed without `Config::untrusted_suffix_fallback`, which fails the resolution.
+    UntrustedSuffix,
+}
+
+/// A step of a resolution traced by `Resolver::try_resolve_traced`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ResolutionStep {
+    /// The source which was tried.
+    pub source:  AddrSource,
+    /// The address found in the source, if any. For the forwarded chain, it is the address selected by `Config::strategy`.
+    pub ip:      Option<IpAddr>,
+    /// What happened to the source.
+    pub verdict: StepVerdict,
+}
+
+/// The steps recorded during a resolution, or nothing if tracing is disabled. The primary source, which is the first accepted one, is always recorded.
+pub(crate) struct Trace {
+    steps:   Option<Vec<ResolutionStep>>,
+    primary: Option<AddrSource>,
+}
+
+impl Trace {
+    #[inline]
+    pub(crate) const fn disabled() -> Trace {
+        Trace {
+            steps:   None,
+            primary: None,
+        }
+    }
+
+    #[inline]
+    pub(crate) const fn enabled() -> Trace {
+        Trace {
+            steps:   Some(Vec::new()),
+            primary: None,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn push(&mut self, source: AddrSource, ip: Option<IpAddr>, verdict: StepVerdict) {
+        if verdict == StepVerdict::Accepted && self.primary.is_none() {
+            self.primary = Some(source);
+        }
+
+        if let Some(steps) = self.steps.as_mut() {
+            steps.push(ResolutionStep {
+                source,
+                ip,
+                verdict,
+            });
+        }
+    }
+
+    /// Get the primary source, i.e. the first accepted one.
+    #[inline]
+    pub(crate) fn primary(&self) -> Option<AddrSource> {
+        self.primary
+    }
+
+    #[inline]
+    pub(crate) fn into_steps(self) -> Vec<ResolutionStep> {
+        self.steps.unwrap_or_default()
+    }
+}
@@ -0,0 +1,1081 @@
+use std::{
+    borrow::Cow,
+    net::{IpAddr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+};
+
+use rocket::{futures::FutureExt, http::Status, request::Request};
+
+use crate::{
+    client_addr::{ipv6_scope, is_local_ip},
+    forwarded,
+    resolution_step::{ResolutionStep, StepVerdict, Trace},
+    AddrFamily, AddrSource, CdnHeader, CdnLayer, ClientAddr, ClientAddrError, Config, CustomSource,
+    IpNetSet, Ipv6Scope, RealIpMode, SourceKind, Strategy, TrustChecker,
+};
+
+/// The resolver used by the `ClientAddr` request guard for getting an IP address from a client.
+///
+/// Put it into the managed state of Rocket to change the default behavior. Without it, `Resolver::default()` is used, which is built from `Config::default()`.
+///
+/// ```rust
+/// use rocket_client_addr::{CdnHeader, IpNetSet, Resolver, Strategy};
+///
+/// let resolver = Resolver::builder()
+///     .trust_cdn_header(CdnHeader::Cloudflare)
+///     .trust_proxies("10.0.0.0/8".parse().into_iter().collect::<IpNetSet>())
+///     .strategy(Strategy::RightmostUntrusted)
+///     .canonicalize_mapped(true)
+///     .build();
+///
+/// let rocket = rocket::build().manage(resolver);
+/// ```
+///
+/// The IP address is resolved from the following sources in order. This is the only precedence of the crate: `ClientRealAddr` uses it too, with a fixed configuration. Apart from the first three overrides, the order can be changed by `Config::sources` (see `SourceKind`).
+///
+/// 1. The TCP peer, if `Config::prefer_tls_peer` is enabled (with the `tls` feature) and Rocket terminates TLS. No other source is used in this case.
+/// 1. The TCP peer, if a header equals a sentinel value of `Config::remote_sentinels`. No other source is used in this case.
+/// 1. The header configured by Rocket's `ip_header`, then the TCP peer, if `Config::real_ip_mode` is `RealIpMode::Defer`. No other source is used in this case.
+/// 1. The CDN headers trusted by `Config::trusted_cdn_headers`, or the CDN header detected by `ClientAddrFairing::auto_detect` if there is none. If the configuration has a way to verify the TCP peer (`Config::trusted_proxies`, `Config::trusted_if` or `Config::trust_checker`), they are used only if the TCP peer is unknown or verified to be trusted.
+/// 1. The TCP peer, if it is neither a local address nor a trusted proxy (`Config::trusted_proxies`, `Config::trusted_if` and `Config::trust_checker`), unless `Config::always_use_headers` is enabled.
+/// 1. The source configured by `Config::custom_source`.
+/// 1. The address selected by `Config::strategy` in the first `X-Forwarded-For` header. By default, it is the rightmost address which is neither a local address nor a trusted proxy. Empty tokens are skipped, and quotes and the port of an entry are stripped.
+/// 1. The address selected by `Config::strategy` in the `Forwarded` headers (RFC 7239), if there is no `X-Forwarded-For` header. `for=unknown` and obfuscated identifiers (see `ForwardedFor`) are never selected, and like invalid entries, they stop the walk of `Strategy::RightmostUntrusted`.
+/// 1. The header configured by Rocket's `ip_header` (`X-Real-IP` by default), unless `Config::real_ip_mode` is `RealIpMode::Ignore`. Note that `ip_header` can be changed or disabled in `Rocket.toml`, in which case this is a different header or nothing. Its value is parsed like an entry of `X-Forwarded-For`, so a port is stripped.
+/// 1. The TCP peer.
+/// 1. The address configured by `Config::fallback`, if any of the above fails.
+///
+/// The TCP peer can be unknown, e.g. in some serverless adapters. In that case, the headers are the only sources and the resolution fails only if none of them holds a valid IP address.
+///
+/// Rocket 0.5 does not serve HTTP/3, so an HTTP/3 (QUIC) client reaches Rocket through a proxy which terminates QUIC and forwards the request over TCP, and it is resolved like any other client of that proxy, from the forwarding headers which the proxy adds. Rocket exposes no metadata of a QUIC connection, so there is nothing to prefer over the headers like `Config::prefer_tls_peer` does for TLS.
+#[derive(Debug, Clone, Default)]
+pub struct Resolver {
+    config: Config,
+}
+
+/// An address resolved by a `Resolver`, along with its primary source and whether it is trustworthy: it is the TCP peer, or it is taken from a header by a trust-walking strategy (`Strategy::RightmostUntrusted` or `Strategy::Leftmost` with the suffix verification) or from a CDN header or the header configured by Rocket's `ip_header` (`RealIpMode::Fallback`), while the TCP peer is known and verified to be trusted. `Config::always_use_headers` does not verify a TCP peer, and `Config::prefer_family` and `Config::custom_source` make an address from headers untrustworthy.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Resolution {
+    pub(crate) client_addr: ClientAddr,
+    pub(crate) source:      AddrSource,
+    pub(crate) trusted:     bool,
+}
+
+impl Resolver {
+    /// Create a resolver with a configuration.
+    #[inline]
+    pub fn new(config: Config) -> Resolver {
+        Resolver { config }
+    }
+
+    /// Create a builder of a resolver, whose defaults are the same as `Config::default()`.
+    #[inline]
+    pub fn builder() -> ResolverBuilder {
+        ResolverBuilder::default()
+    }
+
+    /// Get the configuration.
+    #[inline]
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Resolve the IP address of the client of a request. `Config::trust_checker` is not used because it is asynchronous. See `Resolver::resolve_async`.
+    ///
+    /// It returns `None` if the resolution fails, including the errors reported by `Resolver::try_resolve`.
+    #[inline]
+    pub fn resolve(&self, request: &Request<'_>) -> Option<ClientAddr> {
+        self.try_resolve(request).ok().flatten()
+    }
+
+    /// Resolve the IP address of the client of a request, awaiting `Config::trust_checker` for the TCP peer if it is set.
+    ///
+    /// It returns `None` if the resolution fails, including the errors reported by `Resolver::try_resolve_async`.
+    #[inline]
+    pub async fn resolve_async(&self, request: &Request<'_>) -> Option<ClientAddr> {
+        self.try_resolve_async(request).await.ok().flatten()
+    }
+
+    /// Resolve the IP address of the client of a request like `Resolver::resolve`, but report a header rejected by the configuration (e.g. `Config::strict_parsing`) as an error. `Ok(None)` means no source holds a usable IP address.
+    #[inline]
+    pub fn try_resolve(
+        &self,
+        request: &Request<'_>,
+    ) -> Result<Option<ClientAddr>, ClientAddrError> {
+        Ok(self.try_resolve_with_trust(request)?.map(|resolution| resolution.client_addr))
+    }
+
+    /// Resolve like `Resolver::try_resolve`, along with the primary source and the trustworthiness of the address. See `Resolution`.
+    #[inline]
+    pub(crate) fn try_resolve_with_trust(
+        &self,
+        request: &Request<'_>,
+    ) -> Result<Option<Resolution>, ClientAddrError> {
+        let is_verified_peer = self.is_verified_peer_sync(request);
+
+        self.resolve_with_trust(request, is_verified_peer, None)
+    }
+
+    /// Resolve the IP address of the client of a request like `Resolver::resolve`, and trace every source which is tried, in order, with what happened to it. It is slower than `Resolver::resolve`, so it is meant for diagnostics, e.g. an endpoint which explains why a client gets its IP address.
+    ///
+    /// ```rust
+    /// use rocket::{catch, Request};
+    /// use rocket_client_addr::Resolver;
+    ///
+    /// #[catch(403)]
+    /// fn forbidden(request: &Request<'_>) -> String {
+    ///     let (client_addr, steps) = Resolver::default().try_resolve_traced(request);
+    ///
+    ///     format!("{client_addr:?} is forbidden\n{steps:#?}")
+    /// }
+    /// ```
+    pub fn try_resolve_traced(
+        &self,
+        request: &Request<'_>,
+    ) -> (Option<ClientAddr>, Vec<ResolutionStep>) {
+        let is_verified_peer = self.is_verified_peer_sync(request);
+
+        let mut trace = Trace::enabled();
+
+        let client_addr =
+            resolve(request, &self.config, is_verified_peer, None, &mut trace).ok().flatten();
+
+        (client_addr.and_then(|client_addr| self.accept(client_addr).ok()), trace.into_steps())
+    }
+
+    /// Resolve the IP address of the client of a request like `Resolver::resolve_async`, but report a header rejected by the configuration (e.g. `Config::strict_parsing`) as an error. `Ok(None)` means no source holds a usable IP address.
+    #[inline]
+    pub async fn try_resolve_async(
+        &self,
+        request: &Request<'_>,
+    ) -> Result<Option<ClientAddr>, ClientAddrError> {
+        let resolution = self.resolve_async_with_trust(request, false).await?;
+
+        Ok(resolution.map(|resolution| resolution.client_addr))
+    }
+
+    /// Resolve like `Resolver::try_resolve_async`, along with the primary source and the trustworthiness of the address. See `Resolution`.
+    ///
+    /// If `auto_detect` is `true` and `Config::trusted_cdn_headers` is empty, the CDN header detected by `CdnHeader::detect` is used as the only trusted CDN header (see `ClientAddrFairing::auto_detect`). It is used at the step of `SourceKind::CdnHeaders` like a trusted CDN header, so the precedence of the sources is the same.
+    pub(crate) async fn resolve_async_with_trust(
+        &self,
+        request: &Request<'_>,
+        auto_detect: bool,
+    ) -> Result<Option<Resolution>, ClientAddrError> {
+        let is_verified_peer = self.is_verified_peer(request, true).await;
+
+        let detected_cdn_header = if auto_detect && self.config.trusted_cdn_headers.is_empty() {
+            CdnHeader::detect(request)
+        } else {
+            None
+        };
+
+        self.resolve_with_trust(request, is_verified_peer, detected_cdn_header)
+    }
+
+    fn resolve_with_trust(
+        &self,
+        request: &Request<'_>,
+        is_verified_peer: bool,
+        detected_cdn_header: Option<CdnHeader>,
+    ) -> Result<Option<Resolution>, ClientAddrError> {
+        let mut trace = Trace::disabled();
+
+        let Some(client_addr) =
+            resolve(request, &self.config, is_verified_peer, detected_cdn_header, &mut trace)?
+        else {
+            return Ok(None);
+        };
+
+        let client_addr = self.accept(client_addr)?;
+
+        // every source which gives an address records it as accepted
+        let source = trace.primary().unwrap_or(AddrSource::Fallback);
+
+        let config = &self.config;
+
+        // the CDN layers after the primary source are verified by their ranges
+        let trusted = match source {
+            AddrSource::Remote => true,
+            AddrSource::Fallback | AddrSource::CustomSource => false,
+            AddrSource::CdnHeader(_) => is_verified_peer,
+            AddrSource::RealIp => is_verified_peer && config.real_ip_mode == RealIpMode::Fallback,
+            AddrSource::ForwardedFor | AddrSource::Forwarded => {
+                let is_trust_walking = matches!(
+                    config.strategy,
+                    Strategy::RightmostUntrusted
+                        | Strategy::Leftmost {
+                            verify_trusted_suffix: true,
+                        }
+                );
+
+                is_verified_peer && is_trust_walking && config.prefer_family.is_none()
+            },
+        };
+
+        Ok(Some(Resolution {
+            client_addr,
+            source,
+            trusted,
+        }))
+    }
+
+    /// Check whether the TCP peer of a request is known and verified to be trusted, i.e. it is a local address, or it is trusted by `Config::trusted_proxies`, `Config::trusted_if` or, if `use_trust_checker` is `true`, `Config::trust_checker`. The checker is not awaited for a TCP peer which is trusted in another way.
+    ///
+    /// Every resolution verifies the TCP peer here, once.
+    async fn is_verified_peer(&self, request: &Request<'_>, use_trust_checker: bool) -> bool {
+        let Some(addr) = request.remote() else {
+            return false;
+        };
+
+        let config = &self.config;
+
+        let ip = addr.ip();
+
+        if is_local_ip(&ip)
+            || config.trusted_proxies.contains(&ip)
+            || config.trusted_if.as_ref().map(|p| p.test(request)).unwrap_or(false)
+        {
+            return true;
+        }
+
+        match &config.trust_checker {
+            Some(trust_checker) if use_trust_checker => trust_checker.is_trusted(ip).await,
+            _ => false,
+        }
+    }
+
+    /// `Resolver::is_verified_peer` without `Config::trust_checker`, which awaits nothing, so it completes when it is polled once.
+    #[inline]
+    fn is_verified_peer_sync(&self, request: &Request<'_>) -> bool {
+        self.is_verified_peer(request, false).now_or_never().unwrap_or(false)
+    }
+
+    /// Check whether the headers of a request can be used, i.e. the TCP peer is unknown, because the headers are the only sources then, or it is verified without `Config::trust_checker` (see `Resolver::is_verified_peer`), or `Config::always_use_headers` is enabled.
+    pub(crate) fn is_trusted_peer(&self, request: &Request<'_>) -> bool {
+        request.remote().is_none()
+            || self.config.always_use_headers
+            || self.is_verified_peer_sync(request)
+    }
+
+    /// Finish a resolved address and check it by `Config::reject_private_result`.
+    #[inline]
+    fn accept(&self, client_addr: ClientAddr) -> Result<ClientAddr, ClientAddrError> {
+        let client_addr = self.finish(client_addr);
+
+        if self.config.reject_private_result && is_local_ip(&client_addr.canonical_ip()) {
+            return Err(ClientAddrError::PrivateAddress(client_addr.ip));
+        }
+
+        Ok(client_addr)
+    }
+
+    #[inline]
+    pub(crate) fn finish(&self, mut client_addr: ClientAddr) -> ClientAddr {
+        if self.config.canonicalize_mapped {
+            client_addr.ip = match client_addr.nat64_ipv4() {
+                Some(ipv4) => IpAddr::V4(ipv4),
+                None => client_addr.canonical_ip(),
+            };
+        }
+
+        client_addr
+    }
+
+    #[inline]
+    pub(crate) fn from_request<'r>(request: &'r Request<'_>) -> Cow<'r, Resolver> {
+        match request.rocket().state::<Resolver>() {
+            Some(resolver) => Cow::Borrowed(resolver),
+            None => Cow::Owned(Resolver::default()),
+        }
+    }
+}
+
+impl From<Config> for Resolver {
+    #[inline]
+    fn from(config: Config) -> Self {
+        Resolver::new(config)
+    }
+}
+
+/// A builder of `Resolver`. See `Config` for the meaning of each option.
+#[derive(Debug, Clone, Default)]
+pub struct ResolverBuilder {
+    config: Config,
+}
+
+impl From<Config> for ResolverBuilder {
+    #[inline]
+    fn from(config: Config) -> Self {
+        ResolverBuilder { config }
+    }
+}
+
+impl ResolverBuilder {
+    /// Set the sources of the IP address of a client, in order.
+    #[inline]
+    pub fn sources(mut self, sources: Vec<SourceKind>) -> Self {
+        self.config.sources = Some(sources);
+
+        self
+    }
+
+    /// Trust a CDN header. Headers are checked in the order they are added.
+    #[inline]
+    pub fn trust_cdn_header(mut self, cdn_header: CdnHeader) -> Self {
+        self.config.trusted_cdn_headers.push(cdn_header);
+
+        self
+    }
+
+    /// Add a CDN layer in front of the trusted proxies. Layers are matched in the order they are added.
+    #[inline]
+    pub fn cdn_layer(mut self, cdn_layer: CdnLayer) -> Self {
+        self.config.cdn_layers.push(cdn_layer);
+
+        self
+    }
+
+    /// Accept a comma-separated list in a CDN header.
+    #[inline]
+    pub fn cdn_header_tolerate_list(mut self, tolerate_list: bool) -> Self {
+        self.config.cdn_header_tolerate_list = tolerate_list;
+
+        self
+    }
+
+    /// Set the trusted proxies.
+    #[inline]
+    pub fn trust_proxies(mut self, proxies: IpNetSet) -> Self {
+        self.config.trusted_proxies = proxies;
+
+        self
+    }
+
+    /// Trust the TCP peer when `predicate` returns `true`.
+    #[inline]
+    pub fn trusted_if<F: Fn(&Request<'_>) -> bool + Send + Sync + 'static>(
+        mut self,
+        predicate: F,
+    ) -> Self {
+        self.config = self.config.trusted_if(predicate);
+
+        self
+    }
+
+    /// Trust the TCP peer when `trust_checker` answers `true`.
+    #[inline]
+    pub fn trust_checker(mut self, trust_checker: Arc<dyn TrustChecker>) -> Self {
+        self.config.trust_checker = Some(trust_checker);
+
+        self
+    }
+
+    /// Set the custom source.
+    #[inline]
+    pub fn custom_source(mut self, custom_source: CustomSource) -> Self {
+        self.config.custom_source = Some(custom_source);
+
+        self
+    }
+
+    /// Set the strategy for the `X-Forwarded-For` header.
+    #[inline]
+    pub fn strategy(mut self, strategy: Strategy) -> Self {
+        self.config.strategy = strategy;
+
+        self
+    }
+
+    /// Set the family to prefer when the forwarded chain has addresses of both families.
+    #[inline]
+    pub fn prefer_family(mut self, family: AddrFamily) -> Self {
+        self.config.prefer_family = Some(family);
+
+        self
+    }
+
+    /// Fall back to `Strategy::RightmostUntrusted` when the suffix verification of `Strategy::Leftmost` fails.
+    #[inline]
+    pub fn untrusted_suffix_fallback(mut self, fallback: bool) -> Self {
+        self.config.untrusted_suffix_fallback = fallback;
+
+        self
+    }
+
+    /// Convert an IPv4-mapped IPv6 address, or an IPv4-embedded IPv6 address with the NAT64 well-known prefix, to an IPv4 address.
+    #[inline]
+    pub fn canonicalize_mapped(mut self, canonicalize_mapped: bool) -> Self {
+        self.config.canonicalize_mapped = canonicalize_mapped;
+
+        self
+    }
+
+    /// Reject an `X-Forwarded-For` header which has an invalid entry.
+    #[inline]
+    pub fn strict_parsing(mut self, strict_parsing: bool) -> Self {
+        self.config.strict_parsing = strict_parsing;
+
+        self
+    }
+
+    /// Collapse consecutive identical entries of the forwarded chain.
+    #[inline]
+    pub fn collapse_duplicate_hops(mut self, collapse: bool) -> Self {
+        self.config.collapse_duplicate_hops = collapse;
+
+        self
+    }
+
+    /// Discard IPv6 link-local and unique local addresses taken from headers.
+    #[inline]
+    pub fn reject_nonroutable_from_headers(mut self, reject: bool) -> Self {
+        self.config.reject_nonroutable_from_headers = reject;
+
+        self
+    }
+
+    /// Reject IPv6 site-local addresses taken from headers as errors.
+    #[inline]
+    pub fn reject_site_local_from_headers(mut self, reject: bool) -> Self {
+        self.config.reject_site_local_from_headers = reject;
+
+        self
+    }
+
+    /// Reject a resolved address which is local or private as an error.
+    #[inline]
+    pub fn reject_private_result(mut self, reject: bool) -> Self {
+        self.config.reject_private_result = reject;
+
+        self
+    }
+
+    /// Set how to use the header configured by Rocket's `ip_header`.
+    #[inline]
+    pub fn real_ip_mode(mut self, real_ip_mode: RealIpMode) -> Self {
+        self.config.real_ip_mode = real_ip_mode;
+
+        self
+    }
+
+    /// Set the address used when no source holds a usable IP address.
+    #[inline]
+    pub fn fallback(mut self, fallback: IpAddr) -> Self {
+        self.config.fallback = Some(fallback);
+
+        self
+    }
+
+    /// Set the status with which the `ClientAddr` request guards forward or fail.
+    #[inline]
+    pub fn failure_status(mut self, status: Status) -> Self {
+        self.config.failure_status = Some(status);
+
+        self
+    }
+
+    /// Use the TCP peer and ignore every header when a header named `name` equals `value`.
+    #[inline]
+    pub fn remote_sentinel<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+        self.config.remote_sentinels.push((name.into(), value.into()));
+
+        self
+    }
+
+    /// Use the forwarding headers even if the TCP peer is not trusted.
+    #[inline]
+    pub fn always_use_headers(mut self, always_use_headers: bool) -> Self {
+        self.config.always_use_headers = always_use_headers;
+
+        self
+    }
+
+    /// Use the TCP peer directly when Rocket terminates TLS.
+    #[cfg(feature = "tls")]
+    #[inline]
+    pub fn prefer_tls_peer(mut self, prefer_tls_peer: bool) -> Self {
+        self.config.prefer_tls_peer = prefer_tls_peer;
+
+        self
+    }
+
+    /// Build the resolver.
+    #[inline]
+    pub fn build(self) -> Resolver {
+        Resolver::new(self.config)
+    }
+}
+
+/// Get the first `X-Forwarded-For` header of a request, unless it is empty.
+#[inline]
+fn first_forwarded_for<'r>(request: &'r Request<'_>) -> Option<&'r str> {
+    request
+        .headers()
+        .get("x-forwarded-for")
+        .next() /* Only fetch the first one. */
+        .filter(|v| !v.trim().is_empty()) /* An empty header is treated as absent. */
+}
+
+/// Parse an entry of `X-Forwarded-For` or the value of the header configured by Rocket's `ip_header` tolerantly. Some misbehaving proxies quote it (`"203.0.113.5"`), enclose an IPv6 address in brackets (`"[2001:db8::1]"`), append the port (`203.0.113.5:443` or `[2001:db8::1]:443`) or append the zone identifier of a link-local IPv6 address (`fe80::1%eth0` or `[fe80::1%eth0]:443`), which are stripped.
+pub(crate) fn parse_ip_token(token: &str) -> Option<IpAddr> {
+    let token = token.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(token);
+
+    let without_zone;
+
+    let token = match token.split_once('%') {
+        Some((ip, zone)) => {
+            let (zone, rest) = zone.split_at(zone.find(']').unwrap_or(zone.len()));
+
+            if !ip.contains(':') || !is_zone_id(zone) {
+                return None;
+            }
+
+            without_zone = format!("{ip}{rest}");
+
+            without_zone.as_str()
+        },
+        None => token,
+    };
+
+    if let Ok(ip) = token.parse::<IpAddr>() {
+        return Some(ip);
+    }
+
+    if let Ok(addr) = token.parse::<SocketAddr>() {
+        return Some(addr.ip());
+    }
+
+    let ipv6 = token.strip_prefix('[')?.strip_suffix(']')?;
+
+    ipv6.parse::<Ipv6Addr>().ok().map(IpAddr::V6)
+}
+
+/// A zone identifier of an IPv6 address (RFC 6874), like an interface name or a numeric scope ID.
+#[inline]
+fn is_zone_id(s: &str) -> bool {
+    let is_zone_char = |b: u8| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~');
+
+    !s.is_empty() && s.bytes().all(is_zone_char)
+}
+
+/// Get the address of the header configured by Rocket's `ip_header`, parsed by `parse_ip_token`, unlike `Request::real_ip`.
+#[inline]
+pub(crate) fn real_ip_header(request: &Request<'_>) -> Option<IpAddr> {
+    let name = request.rocket().config().ip_header.as_ref()?;
+
+    parse_ip_token(request.headers().get_one(name.as_str())?.trim())
+}
+
+/// Get the forwarded chain of a request from the first `X-Forwarded-For` header, or from the `Forwarded` headers if there is no `X-Forwarded-For` header. An entry which is not an address is `None`. Empty tokens of `X-Forwarded-For` are skipped, but if `strict_parsing` is `true`, they and any other invalid entry of `X-Forwarded-For` are an error, and so is a `Forwarded` header which is not well-formed.
+pub(crate) fn forwarded_chain(
+    request: &Request<'_>,
+    strict_parsing: bool,
+) -> Result<Vec<Option<IpAddr>>, ClientAddrError> {
+    match first_forwarded_for(request) {
+        Some(forwarded_for_ip) => {
+            let chain: Vec<Option<IpAddr>> = forwarded_for_ip
+                .split(',')
+                .map(str::trim)
+                .filter(|v| strict_parsing || !v.is_empty()) /* Empty tokens are skipped. */
+                .map(parse_ip_token)
+                .collect();
+
+            if strict_parsing && chain.contains(&None) {
+                return Err(ClientAddrError::MalformedHeader);
+            }
+
+            Ok(chain)
+        },
+        None => {
+            if strict_parsing
+                && !request.headers().get("forwarded").all(forwarded::is_well_formed)
+            {
+                return Err(ClientAddrError::MalformedHeader);
+            }
+
+            Ok(forwarded::parse_chain(request)
+                .into_iter()
+                .map(|node| node.and_then(|node| node.addr()))
+                .collect())
+        },
+    }
+}
+
+/// Walk a forwarded chain from right to left and get the first address which is not trusted. If every address is trusted, the leftmost one is used. The walk stops at an entry which is not an address.
+#[inline]
+fn rightmost_untrusted(
+    chain: &[Option<IpAddr>],
+    is_trusted: impl Fn(&IpAddr) -> bool,
+) -> Option<IpAddr> {
+    rightmost_untrusted_index(chain, is_trusted).and_then(|i| chain[i])
+}
+
+/// Get the index of the entry selected by `rightmost_untrusted`.
+pub(crate) fn rightmost_untrusted_index(
+    chain: &[Option<IpAddr>],
+    is_trusted: impl Fn(&IpAddr) -> bool,
+) -> Option<usize> {
+    let mut last_index = None;
+
+    for (i, ip) in chain.iter().enumerate().rev() {
+        let Some(ip) = ip else { break };
+
+        last_index = Some(i);
+
+        if !is_trusted(ip) {
+            break;
+        }
+    }
+
+    last_index
+}
+
+/// Check whether an address of a forwarded chain is a hop which is trusted, i.e. a local address or a trusted proxy (`Config::trusted_proxies`).
+#[inline]
+pub(crate) fn is_trusted_hop(config: &Config, ip: &IpAddr) -> bool {
+    is_local_ip(ip) || config.trusted_proxies.contains(ip)
+}
+
+/// Get the nearest globally routable address of `family` at or to the left of the selected address in a forwarded chain, or the selected address if there is none.
+fn prefer_family(chain: &[Option<IpAddr>], selected: IpAddr, family: AddrFamily) -> IpAddr {
+    let end = chain.iter().rposition(|ip| *ip == Some(selected)).map_or(0, |i| i + 1);
+
+    chain[..end]
+        .iter()
+        .rev()
+        .flatten()
+        .find(|ip| AddrFamily::of(ip) == family && !is_local_ip(ip))
+        .copied()
+        .unwrap_or(selected)
+}
+
+/// Check whether an address taken from a header is acceptable by `Config::reject_nonroutable_from_headers`.
+#[inline]
+fn is_acceptable_from_header(config: &Config, ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V6(ipv6) if config.reject_nonroutable_from_headers => {
+            !matches!(ipv6_scope(ipv6), Ipv6Scope::LinkLocal | Ipv6Scope::UniqueLocal)
+        },
+        _ => true,
+    }
+}
+
+/// Check whether an address taken from a header is rejected by `Config::reject_site_local_from_headers`.
+#[inline]
+fn check_site_local(config: &Config, ip: &IpAddr) -> Result<(), ClientAddrError> {
+    match ip {
+        IpAddr::V6(ipv6)
+            if config.reject_site_local_from_headers
+                && ipv6.segments()[0] & 0xFFC0 == 0xFEC0 =>
+        {
+            Err(ClientAddrError::SiteLocalAddress(*ipv6))
+        },
+        _ => Ok(()),
+    }
+}
+
+/// Record a source which is a header and get its address if it is acceptable by `Config::reject_nonroutable_from_headers`. It fails if the address is rejected by `Config::reject_site_local_from_headers`.
+#[inline]
+fn from_header(
+    config: &Config,
+    trace: &mut Trace,
+    source: AddrSource,
+    ip: Option<IpAddr>,
+) -> Result<Option<IpAddr>, ClientAddrError> {
+    match ip {
+        Some(ip) => {
+            if let Err(error) = check_site_local(config, &ip) {
+                trace.push(source, Some(ip), StepVerdict::SiteLocal);
+
+                return Err(error);
+            }
+
+            if is_acceptable_from_header(config, &ip) {
+                trace.push(source, Some(ip), StepVerdict::Accepted);
+
+                Ok(Some(ip))
+            } else {
+                trace.push(source, Some(ip), StepVerdict::NonRoutable);
+
+                Ok(None)
+            }
+        },
+        None => {
+            trace.push(source, None, StepVerdict::Absent);
+
+            Ok(None)
+        },
+    }
+}
+
+/// Get the address of the header configured by Rocket's `ip_header`, unless `Config::real_ip_mode` ignores it.
+#[inline]
+fn real_ip(
+    request: &Request<'_>,
+    config: &Config,
+    trace: &mut Trace,
+) -> Result<Option<IpAddr>, ClientAddrError> {
+    match config.real_ip_mode {
+        RealIpMode::Ignore => Ok(None),
+        _ => from_header(config, trace, AddrSource::RealIp, real_ip_header(request)),
+    }
+}
+
+/// Record the TCP peer as the last source.
+#[inline]
+fn remote(trace: &mut Trace, remote_ip: Option<IpAddr>) -> Option<ClientAddr> {
+    let verdict = if remote_ip.is_some() { StepVerdict::Accepted } else { StepVerdict::Absent };
+
+    trace.push(AddrSource::Remote, remote_ip, verdict);
+
+    remote_ip.map(|ip| ClientAddr { ip })
+}
+
+/// Record `Config::fallback` as the last source, which is used when every other source fails.
+#[inline]
+fn fallback(config: &Config, trace: &mut Trace) -> Option<ClientAddr> {
+    config.fallback.map(|ip| {
+        trace.push(AddrSource::Fallback, Some(ip), StepVerdict::Accepted);
+
+        ClientAddr { ip }
+    })
+}
+
+/// Replace an address which is an edge of a CDN layer (`Config::cdn_layers`) with the address in the header of that layer, repeatedly for chained CDNs. The address is kept if the header is absent or not acceptable.
+fn through_cdn_layers(
+    request: &Request<'_>,
+    config: &Config,
+    trace: &mut Trace,
+    mut ip: IpAddr,
+) -> Result<IpAddr, ClientAddrError> {
+    // every layer can be passed at most once, unless the headers form a loop
+    for _ in 0..config.cdn_layers.len() {
+        let Some(layer) = config.cdn_layers.iter().find(|layer| layer.ranges.contains(&ip)) else {
+            break;
+        };
+
+        let header_ip = layer.header.get_ip(request, config.cdn_header_tolerate_list);
+
+        match from_header(config, trace, AddrSource::CdnHeader(layer.header), header_ip)? {
+            Some(header_ip) => ip = header_ip,
+            None => break,
+        }
+    }
+
+    Ok(ip)
+}
+
+/// Emit a warning for the forwarding headers sent by an untrusted TCP peer, which are ignored. When the trusted proxies are configured, every such header is likely a spoof attempt. Otherwise, the TCP peer may be a proxy with a public address which should be trusted, which is hinted once per process. The edges of a CDN layer (`Config::cdn_layers`) are expected to send them.
+#[cfg(feature = "tracing")]
+fn warn_ignored_headers(request: &Request<'_>, config: &Config, peer: IpAddr) {
+    static PUBLIC_PEER_HINT: std::sync::Once = std::sync::Once::new();
+
+    if config.cdn_layers.iter().any(|layer| layer.ranges.contains(&peer)) {
+        return;
+    }
+
+    let has_trusted_proxies = !config.trusted_proxies.is_empty()
+        || config.trusted_if.is_some()
+        || config.trust_checker.is_some();
+
+    for name in ["X-Forwarded-For", "Forwarded"] {
+        for value in request.headers().get(name) {
+            if has_trusted_proxies {
+                tracing::warn!(
+                    %peer,
+                    header = name,
+                    value,
+                    "ignored a forwarding header sent by an untrusted peer"
+                );
+            } else {
+                PUBLIC_PEER_HINT.call_once(|| {
+                    tracing::warn!(
+                        %peer,
+                        header = name,
+                        value,
+                        "ignored a forwarding header sent by a public peer; if it is a proxy, \
+                         trust it by `Config::trusted_proxies` or enable \
+                         `Config::always_use_headers` (this is reported once)"
+                    );
+                });
+            }
+        }
+    }
+}
+
+/// Check whether a header of a request equals a sentinel value of `Config::remote_sentinels`. Multiple lines of the header are combined into one value, so a line sent by the client cannot be a sentinel when a proxy appends another line.
+#[inline]
+fn is_sentinel(request: &Request<'_>, config: &Config) -> bool {
+    config.remote_sentinels.iter().any(|(name, sentinel)| {
+        let mut values = request.headers().get(name);
+
+        match values.next() {
+            Some(first) => {
+                let combined = values.fold(first.trim().to_string(), |mut combined, value| {
+                    combined.push_str(", ");
+                    combined.push_str(value.trim());
+
+                    combined
+                });
+
+                combined.eq_ignore_ascii_case(sentinel.trim())
+            },
+            None => false,
+        }
+    })
+}
+
+/// The result of the forwarded chain.
+enum ChainResult {
+    Found(IpAddr),
+    /// Try the next source.
+    Next,
+    /// The resolution fails.
+    Fail,
+}
+
+/// Select the address of the client from the forwarded chain by `Config::strategy`.
+fn select_from_chain(
+    request: &Request<'_>,
+    config: &Config,
+    is_trusted: impl Fn(&IpAddr) -> bool,
+    trace: &mut Trace,
+) -> Result<ChainResult, ClientAddrError> {
+    let chain_source = if first_forwarded_for(request).is_some() {
+        AddrSource::ForwardedFor
+    } else {
+        trace.push(AddrSource::ForwardedFor, None, StepVerdict::Absent);
+
+        AddrSource::Forwarded
+    };
+
+    let mut chain = match forwarded_chain(request, config.strict_parsing) {
+        Ok(chain) => chain,
+        Err(error) => {
+            trace.push(chain_source, None, StepVerdict::Malformed);
+
+            return Err(error);
+        },
+    };
+
+    if config.collapse_duplicate_hops {
+        chain.dedup();
+    }
+
+    if chain.is_empty() {
+        trace.push(chain_source, None, StepVerdict::Absent);
+
+        return Ok(ChainResult::Next);
+    }
+
+    let last_ip = match config.strategy {
+        Strategy::RightmostUntrusted => rightmost_untrusted(&chain, is_trusted),
+        Strategy::FirstPublic => chain.iter().flatten().find(|ip| !is_local_ip(ip)).copied(),
+        Strategy::Rightmost {
+            skip,
+        } => {
+            if skip >= chain.len() {
+                trace.push(chain_source, None, StepVerdict::Absent);
+
+                return Ok(ChainResult::Next);
+            }
+
+            chain[chain.len() - 1 - skip]
+        },
+        Strategy::Leftmost {
+            verify_trusted_suffix,
+        } => {
+            let leftmost_ip = chain[0];
+
+            if verify_trusted_suffix
+                && !chain[1..].iter().all(|ip| ip.map(|ip| is_trusted(&ip)).unwrap_or(false))
+            {
+                if !config.untrusted_suffix_fallback {
+                    trace.push(chain_source, leftmost_ip, StepVerdict::UntrustedSuffix);
+
+                    return Ok(ChainResult::Fail);
+                }
+
+                rightmost_untrusted(&chain, is_trusted)
+            } else {
+                leftmost_ip
+            }
+        },
+    };
+
+    let last_ip = match (config.prefer_family, last_ip) {
+        (Some(family), Some(ip)) if AddrFamily::of(&ip) != family => {
+            Some(prefer_family(&chain, ip, family))
+        },
+        _ => last_ip,
+    };
+
+    match from_header(config, trace, chain_source, last_ip)? {
+        Some(ip) => Ok(ChainResult::Found(ip)),
+        None => Ok(ChainResult::Next),
+    }
+}
+
+/// The `resolve_client_addr` span of `tracing`, which is entered during a resolution and records the total length of the forwarding headers (`header_bytes`) and the duration of the resolution in microseconds (`elapsed_us`), e.g. for detecting clients which slow it down with oversized headers. Nothing is measured if the span is disabled.
+#[cfg(feature = "tracing")]
+struct ResolutionTiming {
+    span:  tracing::span::EnteredSpan,
+    start: Option<std::time::Instant>,
+}
+
+#[cfg(feature = "tracing")]
+impl ResolutionTiming {
+    fn start(request: &Request<'_>) -> ResolutionTiming {
+        let span = tracing::debug_span!(
+            "resolve_client_addr",
+            header_bytes = tracing::field::Empty,
+            elapsed_us = tracing::field::Empty
+        );
+
+        if span.is_disabled() {
+            return ResolutionTiming { span: span.entered(), start: None };
+        }
+
+        let header_bytes: usize = ["X-Forwarded-For", "Forwarded"]
+            .into_iter()
+            .flat_map(|name| request.headers().get(name))
+            .map(str::len)
+            .sum();
+
+        span.record("header_bytes", header_bytes);
+
+        ResolutionTiming { span: span.entered(), start: Some(std::time::Instant::now()) }
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for ResolutionTiming {
+    fn drop(&mut self) {
+        if let Some(start) = self.start {
+            let elapsed_us = u64::try_from(start.elapsed().as_micros()).unwrap_or(u64::MAX);
+
+            self.span.record("elapsed_us", elapsed_us);
+        }
+    }
+}
+
+/// `is_verified_peer` is the result of `Resolver::is_verified_peer`. `detected_cdn_header` is used instead of `Config::trusted_cdn_headers` if it is set (see `ClientAddrFairing::auto_detect`).
+fn resolve(
+    request: &Request<'_>,
+    config: &Config,
+    is_verified_peer: bool,
+    detected_cdn_header: Option<CdnHeader>,
+    trace: &mut Trace,
+) -> Result<Option<ClientAddr>, ClientAddrError> {
+    #[cfg(feature = "tracing")]
+    let _timing = ResolutionTiming::start(request);
+
+    #[cfg(feature = "tls")]
+    if config.prefer_tls_peer && request.rocket().config().tls_enabled() {
+        if let Some(addr) = request.remote() {
+            return Ok(remote(trace, Some(addr.ip())));
+        }
+    }
+
+    if is_sentinel(request, config) {
+        return Ok(remote(trace, request.remote().map(|addr| addr.ip()))
+            .or_else(|| fallback(config, trace)));
+    }
+
+    if config.real_ip_mode == RealIpMode::Defer {
+        if let Some(ip) = from_header(config, trace, AddrSource::RealIp, real_ip_header(request))? {
+            return Ok(Some(ClientAddr { ip }));
+        }
+
+        return Ok(remote(trace, request.remote().map(|addr| addr.ip()))
+            .or_else(|| fallback(config, trace)));
+    }
+
+    let is_trusted = |ip: &IpAddr| is_trusted_hop(config, ip);
+
+    let remote_ip = request.remote().map(|addr| addr.ip());
+
+    let can_verify_peer = !config.trusted_proxies.is_empty()
+        || config.trusted_if.is_some()
+        || config.trust_checker.is_some();
+
+    let is_trusted_peer = is_verified_peer || config.always_use_headers;
+
+    // the CDN headers of a TCP peer which can be verified but is not trusted are ignored
+    let is_cdn_peer = remote_ip.is_none() || !can_verify_peer || is_trusted_peer;
+
+    let cdn_headers = match &detected_cdn_header {
+        Some(cdn_header) => std::slice::from_ref(cdn_header),
+        None => config.trusted_cdn_headers.as_slice(),
+    };
+
+    let sources = config.sources.as_deref().unwrap_or(SourceKind::DEFAULT_ORDER);
+
+    for source in sources {
+        let ip = match source {
+            SourceKind::CdnHeaders => {
+                let mut found = None;
+
+                for cdn_header in cdn_headers.iter().filter(|_| is_cdn_peer) {
+                    let ip = cdn_header.get_ip(request, config.cdn_header_tolerate_list);
+
+                    found = from_header(config, trace, AddrSource::CdnHeader(*cdn_header), ip)?;
+
+                    if found.is_some() {
+                        break;
+                    }
+                }
+
+                found
+            },
+            SourceKind::UntrustedRemote => match remote_ip {
+                Some(ip) => {
+                    if !is_trusted_peer {
+                        #[cfg(feature = "tracing")]
+                        warn_ignored_headers(request, config, ip);
+
+                        remote(trace, Some(ip));
+
+                        let ip = through_cdn_layers(request, config, trace, ip)?;
+
+                        return Ok(Some(ClientAddr { ip }));
+                    }
+
+                    trace.push(AddrSource::Remote, Some(ip), StepVerdict::Trusted);
+
+                    None
+                },
+                None => None,
+            },
+            SourceKind::CustomSource => match config.custom_source.as_ref() {
+                Some(custom_source) => {
+                    let ip = custom_source.get_ip(request);
+
+                    from_header(config, trace, AddrSource::CustomSource, ip)?
+                },
+                None => None,
+            },
+            SourceKind::Custom(custom_source) => {
+                from_header(config, trace, AddrSource::CustomSource, custom_source.get_ip(request))?
+            },
+            SourceKind::ForwardedChain => {
+                match select_from_chain(request, config, is_trusted, trace)? {
+                    ChainResult::Found(ip) => {
+                        Some(through_cdn_layers(request, config, trace, ip)?)
+                    },
+                    ChainResult::Next => None,
+                    ChainResult::Fail => return Ok(fallback(config, trace)),
+                }
+            },
+            SourceKind::RealIp => real_ip(request, config, trace)?,
+            SourceKind::Remote => remote(trace, remote_ip).map(|client_addr| client_addr.ip),
+        };
+
+        if let Some(ip) = ip {
+            return Ok(Some(ClientAddr { ip }));
+        }
+    }
+
+    Ok(fallback(config, trace))
+}
@@ -0,0 +1,147 @@
+use std::{
+    borrow::Cow,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    net::IpAddr,
+};
+
+use rocket::{
+    http::{Cookie, Status},
+    outcome::Outcome,
+    request::{self, FromRequest, Request},
+};
+
+use crate::{client_addr_outcome, ClientAddr, ClientAddrError, IpNet};
+
+/// The configuration of `SessionBoundAddr`. Put it into the managed state of Rocket to change the default behavior.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct SessionBinding {
+    /// The name of the cookie which holds the network of the client. `client_addr_binding` by default.
+    pub cookie_name: Cow<'static, str>,
+    /// The prefix length of the network of an IPv4 client. `32` by default.
+    pub ipv4_prefix: u8,
+    /// The prefix length of the network of an IPv6 client, which tolerates the privacy extensions rotating the low 64 bits of the address. `64` by default.
+    pub ipv6_prefix: u8,
+}
+
+impl Default for SessionBinding {
+    #[inline]
+    fn default() -> Self {
+        SessionBinding {
+            cookie_name: Cow::Borrowed("client_addr_binding"),
+            ipv4_prefix: 32,
+            ipv6_prefix: 64,
+        }
+    }
+}
+
+impl SessionBinding {
+    /// Normalize the IP address of a client to its network, which is compared with the cookie. The canonical IP address (see `ClientAddr::canonical_ip`) is used, and its prefix length is `ipv4_prefix` or `ipv6_prefix`, clamped to the bit length of the address.
+    #[inline]
+    pub fn normalize(&self, client_addr: &ClientAddr) -> IpNet {
+        match client_addr.canonical_ip() {
+            IpAddr::V4(ip) => IpNet::new_v4(ip, self.ipv4_prefix.min(32)),
+            IpAddr::V6(ip) => IpNet::new_v6(ip, self.ipv6_prefix.min(128)),
+        }
+    }
+
+    /// Create the cookie which binds the session to the network of a client, e.g. when the client logs in. It is `HttpOnly`. Add it by `CookieJar::add_private`, which encrypts and authenticates it with the `secret_key` of Rocket, otherwise `SessionBoundAddr` rejects it.
+    #[inline]
+    pub fn cookie(&self, client_addr: &ClientAddr) -> Cookie<'static> {
+        Cookie::build((self.cookie_name.clone(), self.normalize(client_addr).to_string()))
+            .http_only(true)
+            .build()
+    }
+
+    #[inline]
+    fn from_request<'r>(request: &'r Request<'_>) -> Cow<'r, SessionBinding> {
+        match request.rocket().state::<SessionBinding>() {
+            Some(binding) => Cow::Borrowed(binding),
+            None => Cow::Owned(SessionBinding::default()),
+        }
+    }
+}
+
+/// Errors of the `SessionBoundAddr` request guard.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum SessionBoundAddrError {
+    /// The IP address of the client cannot be resolved. See `ClientAddrError`.
+    ClientAddr(ClientAddrError),
+    /// The network of the client is not the one in the cookie, which may be a session hijack, or the cookie is not a valid private cookie, which may be tampered with.
+    Mismatch,
+}
+
+impl Display for SessionBoundAddrError {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionBoundAddrError::ClientAddr(error) => Display::fmt(error, f),
+            SessionBoundAddrError::Mismatch => {
+                f.write_str("the client is not in the network bound to the session")
+            },
+        }
+    }
+}
+
+impl Error for SessionBoundAddrError {}
+
+/// The request guard used for binding a session to the network of a client, as a lightweight protection against session hijacking.
+///
+/// When a session is created, set the cookie created by `SessionBinding::cookie`. Then, this request guard resolves the `ClientAddr` of every request, normalizes it by `SessionBinding::normalize` (the `/32` of an IPv4 client or the `/64` of an IPv6 client by default) and compares it with the cookie.
+///
+/// * If the cookie is absent, it forwards with `Status::Unauthorized`.
+/// * If the network does not match the cookie, or if the cookie cannot be decrypted with the `secret_key` of Rocket (e.g. it is rewritten by the client), it fails with `Status::Forbidden` and `SessionBoundAddrError::Mismatch`.
+///
+/// The cookie is a private cookie of Rocket, so a client holding a stolen session cannot rewrite it to its own network.
+///
+/// ```rust
+/// use rocket::{get, http::CookieJar, post};
+/// use rocket_client_addr::{ClientAddr, SessionBinding, SessionBoundAddr};
+///
+/// #[post("/login")]
+/// fn login(client_addr: &ClientAddr, cookies: &CookieJar<'_>) {
+///     cookies.add_private(SessionBinding::default().cookie(client_addr));
+/// }
+///
+/// #[get("/account")]
+/// fn account(session_bound_addr: SessionBoundAddr) -> String {
+///     session_bound_addr.0.to_string()
+/// }
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct SessionBoundAddr(pub ClientAddr);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for SessionBoundAddr {
+    type Error = SessionBoundAddrError;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let client_addr = match client_addr_outcome(request).await {
+            Outcome::Success(client_addr) => client_addr,
+            Outcome::Error((status, error)) => {
+                return Outcome::Error((status, SessionBoundAddrError::ClientAddr(error)));
+            },
+            Outcome::Forward(status) => return Outcome::Forward(status),
+        };
+
+        let binding = SessionBinding::from_request(request);
+
+        let cookies = request.cookies();
+
+        if cookies.get(&binding.cookie_name).is_none() {
+            return Outcome::Forward(Status::Unauthorized);
+        }
+
+        // `get_private` is `None` if the cookie cannot be decrypted or authenticated
+        let net = cookies
+            .get_private(&binding.cookie_name)
+            .and_then(|cookie| cookie.value().parse::<IpNet>().ok());
+
+        match net {
+            Some(net) if net == binding.normalize(&client_addr) => {
+                Outcome::Success(SessionBoundAddr(client_addr))
+            },
+            _ => Outcome::Error((Status::Forbidden, SessionBoundAddrError::Mismatch)),
+        }
+    }
+}
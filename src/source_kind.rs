@@ -0,0 +1,49 @@
+use crate::CustomSource;
+
+/// A step of the pipeline of a `Resolver`, which walks `Config::sources` in order and uses the first source which holds a usable IP address. See `Resolver` for what each step does by default.
+///
+/// A source after `SourceKind::UntrustedRemote` is consulted only if the TCP peer is trusted, so that position decides which headers can be forged by a client.
+///
+/// ```rust
+/// use rocket_client_addr::{Resolver, SourceKind};
+///
+/// // prefer `X-Real-IP` (set by the trusted proxy) to `X-Forwarded-For`
+/// let resolver = Resolver::builder()
+///     .sources(vec![
+///         SourceKind::CdnHeaders,
+///         SourceKind::UntrustedRemote,
+///         SourceKind::RealIp,
+///         SourceKind::ForwardedChain,
+///         SourceKind::Remote,
+///     ])
+///     .build();
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum SourceKind {
+    /// The CDN headers of `Config::trusted_cdn_headers`, in order.
+    CdnHeaders,
+    /// The TCP peer, if it is neither a local address nor a trusted proxy (`Config::trusted_proxies`, `Config::trusted_if` and `Config::trust_checker`).
+    UntrustedRemote,
+    /// The source of `Config::custom_source`.
+    CustomSource,
+    /// Another custom source, e.g. a cookie set by the proxy of a specific deployment.
+    Custom(CustomSource),
+    /// The address selected by `Config::strategy` in the first `X-Forwarded-For` header, or in the `Forwarded` headers if there is no `X-Forwarded-For` header.
+    ForwardedChain,
+    /// The header configured by Rocket's `ip_header`, unless `Config::real_ip_mode` is `RealIpMode::Ignore`.
+    RealIp,
+    /// The TCP peer.
+    Remote,
+}
+
+impl SourceKind {
+    /// The sources used when `Config::sources` is `None`.
+    pub const DEFAULT_ORDER: &'static [SourceKind] = &[
+        SourceKind::CdnHeaders,
+        SourceKind::UntrustedRemote,
+        SourceKind::CustomSource,
+        SourceKind::ForwardedChain,
+        SourceKind::RealIp,
+        SourceKind::Remote,
+    ];
+}
@@ -0,0 +1,225 @@
+//! The IANA IPv4 and IPv6 Special-Purpose Address Registries (RFC 6890).
+//!
+//! Each block records whether `is_local_ip` treats it as a local address, i.e. an address which never identifies a client on the Internet, so it is not taken from a TCP peer or a forwarding header as the IP address of a client, and whether it is reserved for documentation.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::IpNet;
+
+#[inline]
+const fn v4(a: u8, b: u8, c: u8, d: u8, prefix_len: u8) -> IpNet {
+    IpNet::new_v4(Ipv4Addr::new(a, b, c, d), prefix_len)
+}
+
+#[inline]
+const fn v6(segments: [u16; 8], prefix_len: u8) -> IpNet {
+    let [a, b, c, d, e, f, g, h] = segments;
+
+    IpNet::new_v6(Ipv6Addr::new(a, b, c, d, e, f, g, h), prefix_len)
+}
+
+/// A block of the special-purpose address registries.
+#[derive(Clone, Copy)]
+struct Block {
+    net:           IpNet,
+    /// Whether `is_local_ip` treats the block as local.
+    local:         bool,
+    /// Whether the block is reserved for documentation.
+    documentation: bool,
+}
+
+#[inline]
+const fn block(net: IpNet, local: bool) -> Block {
+    Block {
+        net,
+        local,
+        documentation: false,
+    }
+}
+
+/// A documentation block, which is treated as local.
+#[inline]
+const fn documentation(net: IpNet) -> Block {
+    Block {
+        net,
+        local: true,
+        documentation: true,
+    }
+}
+
+/// <https://www.iana.org/assignments/iana-ipv4-special-registry/>
+const IPV4_BLOCKS: [Block; 24] = [
+    // "This network" (RFC 791), which is invalid as a source address except `0.0.0.0`
+    block(v4(0, 0, 0, 0, 8), true),
+    // "This host on this network" (RFC 1122)
+    block(v4(0, 0, 0, 0, 32), true),
+    // Private-Use (RFC 1918)
+    block(v4(10, 0, 0, 0, 8), true),
+    // Shared Address Space (RFC 6598), which is used by carrier-grade NATs
+    block(v4(100, 64, 0, 0, 10), false),
+    // Loopback (RFC 1122)
+    block(v4(127, 0, 0, 0, 8), true),
+    // Link Local (RFC 3927)
+    block(v4(169, 254, 0, 0, 16), true),
+    // Private-Use (RFC 1918)
+    block(v4(172, 16, 0, 0, 12), true),
+    // IETF Protocol Assignments (RFC 6890)
+    block(v4(192, 0, 0, 0, 24), true),
+    // IPv4 Service Continuity Prefix (RFC 7335)
+    block(v4(192, 0, 0, 0, 29), true),
+    // IPv4 dummy address (RFC 7600)
+    block(v4(192, 0, 0, 8, 32), true),
+    // Port Control Protocol Anycast (RFC 7723)
+    block(v4(192, 0, 0, 9, 32), true),
+    // Traversal Using Relays around NAT Anycast (RFC 8155)
+    block(v4(192, 0, 0, 10, 32), true),
+    // NAT64/DNS64 Discovery (RFC 7050)
+    block(v4(192, 0, 0, 170, 31), true),
+    // Documentation (TEST-NET-1) (RFC 5737)
+    documentation(v4(192, 0, 2, 0, 24)),
+    // AS112-v4 (RFC 7535)
+    block(v4(192, 31, 196, 0, 24), false),
+    // AMT (RFC 7450)
+    block(v4(192, 52, 193, 0, 24), false),
+    // Deprecated (6to4 Relay Anycast) (RFC 7526)
+    block(v4(192, 88, 99, 0, 24), false),
+    // Private-Use (RFC 1918)
+    block(v4(192, 168, 0, 0, 16), true),
+    // Direct Delegation AS112 Service (RFC 7534)
+    block(v4(192, 175, 48, 0, 24), false),
+    // Benchmarking (RFC 2544)
+    block(v4(198, 18, 0, 0, 15), true),
+    // Documentation (TEST-NET-2) (RFC 5737)
+    documentation(v4(198, 51, 100, 0, 24)),
+    // Documentation (TEST-NET-3) (RFC 5737)
+    documentation(v4(203, 0, 113, 0, 24)),
+    // Reserved (RFC 1112)
+    block(v4(240, 0, 0, 0, 4), true),
+    // Limited Broadcast (RFC 919)
+    block(v4(255, 255, 255, 255, 32), true),
+];
+
+/// <https://www.iana.org/assignments/iana-ipv6-special-registry/>
+const IPV6_BLOCKS: [Block; 23] = [
+    // Loopback Address (RFC 4291)
+    block(v6([0, 0, 0, 0, 0, 0, 0, 1], 128), true),
+    // Unspecified Address (RFC 4291)
+    block(v6([0, 0, 0, 0, 0, 0, 0, 0], 128), true),
+    // IPv4-mapped Address (RFC 4291)
+    block(v6([0, 0, 0, 0, 0, 0xFFFF, 0, 0], 96), false),
+    // IPv4-IPv6 Translat. (RFC 6052)
+    block(v6([0x64, 0xFF9B, 0, 0, 0, 0, 0, 0], 96), false),
+    // IPv4-IPv6 Translat. (RFC 8215)
+    block(v6([0x64, 0xFF9B, 1, 0, 0, 0, 0, 0], 48), false),
+    // Discard-Only Address Block (RFC 6666)
+    block(v6([0x100, 0, 0, 0, 0, 0, 0, 0], 64), false),
+    // IETF Protocol Assignments (RFC 2928)
+    block(v6([0x2001, 0, 0, 0, 0, 0, 0, 0], 23), false),
+    // TEREDO (RFC 4380)
+    block(v6([0x2001, 0, 0, 0, 0, 0, 0, 0], 32), false),
+    // Port Control Protocol Anycast (RFC 7723)
+    block(v6([0x2001, 1, 0, 0, 0, 0, 0, 1], 128), false),
+    // Traversal Using Relays around NAT Anycast (RFC 8155)
+    block(v6([0x2001, 1, 0, 0, 0, 0, 0, 2], 128), false),
+    // Benchmarking (RFC 5180)
+    block(v6([0x2001, 2, 0, 0, 0, 0, 0, 0], 48), true),
+    // AMT (RFC 7450)
+    block(v6([0x2001, 3, 0, 0, 0, 0, 0, 0], 32), false),
+    // AS112-v6 (RFC 7535)
+    block(v6([0x2001, 4, 0x112, 0, 0, 0, 0, 0], 48), false),
+    // Deprecated (previously ORCHID) (RFC 4843)
+    block(v6([0x2001, 0x10, 0, 0, 0, 0, 0, 0], 28), false),
+    // ORCHIDv2 (RFC 7343)
+    block(v6([0x2001, 0x20, 0, 0, 0, 0, 0, 0], 28), false),
+    // Drone Remote ID Protocol Entity Tags (DETs) Prefix (RFC 9374)
+    block(v6([0x2001, 0x30, 0, 0, 0, 0, 0, 0], 28), false),
+    // Documentation (RFC 3849)
+    documentation(v6([0x2001, 0xDB8, 0, 0, 0, 0, 0, 0], 32)),
+    // 6to4 (RFC 3056)
+    block(v6([0x2002, 0, 0, 0, 0, 0, 0, 0], 16), false),
+    // Direct Delegation AS112 Service (RFC 7534)
+    block(v6([0x2620, 0x4F, 0x8000, 0, 0, 0, 0, 0], 48), false),
+    // Documentation (RFC 9637)
+    documentation(v6([0x3FFF, 0, 0, 0, 0, 0, 0, 0], 20)),
+    // Segment Routing (SRv6) SIDs (RFC 9602)
+    block(v6([0x5F00, 0, 0, 0, 0, 0, 0, 0], 16), false),
+    // Unique-Local (RFC 4193)
+    block(v6([0xFC00, 0, 0, 0, 0, 0, 0, 0], 7), true),
+    // Link-Local Unicast (RFC 4291)
+    block(v6([0xFE80, 0, 0, 0, 0, 0, 0, 0], 10), true),
+];
+
+/// Get the first octets covered by any of `blocks`, so that most public addresses can be excluded without scanning the blocks.
+const fn first_octets(blocks: &[Block]) -> [bool; 256] {
+    let mut first_octets = [false; 256];
+
+    let mut i = 0;
+
+    while i < blocks.len() {
+        let net = blocks[i].net;
+
+        let first_octet = match net.addr() {
+            IpAddr::V4(addr) => addr.octets()[0],
+            IpAddr::V6(addr) => addr.octets()[0],
+        } as usize;
+
+        let count = if net.prefix_len() >= 8 { 1 } else { 1 << (8 - net.prefix_len()) };
+
+        let mut j = 0;
+
+        while j < count {
+            first_octets[first_octet + j] = true;
+
+            j += 1;
+        }
+
+        i += 1;
+    }
+
+    first_octets
+}
+
+const IPV4_FIRST_OCTETS: [bool; 256] = first_octets(&IPV4_BLOCKS);
+
+const IPV6_FIRST_OCTETS: [bool; 256] = first_octets(&IPV6_BLOCKS);
+
+#[inline]
+fn blocks(ip: &IpAddr) -> &'static [Block] {
+    let (first_octet, first_octets, blocks): (u8, _, &'static [Block]) = match ip {
+        IpAddr::V4(ip) => (ip.octets()[0], &IPV4_FIRST_OCTETS, &IPV4_BLOCKS),
+        IpAddr::V6(ip) => (ip.octets()[0], &IPV6_FIRST_OCTETS, &IPV6_BLOCKS),
+    };
+
+    if first_octets[first_octet as usize] {
+        blocks
+    } else {
+        &[]
+    }
+}
+
+/// Check whether `ip` is in any block of the special-purpose address registries.
+#[inline]
+pub(crate) fn is_special_purpose(ip: &IpAddr) -> bool {
+    blocks(ip).iter().any(|block| block.net.contains(ip))
+}
+
+/// Check whether `ip` is in a documentation block of the special-purpose address registries.
+#[inline]
+pub(crate) fn is_documentation(ip: &IpAddr) -> bool {
+    blocks(ip).iter().any(|block| block.documentation && block.net.contains(ip))
+}
+
+/// Check whether `ip` is in a block of the special-purpose address registries which is treated as local.
+#[inline]
+pub(crate) fn is_local_special_purpose(ip: &IpAddr) -> bool {
+    blocks(ip).iter().any(|block| block.local && block.net.contains(ip))
+}
+
+/// Look up `ip` in the special-purpose address registries in one pass. `None` if it is in no block, or whether any block which contains it is treated as local.
+#[inline]
+pub(crate) fn lookup(ip: &IpAddr) -> Option<bool> {
+    blocks(ip)
+        .iter()
+        .filter(|block| block.net.contains(ip))
+        .fold(None, |found, block| Some(found.unwrap_or(false) || block.local))
+}
@@ -0,0 +1,85 @@
+use std::net::{IpAddr, Ipv4Addr};
+
+use rocket::{
+    outcome::Outcome,
+    request::{self, FromRequest, Request},
+};
+
+use crate::{
+    client_addr_outcome, resolver::real_ip_header, AddrSource, CachedClientAddr, NoHeaderTrust,
+};
+
+/// The request guard used for getting an IP address from a client along with whether it is trustworthy, e.g. for logging every request while making security decisions only on trusted addresses. It never fails.
+///
+/// If the `ClientAddr` request guard would succeed, its address is used, unless it is taken from `Config::fallback`. `trusted` is `true` only if the address cannot be forged by the client: it is the TCP peer, or it is taken from a header while the TCP peer is known and verified to be a local address or a trusted proxy (`Config::trusted_proxies`, `Config::trusted_if` or `Config::trust_checker`), by `Strategy::RightmostUntrusted`, `Strategy::Leftmost` with the suffix verification, a CDN header or the header configured by Rocket's `ip_header` with `RealIpMode::Fallback`. An address selected by another strategy, by `Config::prefer_family` or `Config::custom_source`, by `RealIpMode::Defer`, from a TCP peer trusted only by `Config::always_use_headers`, or from a request without a TCP peer is not trusted.
+///
+/// Otherwise, the address is a best effort: the TCP peer, then the header configured by Rocket's `ip_header`, then `0.0.0.0`, and `trusted` is `false`.
+///
+/// Both `ip` and `trusted` come from the resolution cached by the `ClientAddr` request guards (see `CachedClientAddr`), so the TCP peer is checked only once per request, even with `Config::trust_checker`.
+///
+/// ```rust
+/// use rocket::get;
+/// use rocket_client_addr::TrustedClientAddr;
+///
+/// #[get("/")]
+/// fn index(client_addr: TrustedClientAddr) -> &'static str {
+///     println!("{} (trusted: {})", client_addr.ip, client_addr.trusted);
+///
+///     if client_addr.trusted {
+///         "Hello, world!"
+///     } else {
+///         "Hello, stranger!"
+///     }
+/// }
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct TrustedClientAddr {
+    /// IP address from a client, or the best effort if it cannot be resolved.
+    pub ip:      IpAddr,
+    /// Whether `ip` is resolved from a source which cannot be forged by the client.
+    pub trusted: bool,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for TrustedClientAddr {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        if let Outcome::Success(client_addr) = client_addr_outcome(request).await {
+            // the TCP peer, if `NoHeaderTrust` is set
+            let resolution = if NoHeaderTrust::is_set(request) {
+                Some((AddrSource::Remote, true))
+            } else {
+                CachedClientAddr::get_resolution(request)
+                    .map(|resolution| (resolution.source, resolution.trusted))
+            };
+
+            match resolution {
+                Some((AddrSource::Fallback, _)) => (),
+                Some((_, trusted)) => {
+                    return Outcome::Success(TrustedClientAddr {
+                        ip: client_addr.ip,
+                        trusted,
+                    });
+                },
+                None => {
+                    return Outcome::Success(TrustedClientAddr {
+                        ip:      client_addr.ip,
+                        trusted: false,
+                    });
+                },
+            }
+        }
+
+        let ip = request
+            .remote()
+            .map(|addr| addr.ip())
+            .or_else(|| real_ip_header(request))
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+
+        Outcome::Success(TrustedClientAddr {
+            ip,
+            trusted: false,
+        })
+    }
+}
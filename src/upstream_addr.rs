@@ -0,0 +1,28 @@
+use std::net::IpAddr;
+
+use rocket::{
+    http::Status,
+    outcome::Outcome,
+    request::{self, FromRequest, Request},
+};
+
+/// The request guard used for getting the IP address of the TCP peer, which is the immediate upstream (e.g. the last proxy or load balancer) rather than the client when the application is behind a proxy.
+///
+/// It is never derived from a header. It forwards when the address of the peer is unknown.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct UpstreamAddr {
+    /// IP address of the TCP peer.
+    pub ip: IpAddr,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for UpstreamAddr {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        match request.remote() {
+            Some(addr) => Outcome::Success(UpstreamAddr { ip: addr.ip() }),
+            None => Outcome::Forward(Status::BadRequest),
+        }
+    }
+}
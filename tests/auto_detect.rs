@@ -0,0 +1,164 @@
+#[macro_use]
+extern crate rocket;
+
+use std::{
+    net::IpAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+};
+use rocket_client_addr::{
+    CdnHeader, ClientAddr, ClientAddrFairing, IpNetSet, Resolver, TrustChecker,
+};
+
+#[get("/")]
+fn index(client_addr: &ClientAddr) -> String {
+    client_addr.to_string()
+}
+
+fn client() -> Client {
+    let rocket = rocket::build()
+        .attach(ClientAddrFairing::new().auto_detect(true))
+        .mount("/", routes![index]);
+
+    Client::untracked(rocket).unwrap()
+}
+
+fn client_with(resolver: Resolver) -> Client {
+    let rocket = rocket::build()
+        .manage(resolver)
+        .attach(ClientAddrFairing::new().auto_detect(true))
+        .mount("/", routes![index]);
+
+    Client::untracked(rocket).unwrap()
+}
+
+fn get(client: &Client, headers: &[(&'static str, &'static str)]) -> String {
+    let mut request = client.get("/").remote("34.120.0.1:8000".parse().unwrap());
+
+    for (name, value) in headers {
+        request = request.header(Header::new(*name, *value));
+    }
+
+    let response = request.dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+
+    response.into_string().unwrap()
+}
+
+#[test]
+fn cloudflare() {
+    let headers = [
+        ("CF-Connecting-IP", "93.184.216.34"),
+        ("CF-Ray", "8a1b2c3d4e5f6a7b-LAX"),
+        ("X-Forwarded-For", "93.184.216.34"),
+    ];
+
+    assert_eq!("93.184.216.34", get(&client(), &headers));
+}
+
+#[test]
+fn fastly() {
+    let headers = [("Fastly-Client-IP", "2606:4700::1111"), ("X-Forwarded-For", "2606:4700::1111")];
+
+    assert_eq!("2606:4700::1111", get(&client(), &headers));
+}
+
+#[test]
+fn cloudfront() {
+    let headers = [
+        ("CloudFront-Viewer-Address", "93.184.216.34:46532"),
+        ("X-Amz-Cf-Id", "4Jb8fWd5ZwA2q0KqHzB8x1C9Z7LxQ4eB3yN6fA2vT1uR5sM0pK9jDw=="),
+    ];
+
+    assert_eq!("93.184.216.34", get(&client(), &headers));
+
+    let headers = [("CloudFront-Viewer-Address", "2606:4700:0:0:0:0:0:1111:46532")];
+
+    assert_eq!("2606:4700::1111", get(&client(), &headers));
+}
+
+#[test]
+fn azure() {
+    let headers = [
+        ("X-Azure-ClientIP", "1.1.1.1"),
+        ("X-Azure-SocketIP", "93.184.216.34"),
+        ("X-Azure-Ref", "0zxV+XAAAAABKMMOjBv2NT4TY6SQVjC0zV1NURURHRTA2MTkANjRhYjE"),
+    ];
+
+    assert_eq!("93.184.216.34", get(&client(), &headers));
+}
+
+#[test]
+fn detection_order() {
+    let headers = [("Fastly-Client-IP", "1.1.1.1"), ("CF-Connecting-IP", "93.184.216.34")];
+
+    assert_eq!("93.184.216.34", get(&client(), &headers));
+}
+
+#[test]
+fn no_cdn() {
+    assert_eq!("34.120.0.1", get(&client(), &[]));
+}
+
+#[test]
+fn untrusted_peer_is_not_verified() {
+    let resolver =
+        Resolver::builder().trust_proxies("104.16.0.0/13".parse().into_iter().collect()).build();
+
+    let headers = [("CF-Connecting-IP", "93.184.216.34")];
+
+    assert_eq!("34.120.0.1", get(&client_with(resolver), &headers));
+}
+
+#[test]
+fn trusted_peer_is_verified() {
+    let resolver = Resolver::builder()
+        .trust_proxies("34.120.0.0/16".parse().into_iter().collect::<IpNetSet>())
+        .build();
+
+    let headers = [("CF-Connecting-IP", "93.184.216.34")];
+
+    assert_eq!("93.184.216.34", get(&client_with(resolver), &headers));
+}
+
+#[test]
+fn configured_cdn_header_takes_precedence() {
+    let resolver = Resolver::builder().trust_cdn_header(CdnHeader::Fastly).build();
+
+    let headers = [("Fastly-Client-IP", "1.1.1.1"), ("CF-Connecting-IP", "93.184.216.34")];
+
+    assert_eq!("1.1.1.1", get(&client_with(resolver), &headers));
+}
+
+struct CountingChecker(AtomicUsize);
+
+#[rocket::async_trait]
+impl TrustChecker for CountingChecker {
+    async fn is_trusted(&self, _ip: IpAddr) -> bool {
+        self.0.fetch_add(1, Ordering::Relaxed);
+
+        true
+    }
+}
+
+#[test]
+fn trust_checker_is_awaited_once() {
+    let checker = Arc::new(CountingChecker(AtomicUsize::new(0)));
+
+    let client = client_with(Resolver::builder().trust_checker(checker.clone()).build());
+
+    assert_eq!("93.184.216.34", get(&client, &[("CF-Connecting-IP", "93.184.216.34")]));
+    assert_eq!(1, checker.0.load(Ordering::Relaxed));
+
+    // no CDN header is detected
+    assert_eq!("93.184.216.34", get(&client, &[("X-Forwarded-For", "93.184.216.34")]));
+    assert_eq!(2, checker.0.load(Ordering::Relaxed));
+}
@@ -0,0 +1,150 @@
+#[macro_use]
+extern crate rocket;
+
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    sync::Arc,
+};
+
+use rocket::{
+    fairing::AdHoc,
+    http::Header,
+    local::blocking::Client,
+    request::{FromRequest, Outcome, Request},
+};
+use rocket_client_addr::{CachedClientAddr, ClientAddr, ClientAddrFairing, Resolver, TrustChecker};
+
+struct CachedByOthers(Option<ClientAddr>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for CachedByOthers {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(CachedByOthers(CachedClientAddr::get(request).copied()))
+    }
+}
+
+#[get("/")]
+fn index(cached: CachedByOthers, client_addr: &ClientAddr) -> String {
+    assert_eq!(Some(*client_addr), cached.0);
+
+    client_addr.to_string()
+}
+
+#[get("/sync-first")]
+fn sync_first(cached: CachedByOthers, client_addr: &ClientAddr) -> String {
+    format!("{:?} {client_addr}", cached.0)
+}
+
+#[get("/async-first")]
+fn async_first(client_addr: &ClientAddr, cached: CachedByOthers) -> String {
+    format!("{:?} {client_addr}", cached.0)
+}
+
+#[test]
+fn fairing() {
+    let rocket = rocket::build()
+        .attach(ClientAddrFairing::new())
+        .attach(AdHoc::on_request("Check", |request, _| {
+            Box::pin(async move {
+                let cached = CachedClientAddr::get(request);
+
+                assert_eq!("93.184.216.34", cached.unwrap().to_string());
+            })
+        }))
+        .mount("/", routes![index]);
+
+    let client = Client::untracked(rocket).unwrap();
+
+    let response = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "93.184.216.34"))
+        .dispatch();
+
+    assert_eq!("93.184.216.34", response.into_string().unwrap());
+}
+
+#[test]
+fn guard() {
+    let client = Client::untracked(rocket::build().mount("/", routes![index])).unwrap();
+
+    let response = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "93.184.216.34"))
+        .dispatch();
+
+    assert_eq!("93.184.216.34", response.into_string().unwrap());
+}
+
+#[test]
+fn pinned_before_header_rewrites() {
+    let rewrite = || {
+        AdHoc::on_request("Rewrite", |request, _| {
+            Box::pin(async move {
+                request.replace_header(Header::new("X-Forwarded-For", "1.1.1.1"));
+            })
+        })
+    };
+
+    let get = |rocket| {
+        Client::untracked(rocket)
+            .unwrap()
+            .get("/")
+            .remote("10.0.0.1:8000".parse().unwrap())
+            .header(Header::new("X-Forwarded-For", "93.184.216.34"))
+            .dispatch()
+            .into_string()
+            .unwrap()
+    };
+
+    let rocket = rocket::build()
+        .attach(ClientAddrFairing::new())
+        .attach(rewrite())
+        .mount("/", routes![index]);
+
+    assert_eq!("93.184.216.34", get(rocket));
+
+    // attached after the rewrite, so it sees the rewritten header
+    let rocket = rocket::build()
+        .attach(rewrite())
+        .attach(ClientAddrFairing::new())
+        .mount("/", routes![index]);
+
+    assert_eq!("1.1.1.1", get(rocket));
+}
+
+#[test]
+fn trust_checker() {
+    struct StubChecker;
+
+    #[rocket::async_trait]
+    impl TrustChecker for StubChecker {
+        async fn is_trusted(&self, ip: IpAddr) -> bool {
+            ip == IpAddr::V4(Ipv4Addr::new(34, 120, 0, 7))
+        }
+    }
+
+    let resolver = Resolver::builder().trust_checker(Arc::new(StubChecker)).build();
+
+    let client = Client::untracked(
+        rocket::build().manage(resolver).mount("/", routes![sync_first, async_first]),
+    )
+    .unwrap();
+
+    let get = |uri: &'static str| {
+        client
+            .get(uri)
+            .remote("34.120.0.7:8000".parse().unwrap())
+            .header(Header::new("X-Forwarded-For", "93.184.216.34"))
+            .dispatch()
+            .into_string()
+            .unwrap()
+    };
+
+    // the synchronous resolution does not await the checker, but does not poison the guard either
+    assert_eq!("Some(34.120.0.7) 93.184.216.34", get("/sync-first"));
+    assert_eq!("Some(93.184.216.34) 93.184.216.34", get("/async-first"));
+}
@@ -0,0 +1,70 @@
+use rocket::{http::Header, local::blocking::Client};
+use rocket_client_addr::{candidate_ips, AddrSource, CdnHeader};
+
+fn candidates(
+    remote: Option<&str>,
+    headers: &[(&'static str, &'static str)],
+) -> Vec<(AddrSource, String)> {
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    let mut request = client.get("/");
+
+    if let Some(remote) = remote {
+        request = request.remote(format!("{remote}:8000").parse().unwrap());
+    }
+
+    for (name, value) in headers {
+        request = request.header(Header::new(*name, *value));
+    }
+
+    candidate_ips(request.inner())
+        .into_iter()
+        .map(|(source, ip)| (source, ip.to_string()))
+        .collect()
+}
+
+#[test]
+fn every_source() {
+    let candidates = candidates(Some("10.0.0.1"), &[
+        ("X-Forwarded-For", "93.184.216.34, 10.0.0.2"),
+        ("X-Forwarded-For", "1.1.1.1"),
+        ("Forwarded", "for=34.120.0.1, for=\"[2606:4700::1111]:443\""),
+        ("X-Real-IP", "34.120.0.2"),
+        ("CF-Connecting-IP", "1.0.0.1"),
+        ("Fastly-Client-IP", "1.0.0.2, 1.0.0.3"),
+    ]);
+
+    assert_eq!(
+        vec![
+            (AddrSource::Remote, "10.0.0.1".into()),
+            (AddrSource::CdnHeader(CdnHeader::Cloudflare), "1.0.0.1".into()),
+            (AddrSource::CdnHeader(CdnHeader::Fastly), "1.0.0.2".into()),
+            (AddrSource::RealIp, "34.120.0.2".into()),
+            (AddrSource::ForwardedFor, "93.184.216.34".into()),
+            (AddrSource::ForwardedFor, "10.0.0.2".into()),
+            (AddrSource::ForwardedFor, "1.1.1.1".into()),
+            (AddrSource::Forwarded, "34.120.0.1".into()),
+            (AddrSource::Forwarded, "2606:4700::1111".into()),
+        ],
+        candidates
+    );
+}
+
+#[test]
+fn invalid_and_duplicate_entries() {
+    let candidates = candidates(None, &[
+        ("X-Forwarded-For", "93.184.216.34, garbage, , 93.184.216.34:443"),
+        ("Forwarded", "for=unknown, for=_hidden, for=93.184.216.34"),
+        ("X-Real-IP", "not an address"),
+    ]);
+
+    assert_eq!(
+        vec![
+            (AddrSource::ForwardedFor, "93.184.216.34".into()),
+            (AddrSource::Forwarded, "93.184.216.34".into()),
+        ],
+        candidates
+    );
+
+    assert!(self::candidates(None, &[]).is_empty());
+}
@@ -0,0 +1,64 @@
+#[macro_use]
+extern crate rocket;
+
+use std::net::IpAddr;
+
+use rocket::{http::Header, local::blocking::Client};
+use rocket_client_addr::{Canonicalized, ClientAddr, Octets};
+
+fn client_addr(ip: &str) -> ClientAddr {
+    ClientAddr {
+        ip: ip.parse().unwrap()
+    }
+}
+
+#[get("/")]
+fn index(client_addr: Canonicalized) -> String {
+    client_addr.to_string()
+}
+
+#[test]
+fn accessors() {
+    let ipv4 = client_addr("93.184.216.34").canonicalized();
+    let mapped = client_addr("::ffff:93.184.216.34").canonicalized();
+
+    assert_eq!(ipv4, mapped);
+    assert_eq!("93.184.216.34".parse::<IpAddr>().unwrap(), mapped.ip);
+    assert_eq!(client_addr("93.184.216.34"), mapped.into_inner());
+
+    for canonicalized in [ipv4, mapped] {
+        assert_eq!("93.184.216.34", canonicalized.to_string());
+        assert_eq!("93.184.216.34", format!("{canonicalized:?}"));
+        assert_eq!(Some("93.184.216.34".parse().unwrap()), canonicalized.get_ipv4());
+        assert_eq!(Some("93.184.216.34".to_string()), canonicalized.get_ipv4_string());
+        assert_eq!(
+            "::ffff:93.184.216.34".parse::<std::net::Ipv6Addr>().unwrap(),
+            canonicalized.get_ipv6()
+        );
+        assert_eq!("::ffff:93.184.216.34", canonicalized.get_ipv6_string());
+        assert_eq!("93.184.216.34", canonicalized.to_canonical_string());
+        assert_eq!(Octets::V4([93, 184, 216, 34]), canonicalized.octets());
+        assert_eq!(0xFFFF_5DB8_D822, canonicalized.to_u128());
+        assert_eq!("93.184.216.0/24", canonicalized.enclosing_net(24).to_string());
+        assert_eq!(None, canonicalized.ipv6_scope());
+        assert_eq!("src=93.184.216.34", canonicalized.to_cef_field());
+    }
+
+    let ipv6 = client_addr("2606:4700::1111");
+
+    assert_eq!(ipv6, ipv6.canonicalized().into_inner());
+    assert_eq!("2606:4700::1111", Canonicalized::from(ipv6).to_string());
+}
+
+#[test]
+fn guard() {
+    let client = Client::untracked(rocket::build().mount("/", routes![index])).unwrap();
+
+    let response = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "::ffff:93.184.216.34"))
+        .dispatch();
+
+    assert_eq!("93.184.216.34", response.into_string().unwrap());
+}
@@ -0,0 +1,35 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+    Request,
+};
+use rocket_client_addr::{ClientAddr, Config, Resolver};
+
+#[catch(404)]
+async fn not_found(request: &Request<'_>) -> String {
+    ClientAddr::resolve(request).await.unwrap().to_string()
+}
+
+#[test]
+fn catcher() {
+    let resolver = Resolver::from(Config {
+        canonicalize_mapped: true,
+        ..Config::default()
+    });
+
+    let client =
+        Client::untracked(rocket::build().manage(resolver).register("/", catchers![not_found]))
+            .unwrap();
+
+    let response = client
+        .get("/missing")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "::ffff:93.184.216.34"))
+        .dispatch();
+
+    assert_eq!(Status::NotFound, response.status());
+    assert_eq!("93.184.216.34", response.into_string().unwrap());
+}
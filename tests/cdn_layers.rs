@@ -0,0 +1,157 @@
+use rocket::{http::Header, local::blocking::Client};
+use rocket_client_addr::{AddrSource, CdnHeader, CdnLayer, ClientAddr, IpNetSet, Resolver};
+
+const INTERNAL_PROXY: &str = "10.0.0.5";
+const CLOUDFLARE_EDGE: &str = "173.245.48.10";
+const FASTLY_EDGE: &str = "151.101.0.20";
+
+fn ranges(nets: &[&str]) -> IpNetSet {
+    nets.iter().map(|net| net.parse().unwrap()).collect()
+}
+
+/// Trust `X-Forwarded-For` from the internal proxies, then `CF-Connecting-IP` from Cloudflare.
+fn two_layers() -> Resolver {
+    Resolver::builder()
+        .cdn_layer(CdnLayer {
+            header: CdnHeader::Cloudflare, ranges: ranges(&["173.245.48.0/20"])
+        })
+        .build()
+}
+
+fn resolve(
+    resolver: &Resolver,
+    remote: &str,
+    headers: &[(&'static str, &'static str)],
+) -> Option<String> {
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    let mut request = client.get("/").remote(format!("{remote}:8000").parse().unwrap());
+
+    for (name, value) in headers {
+        request = request.header(Header::new(*name, *value));
+    }
+
+    resolver.resolve(request.inner()).map(|client_addr: ClientAddr| client_addr.to_string())
+}
+
+#[test]
+fn two_layers_setup() {
+    let resolver = two_layers();
+
+    // client -> Cloudflare -> internal proxy -> Rocket
+    assert_eq!(
+        Some("93.184.216.34".into()),
+        resolve(&resolver, INTERNAL_PROXY, &[
+            ("X-Forwarded-For", "93.184.216.34, 173.245.48.10"),
+            ("CF-Connecting-IP", "93.184.216.34"),
+        ])
+    );
+
+    // Cloudflare sees the client claiming another address in X-Forwarded-For
+    assert_eq!(
+        Some("93.184.216.34".into()),
+        resolve(&resolver, INTERNAL_PROXY, &[
+            ("X-Forwarded-For", "1.1.1.1, 93.184.216.34, 173.245.48.10"),
+            ("CF-Connecting-IP", "93.184.216.34"),
+        ])
+    );
+
+    // Cloudflare connects to Rocket directly
+    assert_eq!(
+        Some("93.184.216.34".into()),
+        resolve(&resolver, CLOUDFLARE_EDGE, &[("CF-Connecting-IP", "93.184.216.34")])
+    );
+
+    // without the header, the edge of Cloudflare is the best known address
+    assert_eq!(
+        Some(CLOUDFLARE_EDGE.into()),
+        resolve(&resolver, INTERNAL_PROXY, &[("X-Forwarded-For", "93.184.216.34, 173.245.48.10")])
+    );
+}
+
+#[test]
+fn forged_header_bypassing_the_cdn() {
+    let resolver = two_layers();
+
+    // client -> internal proxy -> Rocket, with a forged CF-Connecting-IP
+    assert_eq!(
+        Some("34.120.0.9".into()),
+        resolve(&resolver, INTERNAL_PROXY, &[
+            ("X-Forwarded-For", "34.120.0.9"),
+            ("CF-Connecting-IP", "1.1.1.1"),
+        ])
+    );
+
+    // a client connecting to Rocket directly
+    assert_eq!(
+        Some("34.120.0.9".into()),
+        resolve(&resolver, "34.120.0.9", &[("CF-Connecting-IP", "1.1.1.1")])
+    );
+
+    // a forged edge of Cloudflare before a real hop
+    assert_eq!(
+        Some("34.120.0.9".into()),
+        resolve(&resolver, INTERNAL_PROXY, &[
+            ("X-Forwarded-For", "173.245.48.10, 34.120.0.9"),
+            ("CF-Connecting-IP", "1.1.1.1"),
+        ])
+    );
+}
+
+#[test]
+fn chained_cdns() {
+    // client -> Fastly -> Cloudflare -> internal proxy -> Rocket
+    let resolver = Resolver::builder()
+        .cdn_layer(CdnLayer {
+            header: CdnHeader::Cloudflare, ranges: ranges(&["173.245.48.0/20"])
+        })
+        .cdn_layer(CdnLayer {
+            header: CdnHeader::Fastly, ranges: ranges(&["151.101.0.0/16"])
+        })
+        .build();
+
+    assert_eq!(
+        Some("93.184.216.34".into()),
+        resolve(&resolver, INTERNAL_PROXY, &[
+            ("X-Forwarded-For", "93.184.216.34, 151.101.0.20, 173.245.48.10"),
+            ("CF-Connecting-IP", FASTLY_EDGE),
+            ("Fastly-Client-IP", "93.184.216.34"),
+        ])
+    );
+
+    // the headers of the layers pointing at each other do not loop forever
+    assert_eq!(
+        Some(CLOUDFLARE_EDGE.into()),
+        resolve(&resolver, INTERNAL_PROXY, &[
+            ("X-Forwarded-For", "173.245.48.10"),
+            ("CF-Connecting-IP", FASTLY_EDGE),
+            ("Fastly-Client-IP", CLOUDFLARE_EDGE),
+        ])
+    );
+}
+
+#[test]
+fn traced() {
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    let request = client
+        .get("/")
+        .remote(format!("{INTERNAL_PROXY}:8000").parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "93.184.216.34, 173.245.48.10"))
+        .header(Header::new("CF-Connecting-IP", "93.184.216.34"));
+
+    let (client_addr, steps) = two_layers().try_resolve_traced(request.inner());
+
+    assert_eq!("93.184.216.34", client_addr.unwrap().to_string());
+
+    let sources: Vec<AddrSource> = steps.iter().map(|step| step.source).collect();
+
+    assert_eq!(
+        vec![
+            AddrSource::Remote,
+            AddrSource::ForwardedFor,
+            AddrSource::CdnHeader(CdnHeader::Cloudflare)
+        ],
+        sources
+    );
+}
@@ -0,0 +1,19 @@
+#![cfg(feature = "cidr")]
+
+use cidr::IpCidr;
+use rocket_client_addr::IpNet;
+
+#[test]
+fn conversion() {
+    for net in
+        ["10.0.0.0/8", "93.184.216.34/32", "0.0.0.0/0", "2001:db8::/32", "::ffff:10.0.0.1/128"]
+    {
+        let net = net.parse::<IpNet>().unwrap();
+        let cidr = IpCidr::from(net);
+
+        assert_eq!(net.addr(), cidr.first_address(), "{net}");
+        assert_eq!(net.prefix_len(), cidr.network_length(), "{net}");
+        assert_eq!(net, IpNet::from(cidr), "{net}");
+        assert_eq!(cidr, net.to_string().parse::<IpCidr>().unwrap(), "{net}");
+    }
+}
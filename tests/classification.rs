@@ -0,0 +1,56 @@
+use std::net::IpAddr;
+
+use rocket_client_addr::{classify_many, Classification, ClientAddr};
+
+#[test]
+fn classify() {
+    for (ip, expected) in [
+        ("10.0.0.1", Classification::Local),
+        ("0.0.0.0", Classification::Local),
+        ("0.1.2.3", Classification::Local),
+        ("0.255.255.255", Classification::Local),
+        ("127.0.0.1", Classification::Local),
+        ("192.0.2.1", Classification::Local),
+        ("192.0.0.9", Classification::Local),
+        ("::ffff:192.168.1.1", Classification::Local),
+        ("::1", Classification::Local),
+        ("fe80::1", Classification::Local),
+        ("ff02::1", Classification::Local),
+        ("2001:db8::1", Classification::Local),
+        ("100.64.0.1", Classification::SpecialPurpose),
+        ("192.88.99.1", Classification::SpecialPurpose),
+        ("64:ff9b::5db8:d822", Classification::SpecialPurpose),
+        ("2002:5db8:d822::1", Classification::SpecialPurpose),
+        ("2001::1", Classification::SpecialPurpose),
+        ("93.184.216.34", Classification::Public),
+        ("::ffff:93.184.216.34", Classification::Public),
+        ("2606:4700::1111", Classification::Public),
+        ("ff0e::1", Classification::Public),
+    ] {
+        let ip: IpAddr = ip.parse().unwrap();
+
+        assert_eq!(expected, Classification::of(&ip), "{ip}");
+        assert_eq!(expected, ClientAddr { ip }.classify(), "{ip}");
+    }
+}
+
+#[test]
+fn bulk() {
+    let ips: Vec<IpAddr> = ["10.0.0.1", "100.64.0.1", "93.184.216.34", "2606:4700::1111"]
+        .iter()
+        .map(|ip| ip.parse().unwrap())
+        .collect();
+
+    let classifications = classify_many(&ips);
+
+    assert_eq!(
+        vec![
+            Classification::Local,
+            Classification::SpecialPurpose,
+            Classification::Public,
+            Classification::Public
+        ],
+        classifications
+    );
+    assert!(classify_many(&[]).is_empty());
+}
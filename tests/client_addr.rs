@@ -0,0 +1,1174 @@
+#[macro_use]
+extern crate rocket;
+
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    sync::Arc,
+};
+
+use rocket::{
+    http::{Cookie, Header, Status},
+    local::blocking::Client,
+};
+use rocket_client_addr::{
+    AddrFamily, CdnHeader, ClientAddr, ClientAddrError, Config, CustomSource, IpNet, IpNetSet,
+    RealIpMode, Resolver, Strategy, TrustChecker,
+};
+
+#[get("/")]
+fn index(client_addr: &ClientAddr) -> String {
+    client_addr.to_string()
+}
+
+fn client() -> Client {
+    Client::untracked(rocket::build().mount("/", routes![index])).unwrap()
+}
+
+fn client_with(config: Config) -> Client {
+    Client::untracked(rocket::build().manage(Resolver::from(config)).mount("/", routes![index]))
+        .unwrap()
+}
+
+#[test]
+fn empty_forwarded_for_falls_through_to_real_ip() {
+    let client = client();
+
+    let response = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", ""))
+        .header(Header::new("X-Real-IP", "203.0.113.5"))
+        .dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!("203.0.113.5", response.into_string().unwrap());
+}
+
+#[test]
+fn empty_forwarded_for_falls_through_to_remote() {
+    let client = client();
+
+    let response = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", " "))
+        .dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!("10.0.0.1", response.into_string().unwrap());
+}
+
+#[test]
+fn cdn_headers_are_not_trusted_by_default() {
+    let client = client();
+
+    let response = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("Fastly-Client-IP", "2001:4860::8888"))
+        .dispatch();
+
+    assert_eq!("10.0.0.1", response.into_string().unwrap());
+}
+
+#[test]
+fn fastly_client_ip() {
+    let client = client_with(Config {
+        trusted_cdn_headers: vec![CdnHeader::Fastly],
+        ..Config::default()
+    });
+
+    let response = client
+        .get("/")
+        .remote("151.101.1.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "203.0.113.5"))
+        .header(Header::new("Fastly-Client-IP", "2001:4860::8888"))
+        .dispatch();
+
+    assert_eq!("2001:4860::8888", response.into_string().unwrap());
+}
+
+#[test]
+fn azure_front_door() {
+    let request = |client: &Client| {
+        client
+            .get("/")
+            .remote("147.243.0.1:8000".parse().unwrap())
+            .header(Header::new("X-Azure-ClientIP", "203.0.113.5"))
+            .header(Header::new("X-Azure-SocketIP", "198.51.100.7"))
+            .dispatch()
+            .into_string()
+            .unwrap()
+    };
+
+    let client = client_with(Config {
+        trusted_cdn_headers: vec![CdnHeader::AzureClientIp],
+        ..Config::default()
+    });
+
+    assert_eq!("203.0.113.5", request(&client));
+
+    let client = client_with(Config {
+        trusted_cdn_headers: vec![CdnHeader::AzureSocketIp],
+        ..Config::default()
+    });
+
+    assert_eq!("198.51.100.7", request(&client));
+}
+
+#[test]
+fn trusted_proxies() {
+    static TRUSTED_PROXIES: IpNetSet =
+        IpNetSet::from_static(&[IpNet::new_v4(Ipv4Addr::new(34, 120, 0, 0), 16)]);
+
+    let request = |client: &Client| {
+        client
+            .get("/")
+            .remote("34.120.0.7:8000".parse().unwrap())
+            .header(Header::new("X-Forwarded-For", "93.184.216.34, 34.120.0.8"))
+            .dispatch()
+            .into_string()
+            .unwrap()
+    };
+
+    assert_eq!("34.120.0.7", request(&client()));
+
+    let client = client_with(Config {
+        trusted_proxies: TRUSTED_PROXIES.clone(),
+        ..Config::default()
+    });
+
+    assert_eq!("93.184.216.34", request(&client));
+}
+
+#[test]
+fn cdn_header_list() {
+    let request = |client: &Client, value: &str| {
+        client
+            .get("/")
+            .remote("10.0.0.1:8000".parse().unwrap())
+            .header(Header::new("CF-Connecting-IP", value.to_string()))
+            .dispatch()
+            .into_string()
+            .unwrap()
+    };
+
+    let client = client_with(Config {
+        trusted_cdn_headers: vec![CdnHeader::Cloudflare],
+        ..Config::default()
+    });
+
+    assert_eq!("93.184.216.34", request(&client, "93.184.216.34"));
+    assert_eq!("10.0.0.1", request(&client, "93.184.216.34, 172.70.0.1"));
+
+    let client = client_with(Config {
+        trusted_cdn_headers: vec![CdnHeader::Cloudflare],
+        cdn_header_tolerate_list: true,
+        ..Config::default()
+    });
+
+    assert_eq!("93.184.216.34", request(&client, "93.184.216.34"));
+    assert_eq!("93.184.216.34", request(&client, "93.184.216.34, 172.70.0.1"));
+}
+
+#[test]
+fn proxy_user_ip() {
+    let request = |client: &Client, value: &str| {
+        client
+            .get("/")
+            .remote("10.0.0.1:8000".parse().unwrap())
+            .header(Header::new("X-ProxyUser-Ip", value.to_string()))
+            .dispatch()
+            .into_string()
+            .unwrap()
+    };
+
+    // not trusted by default
+    assert_eq!("10.0.0.1", request(&client(), "93.184.216.34"));
+
+    let client = client_with(Config {
+        trusted_cdn_headers: vec![CdnHeader::ProxyUserIp],
+        ..Config::default()
+    });
+
+    assert_eq!("93.184.216.34", request(&client, "93.184.216.34"));
+    assert_eq!("2606:4700::1111", request(&client, " 2606:4700::1111 "));
+    assert_eq!("10.0.0.1", request(&client, "93.184.216.34, 10.0.0.2"));
+    assert_eq!("10.0.0.1", request(&client, "garbage"));
+}
+
+#[test]
+fn x_forwarded() {
+    let request = |client: &Client, value: &str| {
+        client
+            .get("/")
+            .remote("10.0.0.1:8000".parse().unwrap())
+            .header(Header::new("X-Forwarded", value.to_string()))
+            .dispatch()
+            .into_string()
+            .unwrap()
+    };
+
+    // not trusted by default
+    assert_eq!("10.0.0.1", request(&client(), "93.184.216.34"));
+
+    for tolerate_list in [false, true] {
+        let client = client_with(Config {
+            trusted_cdn_headers: vec![CdnHeader::XForwarded],
+            cdn_header_tolerate_list: tolerate_list,
+            ..Config::default()
+        });
+
+        // a single address
+        assert_eq!("93.184.216.34", request(&client, "93.184.216.34"));
+        assert_eq!("2606:4700::1111", request(&client, "[2606:4700::1111]:443"));
+
+        // a chain, whose rightmost entry is added by the proxy
+        assert_eq!("93.184.216.34", request(&client, "1.1.1.1, 93.184.216.34"));
+        assert_eq!("93.184.216.34", request(&client, "1.1.1.1,93.184.216.34:443"));
+
+        // `Forwarded` elements
+        assert_eq!("93.184.216.34", request(&client, "for=93.184.216.34"));
+        assert_eq!("93.184.216.34", request(&client, "for=1.1.1.1, for=93.184.216.34;proto=https"));
+        assert_eq!("2606:4700::1111", request(&client, "For=\"[2606:4700::1111]:443\""));
+
+        // a proxy which adds another header instead of appending
+        let response = client
+            .get("/")
+            .remote("10.0.0.1:8000".parse().unwrap())
+            .header(Header::new("X-Forwarded", "93.184.216.34"))
+            .header(Header::new("X-Forwarded", "for=1.1.1.1, for=34.120.0.9"))
+            .dispatch();
+
+        assert_eq!("34.120.0.9", response.into_string().unwrap());
+
+        // invalid, so the TCP peer is used
+        assert_eq!("10.0.0.1", request(&client, "93.184.216.34, garbage"));
+        assert_eq!("10.0.0.1", request(&client, "for=93.184.216.34, proto=https"));
+        assert_eq!("10.0.0.1", request(&client, "for=unknown"));
+    }
+}
+
+#[test]
+fn trusted_if() {
+    let client = client_with(Config::default().trusted_if(|request| {
+        request.headers().get_one("X-Client-Cert-Fingerprint") == Some("5E:6F:27:3A")
+    }));
+
+    let response = client
+        .get("/")
+        .remote("34.120.0.7:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "93.184.216.34"))
+        .header(Header::new("X-Client-Cert-Fingerprint", "5E:6F:27:3A"))
+        .dispatch();
+
+    assert_eq!("93.184.216.34", response.into_string().unwrap());
+
+    let response = client
+        .get("/")
+        .remote("34.120.0.7:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "93.184.216.34"))
+        .header(Header::new("X-Client-Cert-Fingerprint", "00:00:00:00"))
+        .dispatch();
+
+    assert_eq!("34.120.0.7", response.into_string().unwrap());
+}
+
+#[test]
+fn leftmost() {
+    let request = |client: &Client, value: &str| {
+        let response = client
+            .get("/")
+            .remote("10.0.0.1:8000".parse().unwrap())
+            .header(Header::new("X-Forwarded-For", value.to_string()))
+            .dispatch();
+
+        if response.status() == Status::Ok {
+            response.into_string()
+        } else {
+            None
+        }
+    };
+
+    let config = Config {
+        trusted_proxies: "34.120.0.0/16".parse::<IpNet>().into_iter().collect(),
+        strategy: Strategy::Leftmost {
+            verify_trusted_suffix: true,
+        },
+        ..Config::default()
+    };
+
+    let valid = "93.184.216.34, 34.120.0.8, 10.0.0.2";
+    let tampered = "1.1.1.1, 93.184.216.34, 34.120.0.8";
+
+    let client = client_with(config.clone());
+
+    assert_eq!(Some("93.184.216.34".into()), request(&client, valid));
+    assert_eq!(None, request(&client, tampered));
+
+    let client = client_with(Config {
+        untrusted_suffix_fallback: true,
+        ..config.clone()
+    });
+
+    assert_eq!(Some("93.184.216.34".into()), request(&client, tampered));
+
+    let client = client_with(Config {
+        strategy: Strategy::Leftmost {
+            verify_trusted_suffix: false,
+        },
+        ..config
+    });
+
+    assert_eq!(Some("1.1.1.1".into()), request(&client, tampered));
+}
+
+#[test]
+fn canonicalize_mapped() {
+    let request = |client: &Client| {
+        client
+            .get("/")
+            .remote("[::ffff:93.184.216.34]:8000".parse().unwrap())
+            .dispatch()
+            .into_string()
+            .unwrap()
+    };
+
+    assert_eq!("::ffff:93.184.216.34", request(&client()));
+
+    let client = client_with(Config {
+        canonicalize_mapped: true,
+        ..Config::default()
+    });
+
+    assert_eq!("93.184.216.34", request(&client));
+}
+
+#[test]
+fn canonicalize_mapped_nat64() {
+    let request = |client: &Client| {
+        client
+            .get("/")
+            .remote("10.0.0.1:8000".parse().unwrap())
+            .header(Header::new("X-Forwarded-For", "64:ff9b::5db8:d822"))
+            .dispatch()
+            .into_string()
+            .unwrap()
+    };
+
+    assert_eq!("64:ff9b::5db8:d822", request(&client()));
+
+    let client = client_with(Config {
+        canonicalize_mapped: true,
+        ..Config::default()
+    });
+
+    assert_eq!("93.184.216.34", request(&client));
+}
+
+#[test]
+fn custom_source_cookie() {
+    let client = client_with(Config {
+        custom_source: Some(CustomSource::Cookie("client_ip".into())),
+        ..Config::default()
+    });
+
+    let response = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "1.1.1.1"))
+        .cookie(Cookie::new("client_ip", "93.184.216.34"))
+        .dispatch();
+
+    assert_eq!("93.184.216.34", response.into_string().unwrap());
+
+    let response = client
+        .get("/")
+        .remote("34.120.0.7:8000".parse().unwrap())
+        .cookie(Cookie::new("client_ip", "93.184.216.34"))
+        .dispatch();
+
+    assert_eq!("34.120.0.7", response.into_string().unwrap());
+}
+
+#[test]
+fn custom_source_query() {
+    let client = client_with(Config {
+        custom_source: Some(CustomSource::Query("client_ip".into())),
+        ..Config::default()
+    });
+
+    let response = client
+        .get("/?client_ip=2001:4860::8888")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "1.1.1.1"))
+        .dispatch();
+
+    assert_eq!("2001:4860::8888", response.into_string().unwrap());
+
+    let response = client
+        .get("/?client_ip=invalid")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "1.1.1.1"))
+        .dispatch();
+
+    assert_eq!("1.1.1.1", response.into_string().unwrap());
+}
+
+#[test]
+fn no_remote() {
+    let client = client();
+
+    let request = |headers: &[(&'static str, &'static str)]| {
+        let mut request = client.get("/");
+
+        for (name, value) in headers {
+            request = request.header(Header::new(*name, *value));
+        }
+
+        let response = request.dispatch();
+
+        if response.status() == Status::Ok {
+            response.into_string()
+        } else {
+            None
+        }
+    };
+
+    assert_eq!(
+        Some("93.184.216.34".into()),
+        request(&[("X-Forwarded-For", "1.1.1.1, 93.184.216.34, 10.0.0.2")])
+    );
+    assert_eq!(Some("10.0.0.2".into()), request(&[("X-Forwarded-For", "10.0.0.2")]));
+    assert_eq!(Some("93.184.216.34".into()), request(&[("X-Real-IP", "93.184.216.34")]));
+    assert_eq!(
+        Some("93.184.216.34".into()),
+        request(&[("X-Forwarded-For", "invalid"), ("X-Real-IP", "93.184.216.34")])
+    );
+    assert_eq!(None, request(&[("X-Forwarded-For", "invalid")]));
+    assert_eq!(None, request(&[]));
+}
+
+#[test]
+fn resolver_builder() {
+    let resolver = Resolver::builder()
+        .trust_cdn_header(CdnHeader::Cloudflare)
+        .trust_proxies("34.120.0.0/16".parse::<IpNet>().into_iter().collect())
+        .strategy(Strategy::RightmostUntrusted)
+        .canonicalize_mapped(true)
+        .build();
+
+    let client =
+        Client::untracked(rocket::build().manage(resolver).mount("/", routes![index])).unwrap();
+
+    let response = client
+        .get("/")
+        .remote("34.120.0.7:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "::ffff:93.184.216.34, 34.120.0.8"))
+        .dispatch();
+
+    assert_eq!("93.184.216.34", response.into_string().unwrap());
+
+    let response = client
+        .get("/")
+        .remote("34.120.0.7:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "93.184.216.34"))
+        .header(Header::new("CF-Connecting-IP", "1.1.1.1"))
+        .dispatch();
+
+    assert_eq!("1.1.1.1", response.into_string().unwrap());
+}
+
+#[test]
+fn trust_checker() {
+    struct StubChecker;
+
+    #[rocket::async_trait]
+    impl TrustChecker for StubChecker {
+        async fn is_trusted(&self, ip: IpAddr) -> bool {
+            ip == IpAddr::V4(Ipv4Addr::new(34, 120, 0, 7))
+        }
+    }
+
+    let client = client_with(Config {
+        trust_checker: Some(Arc::new(StubChecker)),
+        ..Config::default()
+    });
+
+    let request = |remote: &str| {
+        client
+            .get("/")
+            .remote(remote.parse().unwrap())
+            .header(Header::new("X-Forwarded-For", "93.184.216.34"))
+            .dispatch()
+            .into_string()
+            .unwrap()
+    };
+
+    assert_eq!("93.184.216.34", request("34.120.0.7:8000"));
+    assert_eq!("34.120.0.8", request("34.120.0.8:8000"));
+}
+
+#[test]
+fn reject_nonroutable_from_headers() {
+    let trusted_proxies = "34.120.0.0/16".parse().into_iter().collect::<IpNetSet>();
+
+    let request = |client: &Client, forwarded_for: &'static str| {
+        client
+            .get("/")
+            .remote("34.120.0.1:8000".parse().unwrap())
+            .header(Header::new("X-Forwarded-For", forwarded_for))
+            .dispatch()
+            .into_string()
+            .unwrap()
+    };
+
+    let client = client_with(Config {
+        trusted_proxies: trusted_proxies.clone(),
+        ..Config::default()
+    });
+
+    assert_eq!("fe80::1", request(&client, "fe80::1"));
+
+    let client = client_with(Config {
+        trusted_proxies,
+        reject_nonroutable_from_headers: true,
+        ..Config::default()
+    });
+
+    assert_eq!("34.120.0.1", request(&client, "fe80::1"));
+    assert_eq!("34.120.0.1", request(&client, "fd12:3456::1"));
+    assert_eq!("2606:4700::1111", request(&client, "2606:4700::1111"));
+    assert_eq!("93.184.216.34", request(&client, "93.184.216.34, fe80::1"));
+
+    let response = client
+        .get("/")
+        .remote("34.120.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "fe80::1"))
+        .header(Header::new("X-Real-IP", "93.184.216.34"))
+        .dispatch();
+
+    assert_eq!("93.184.216.34", response.into_string().unwrap());
+}
+
+#[test]
+fn strict_parsing() {
+    let request = |client: Client| {
+        let response = client
+            .get("/")
+            .remote("10.0.0.1:8000".parse().unwrap())
+            .header(Header::new("X-Forwarded-For", "93.184.216.34, 1.1.1.1:https, 10.0.0.2"))
+            .dispatch();
+
+        (response.status(), response.into_string())
+    };
+
+    // lenient: the walk stops at the invalid entry
+    assert_eq!((Status::Ok, Some("10.0.0.2".to_string())), request(client()));
+
+    let (status, _) = request(client_with(Config {
+        strict_parsing: true,
+        ..Config::default()
+    }));
+
+    assert_eq!(Status::BadRequest, status);
+
+    let resolver = Resolver::builder().strict_parsing(true).build();
+
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    let request = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "93.184.216.34, , 10.0.0.2"));
+
+    assert_eq!(Err(ClientAddrError::MalformedHeader), resolver.try_resolve(request.inner()));
+    assert_eq!(None, resolver.resolve(request.inner()));
+
+    let request = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "93.184.216.34, 10.0.0.2"));
+
+    assert_eq!(
+        Ok(Some(ClientAddr { ip: "93.184.216.34".parse().unwrap() })),
+        resolver.try_resolve(request.inner())
+    );
+}
+
+#[test]
+fn reserved_peers_are_local() {
+    let client = client();
+
+    let request = |remote: &str| {
+        client
+            .get("/")
+            .remote(remote.parse().unwrap())
+            .header(Header::new("X-Forwarded-For", "93.184.216.34"))
+            .dispatch()
+            .into_string()
+            .unwrap()
+    };
+
+    for remote in [
+        "192.0.0.1:8000",
+        "192.0.0.255:8000",
+        "198.18.0.1:8000",
+        "198.19.255.254:8000",
+        "240.0.0.1:8000",
+        "254.1.2.3:8000",
+    ] {
+        assert_eq!("93.184.216.34", request(remote), "{remote}");
+    }
+
+    for remote in ["192.0.1.1:8000", "198.17.255.255:8000", "198.20.0.1:8000", "34.120.0.1:8000"] {
+        assert_eq!(remote.strip_suffix(":8000").unwrap(), request(remote));
+    }
+}
+
+#[test]
+fn reserved_forwarded_for_entries_are_skipped() {
+    let client = client();
+
+    let response = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "93.184.216.34, 198.18.0.1, 192.0.0.8"))
+        .dispatch();
+
+    assert_eq!("93.184.216.34", response.into_string().unwrap());
+}
+
+#[test]
+fn this_network_is_not_routable() {
+    let client = client();
+
+    let request = |remote: &str, forwarded_for: &'static str| {
+        client
+            .get("/")
+            .remote(format!("{remote}:8000").parse().unwrap())
+            .header(Header::new("X-Forwarded-For", forwarded_for))
+            .dispatch()
+            .into_string()
+            .unwrap()
+    };
+
+    // skipped like any other local address
+    assert_eq!("93.184.216.34", request("10.0.0.1", "93.184.216.34, 0.1.2.3"));
+    assert_eq!("93.184.216.34", request("10.0.0.1", "93.184.216.34, 0.0.0.0"));
+    assert_eq!("93.184.216.34", request("0.1.2.3", "93.184.216.34"));
+    assert_eq!("93.184.216.34", request("0.0.0.0", "93.184.216.34"));
+}
+
+#[test]
+fn real_ip_mode_with_custom_ip_header() {
+    let get = |real_ip_mode: RealIpMode, remote: &str, headers: &[(&str, &str)]| {
+        let figment = rocket::Config::figment().merge(("ip_header", "X-Client-IP"));
+        let resolver = Resolver::builder().real_ip_mode(real_ip_mode).build();
+        let rocket = rocket::custom(figment).manage(resolver).mount("/", routes![index]);
+        let client = Client::untracked(rocket).unwrap();
+
+        let mut request = client.get("/").remote(format!("{remote}:8000").parse().unwrap());
+
+        for (name, value) in headers {
+            request = request.header(Header::new(name.to_string(), value.to_string()));
+        }
+
+        request.dispatch().into_string().unwrap()
+    };
+
+    // Rocket reads `X-Client-IP` instead of `X-Real-IP`
+    let headers = [("X-Client-IP", "93.184.216.34"), ("X-Real-IP", "1.1.1.1")];
+
+    assert_eq!("93.184.216.34", get(RealIpMode::Fallback, "10.0.0.1", &headers));
+    assert_eq!("10.0.0.1", get(RealIpMode::Ignore, "10.0.0.1", &headers));
+    assert_eq!("34.120.0.9", get(RealIpMode::Fallback, "34.120.0.9", &headers));
+
+    let headers = [("X-Client-IP", "93.184.216.34"), ("X-Forwarded-For", "1.1.1.1")];
+
+    assert_eq!("1.1.1.1", get(RealIpMode::Fallback, "10.0.0.1", &headers));
+    assert_eq!("93.184.216.34", get(RealIpMode::Defer, "10.0.0.1", &headers));
+    assert_eq!("93.184.216.34", get(RealIpMode::Defer, "34.120.0.9", &headers));
+    assert_eq!("34.120.0.9", get(RealIpMode::Defer, "34.120.0.9", &[]));
+}
+
+#[test]
+fn real_ip_disabled_in_rocket() {
+    let figment = rocket::Config::figment().merge(("ip_header", false));
+    let client = Client::untracked(rocket::custom(figment).mount("/", routes![index])).unwrap();
+
+    let response = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Real-IP", "93.184.216.34"))
+        .dispatch();
+
+    assert_eq!("10.0.0.1", response.into_string().unwrap());
+}
+
+#[test]
+fn fallback() {
+    let request = |client: &Client, forwarded_for: Option<&'static str>| {
+        let mut request = client.get("/");
+
+        if let Some(forwarded_for) = forwarded_for {
+            request = request.header(Header::new("X-Forwarded-For", forwarded_for));
+        }
+
+        let response = request.dispatch();
+
+        (response.status(), response.into_string())
+    };
+
+    // absent: the guard forwards when no source holds an address
+    let client = client();
+
+    assert_eq!(Status::BadRequest, request(&client, None).0);
+    assert_eq!(Status::BadRequest, request(&client, Some("invalid")).0);
+
+    // present: the guard succeeds with the fallback
+    let client = client_with(Config {
+        fallback: Some(Ipv4Addr::UNSPECIFIED.into()),
+        ..Config::default()
+    });
+
+    assert_eq!((Status::Ok, Some("0.0.0.0".to_string())), request(&client, None));
+    assert_eq!((Status::Ok, Some("0.0.0.0".to_string())), request(&client, Some("invalid")));
+    assert_eq!(
+        (Status::Ok, Some("93.184.216.34".to_string())),
+        request(&client, Some("93.184.216.34"))
+    );
+
+    // a malformed header is still an error
+    let resolver = Resolver::builder()
+        .strict_parsing(true)
+        .fallback("93.184.216.34".parse().unwrap())
+        .build();
+
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    let request = client.get("/").header(Header::new("X-Forwarded-For", "invalid"));
+
+    assert_eq!(Err(ClientAddrError::MalformedHeader), resolver.try_resolve(request.inner()));
+
+    let request = client.get("/");
+
+    assert_eq!(
+        Ok(Some(ClientAddr { ip: "93.184.216.34".parse().unwrap() })),
+        resolver.try_resolve(request.inner())
+    );
+}
+
+#[test]
+fn first_public() {
+    let client = client_with(Config {
+        strategy: Strategy::FirstPublic,
+        ..Config::default()
+    });
+
+    let request = |forwarded_for: &'static str| {
+        client
+            .get("/")
+            .remote("10.0.0.1:8000".parse().unwrap())
+            .header(Header::new("X-Forwarded-For", forwarded_for))
+            .dispatch()
+            .into_string()
+            .unwrap()
+    };
+
+    assert_eq!("8.8.8.8", request("10.0.0.1, 8.8.8.8, 1.1.1.1"));
+    assert_eq!("8.8.8.8", request("invalid, 192.168.0.1, fd00::1, 8.8.8.8"));
+    assert_eq!("2606:4700::1111", request("::1, 2606:4700::1111, 8.8.8.8"));
+    // a client can prepend a fake public address
+    assert_eq!("1.0.0.1", request("1.0.0.1, 8.8.8.8"));
+    // no public address, so the TCP peer is used
+    assert_eq!("10.0.0.1", request("10.0.0.2, 192.168.0.1"));
+    // an IPv4-mapped IPv6 entry is checked as it is
+    assert_eq!("::ffff:10.0.0.2", request("::ffff:10.0.0.2, 8.8.8.8"));
+}
+
+#[test]
+fn reject_site_local_from_headers() {
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    let request = |name: &'static str, value: &'static str| {
+        client.get("/").remote("10.0.0.1:8000".parse().unwrap()).header(Header::new(name, value))
+    };
+
+    let site_local = ClientAddrError::SiteLocalAddress("fec0::1".parse().unwrap());
+
+    // accepted without the option, even with `reject_nonroutable_from_headers`
+    let resolver = Resolver::builder().reject_nonroutable_from_headers(true).build();
+
+    assert_eq!(
+        Ok(Some(ClientAddr { ip: "fec0::1".parse().unwrap() })),
+        resolver.try_resolve(request("X-Forwarded-For", "fec0::1").inner())
+    );
+
+    let resolver = Resolver::builder().reject_site_local_from_headers(true).build();
+
+    assert_eq!(
+        Err(site_local.clone()),
+        resolver.try_resolve(request("X-Forwarded-For", "fec0::1, 10.0.0.2").inner())
+    );
+    assert_eq!(
+        Err(site_local.clone()),
+        resolver.try_resolve(request("Forwarded", "for=\"[fec0::1]\"").inner())
+    );
+    assert_eq!(Err(site_local), resolver.try_resolve(request("X-Real-IP", "fec0::1").inner()));
+
+    // a site-local entry which is skipped by the walk is not selected
+    assert_eq!(
+        Ok(Some(ClientAddr { ip: "93.184.216.34".parse().unwrap() })),
+        resolver.try_resolve(request("X-Forwarded-For", "93.184.216.34, fec0::1").inner())
+    );
+
+    // the request guard fails with `Status::BadRequest`
+    let client = client_with(Config {
+        reject_site_local_from_headers: true,
+        ..Config::default()
+    });
+
+    let response = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "fec0::1"))
+        .header(Header::new("X-Real-IP", "93.184.216.34"))
+        .dispatch();
+
+    assert_eq!(Status::BadRequest, response.status());
+}
+
+#[test]
+fn reject_private_result() {
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    let request = |remote: &str, forwarded_for: &'static str| {
+        client
+            .get("/")
+            .remote(remote.parse().unwrap())
+            .header(Header::new("X-Forwarded-For", forwarded_for))
+    };
+
+    let resolver = Resolver::builder().reject_private_result(true).build();
+
+    // the only candidate is private
+    assert_eq!(
+        Ok(Some(ClientAddr { ip: "10.0.0.1".parse().unwrap() })),
+        Resolver::default().try_resolve(request("10.0.0.1:8000", "").inner())
+    );
+    assert_eq!(
+        Err(ClientAddrError::PrivateAddress("10.0.0.1".parse().unwrap())),
+        resolver.try_resolve(request("10.0.0.1:8000", "").inner())
+    );
+    assert_eq!(
+        Err(ClientAddrError::PrivateAddress("192.168.0.1".parse().unwrap())),
+        resolver.try_resolve(request("10.0.0.1:8000", "192.168.0.1, 10.0.0.2").inner())
+    );
+    assert_eq!(
+        Err(ClientAddrError::PrivateAddress("::ffff:10.0.0.1".parse().unwrap())),
+        resolver.try_resolve(request("[::ffff:10.0.0.1]:8000", "").inner())
+    );
+
+    // a public address is accepted
+    assert_eq!(
+        Ok(Some(ClientAddr { ip: "93.184.216.34".parse().unwrap() })),
+        resolver.try_resolve(request("10.0.0.1:8000", "93.184.216.34, 10.0.0.2").inner())
+    );
+
+    // so is the fallback, if it is public
+    let resolver = Resolver::builder()
+        .reject_private_result(true)
+        .fallback("93.184.216.34".parse().unwrap())
+        .build();
+
+    assert_eq!(
+        Ok(Some(ClientAddr { ip: "93.184.216.34".parse().unwrap() })),
+        resolver.try_resolve(client.get("/").inner())
+    );
+
+    // the request guard fails with `Status::BadRequest`
+    let client = client_with(Config { reject_private_result: true, ..Config::default() });
+
+    let response = client.get("/").remote("10.0.0.1:8000".parse().unwrap()).dispatch();
+
+    assert_eq!(Status::BadRequest, response.status());
+
+    let response = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "93.184.216.34"))
+        .dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!("93.184.216.34", response.into_string().unwrap());
+}
+
+#[test]
+fn quoted_forwarded_for_entries() {
+    let client = client();
+
+    let request = |forwarded_for: &'static str| {
+        client
+            .get("/")
+            .remote("10.0.0.1:8000".parse().unwrap())
+            .header(Header::new("X-Forwarded-For", forwarded_for))
+            .dispatch()
+            .into_string()
+            .unwrap()
+    };
+
+    assert_eq!("203.0.113.5", request("\"203.0.113.5\""));
+    assert_eq!("93.184.216.34", request("1.1.1.1, \"93.184.216.34\", \"10.0.0.2\""));
+    assert_eq!("2606:4700::1111", request("\"[2606:4700::1111]\""));
+    assert_eq!("2606:4700::1111", request("[2606:4700::1111], 10.0.0.2"));
+    assert_eq!("2606:4700::1111", request("\"2606:4700::1111\""));
+
+    // malformed quotes and brackets still stop the walk
+    assert_eq!("10.0.0.2", request("\"93.184.216.34, 10.0.0.2"));
+    assert_eq!("10.0.0.2", request("\"[93.184.216.34]\", 10.0.0.2"));
+    assert_eq!("10.0.0.2", request("\"[2606:4700::1111\", 10.0.0.2"));
+
+    let resolver = Resolver::builder().strict_parsing(true).build();
+
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    let request = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "\"93.184.216.34\", \"[2606:4700::1111]\""));
+
+    assert_eq!(
+        Ok(Some(ClientAddr { ip: "2606:4700::1111".parse().unwrap() })),
+        resolver.try_resolve(request.inner())
+    );
+}
+
+#[test]
+fn failure_status() {
+    let request = |client: &Client, forwarded_for: Option<&'static str>| {
+        let mut request = client.get("/");
+
+        if let Some(forwarded_for) = forwarded_for {
+            request = request.header(Header::new("X-Forwarded-For", forwarded_for));
+        }
+
+        request.dispatch().status()
+    };
+
+    // forwarded
+    assert_eq!(Status::BadRequest, request(&client(), None));
+    let client = client_with(Config {
+        failure_status: Some(Status::Forbidden),
+        ..Config::default()
+    });
+
+    assert_eq!(Status::Forbidden, request(&client, None));
+
+    // failed
+    let client = client_with(Config {
+        strict_parsing: true,
+        failure_status: Some(Status::UnprocessableEntity),
+        ..Config::default()
+    });
+
+    assert_eq!(Status::UnprocessableEntity, request(&client, Some("93.184.216.34, invalid")));
+    assert_eq!(Status::Ok, request(&client, Some("93.184.216.34")));
+
+    let client = Client::untracked(
+        rocket::build()
+            .manage(Resolver::builder().failure_status(Status::Forbidden).build())
+            .mount("/", routes![index]),
+    )
+    .unwrap();
+
+    assert_eq!(Status::Forbidden, request(&client, None));
+}
+
+#[test]
+fn ports_are_stripped_consistently() {
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    for (value, expected) in [
+        ("203.0.113.5:443", Some("203.0.113.5")),
+        ("\"203.0.113.5:443\"", Some("203.0.113.5")),
+        ("[2606:4700::1111]:443", Some("2606:4700::1111")),
+        ("[2606:4700::1111]", Some("2606:4700::1111")),
+        ("2606:4700::1111", Some("2606:4700::1111")),
+        ("203.0.113.5:https", None),
+        ("203.0.113.5:65536", None),
+        ("[203.0.113.5]:443", None),
+    ] {
+        let expected = expected.map(|ip| ClientAddr { ip: ip.parse().unwrap() });
+
+        for name in ["X-Forwarded-For", "X-Real-IP"] {
+            let request = client.get("/").header(Header::new(name, value));
+
+            assert_eq!(expected, Resolver::default().resolve(request.inner()), "{name}: {value}");
+        }
+    }
+}
+
+#[test]
+fn remote_sentinels() {
+    let resolver = Resolver::builder()
+        .strict_parsing(true)
+        .remote_sentinel("X-Forwarded-For", "unknown")
+        .build();
+
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    let resolve = |forwarded_for: &'static str| {
+        let request = client
+            .get("/")
+            .remote("10.0.0.1:8000".parse().unwrap())
+            .header(Header::new("X-Forwarded-For", forwarded_for))
+            .header(Header::new("X-Real-IP", "93.184.216.34"));
+
+        resolver.try_resolve(request.inner()).map(|client_addr| client_addr.unwrap().to_string())
+    };
+
+    // a health check probe: every header is ignored, even under strict parsing
+    assert_eq!(Ok("10.0.0.1".to_string()), resolve("unknown"));
+    assert_eq!(Ok("10.0.0.1".to_string()), resolve(" Unknown "));
+
+    // not a sentinel
+    assert_eq!(Ok("1.1.1.1".to_string()), resolve("1.1.1.1"));
+    assert_eq!(Err(ClientAddrError::MalformedHeader), resolve("unknown, 1.1.1.1"));
+
+    // a sentinel line sent by the client, followed by the line appended by a proxy
+    let request = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "unknown"))
+        .header(Header::new("X-Forwarded-For", "93.184.216.34"));
+
+    assert_eq!(Err(ClientAddrError::MalformedHeader), resolver.try_resolve(request.inner()));
+
+    // without the TCP peer, the fallback is used
+    let resolver = Resolver::builder()
+        .remote_sentinel("X-Forwarded-For", "unknown")
+        .fallback(Ipv4Addr::UNSPECIFIED.into())
+        .build();
+
+    let request = client.get("/").header(Header::new("X-Forwarded-For", "unknown"));
+
+    assert_eq!(
+        Some(ClientAddr { ip: Ipv4Addr::UNSPECIFIED.into() }),
+        resolver.resolve(request.inner())
+    );
+}
+
+#[test]
+fn collapse_duplicate_hops() {
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    let request = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "1.1.1.1, 93.184.216.34, 93.184.216.34"));
+
+    let resolve = |collapse: bool| {
+        Resolver::builder()
+            .strategy(Strategy::Rightmost { skip: 1 })
+            .collapse_duplicate_hops(collapse)
+            .build()
+            .resolve(request.inner())
+            .unwrap()
+            .to_string()
+    };
+
+    assert_eq!("93.184.216.34", resolve(false));
+    // the proxy which appended its own address twice counts as one hop
+    assert_eq!("1.1.1.1", resolve(true));
+}
+
+#[test]
+fn prefer_family() {
+    let resolve = |resolver: &Resolver, forwarded_for: &'static str| {
+        let client = Client::untracked(rocket::build()).unwrap();
+
+        let request = client
+            .get("/")
+            .remote("10.0.0.1:8000".parse().unwrap())
+            .header(Header::new("X-Forwarded-For", forwarded_for));
+
+        resolver.resolve(request.inner()).unwrap().to_string()
+    };
+
+    let v6 = Resolver::builder().prefer_family(AddrFamily::V6).build();
+
+    // a global IPv6 address is preferred
+    assert_eq!("2606:4700::1111", resolve(&v6, "2606:4700::1111, 93.184.216.34"));
+    assert_eq!(
+        "2606:4700::1111",
+        resolve(&v6, "2606:4700::1111, 1.1.1.1, 93.184.216.34, 10.0.0.2")
+    );
+    assert_eq!("2606:4700::1111", resolve(&v6, "93.184.216.34, 2606:4700::1111"));
+
+    // but not a link-local or unique local one
+    assert_eq!("93.184.216.34", resolve(&v6, "fe80::1, 93.184.216.34"));
+    assert_eq!("93.184.216.34", resolve(&v6, "fd12:3456::1, 93.184.216.34"));
+    assert_eq!("93.184.216.34", resolve(&v6, "::ffff:1.1.1.1, 93.184.216.34"));
+    assert_eq!("93.184.216.34", resolve(&v6, "93.184.216.34"));
+
+    // the trusted proxies to the right of the selected address are never used
+    let v6_behind_proxy = Resolver::builder()
+        .prefer_family(AddrFamily::V6)
+        .trust_proxies(["2606:4700::/32".parse().unwrap()].into_iter().collect::<IpNetSet>())
+        .build();
+
+    assert_eq!("93.184.216.34", resolve(&v6_behind_proxy, "93.184.216.34, 2606:4700::1111"));
+
+    let v4 = Resolver::builder().prefer_family(AddrFamily::V4).build();
+
+    assert_eq!("93.184.216.34", resolve(&v4, "93.184.216.34, 2606:4700::1111"));
+    assert_eq!("2606:4700::1111", resolve(&v4, "10.0.0.3, 2606:4700::1111"));
+
+    // without the preference, the strategy decides
+    assert_eq!("93.184.216.34", resolve(&Resolver::default(), "2606:4700::1111, 93.184.216.34"));
+}
+
+#[test]
+fn always_use_headers() {
+    let resolve = |resolver: &Resolver| {
+        let client = Client::untracked(rocket::build()).unwrap();
+
+        let request = client
+            .get("/")
+            .remote("34.120.0.9:8000".parse().unwrap())
+            .header(Header::new("X-Forwarded-For", "93.184.216.34"));
+
+        resolver.resolve(request.inner()).unwrap().to_string()
+    };
+
+    // a public TCP peer is used immediately
+    assert_eq!("34.120.0.9", resolve(&Resolver::default()));
+
+    // unless the headers are always used
+    assert_eq!("93.184.216.34", resolve(&Resolver::builder().always_use_headers(true).build()));
+}
+
+#[test]
+fn zone_identifiers_are_stripped() {
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    let resolve = |value: &'static str| {
+        let request = client.get("/").header(Header::new("X-Real-IP", value));
+
+        Resolver::default().resolve(request.inner())
+    };
+
+    let link_local: IpAddr = "fe80::1".parse().unwrap();
+
+    for value in ["fe80::1", "fe80::1%eth0", "fe80::1%2", "[fe80::1%eth0]:443", "\"fe80::1%en0\""] {
+        let client_addr = resolve(value).unwrap_or_else(|| panic!("{value}"));
+
+        assert_eq!(link_local, client_addr.ip, "{value}");
+        assert!(client_addr.eq_ignoring_scope(&"fe80:2::1".parse().unwrap()), "{value}");
+    }
+
+    for value in ["fe80::1%", "fe80::1%eth 0", "203.0.113.5%eth0", "[fe80::1%eth0]:https"] {
+        assert_eq!(None, resolve(value), "{value}");
+    }
+}
@@ -0,0 +1,102 @@
+#[macro_use]
+extern crate rocket;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+    request::{self, FromRequest, Request},
+    State,
+};
+
+use rocket_client_addr::{client_addr_derived, ClientAddrError, Resolver};
+
+struct Derivations(AtomicUsize);
+
+struct Network(String);
+
+async fn network<'r>(request: &'r Request<'_>) -> request::Outcome<&'r Network, ClientAddrError> {
+    let derivations = request.rocket().state::<Derivations>().unwrap();
+
+    client_addr_derived(request, |client_addr| {
+        derivations.0.fetch_add(1, Ordering::SeqCst);
+
+        Network(client_addr.enclosing_net(24).to_string())
+    })
+    .await
+}
+
+struct First(String);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for First {
+    type Error = ClientAddrError;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        network(request).await.map(|network| First(network.0.clone()))
+    }
+}
+
+struct Second(String);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Second {
+    type Error = ClientAddrError;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        network(request).await.map(|network| Second(network.0.clone()))
+    }
+}
+
+#[get("/")]
+fn index(first: First, second: Second, derivations: &State<Derivations>) -> String {
+    format!("{} {} {}", first.0, second.0, derivations.0.load(Ordering::SeqCst))
+}
+
+#[test]
+fn shared_by_guards() {
+    let resolver = Resolver::builder().strict_parsing(true).build();
+    let client = Client::untracked(
+        rocket::build()
+            .manage(resolver)
+            .manage(Derivations(AtomicUsize::new(0)))
+            .mount("/", routes![index]),
+    )
+    .unwrap();
+
+    let response = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "93.184.216.34"))
+        .dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!("93.184.216.0/24 93.184.216.0/24 1", response.into_string().unwrap());
+
+    // derived again for another request
+    let response = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "34.120.0.1"))
+        .dispatch();
+
+    assert_eq!("34.120.0.0/24 34.120.0.0/24 2", response.into_string().unwrap());
+
+    let response = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "93.184.216.34, garbage"))
+        .dispatch();
+
+    assert_eq!(Status::BadRequest, response.status());
+
+    // forwarded with `Status::BadRequest` because there is no source
+    let response = client.get("/").dispatch();
+
+    assert_eq!(Status::BadRequest, response.status());
+
+    let derivations = client.rocket().state::<Derivations>().unwrap();
+
+    assert_eq!(2, derivations.0.load(Ordering::SeqCst));
+}
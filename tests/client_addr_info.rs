@@ -0,0 +1,108 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{http::Header, local::blocking::Client};
+use rocket_client_addr::{ClientAddr, ClientAddrInfo, Resolver};
+
+#[get("/")]
+fn index(info: ClientAddrInfo) -> String {
+    format!("{:?} {} {:?}", info.client_addr, info.hops, info.via_hops)
+}
+
+fn get(headers: &[(&'static str, &'static str)]) -> String {
+    let client = Client::untracked(rocket::build().mount("/", routes![index])).unwrap();
+
+    let mut request = client.get("/").remote("10.0.0.1:8000".parse().unwrap());
+
+    for (name, value) in headers {
+        request = request.header(Header::new(*name, *value));
+    }
+
+    request.dispatch().into_string().unwrap()
+}
+
+#[test]
+fn no_proxy() {
+    assert_eq!("Some(10.0.0.1) 0 None", get(&[]));
+}
+
+#[test]
+fn via_hops() {
+    assert_eq!(
+        "Some(93.184.216.34) 1 Some(3)",
+        get(&[
+            ("X-Forwarded-For", "93.184.216.34"),
+            ("Via", "1.0 fred, 1.1 p.example.net (Apache/1.1, mod_proxy)"),
+            ("Via", "HTTP/1.1 GWA"),
+        ])
+    );
+
+    assert_eq!("Some(10.0.0.1) 0 Some(1)", get(&[("Via", " , 1.1 vegur,")]));
+}
+
+#[test]
+fn forwarded_hops() {
+    assert_eq!(
+        "Some(10.0.0.2) 3 Some(2)",
+        get(&[
+            ("Forwarded", "for=93.184.216.34, for=_hidden"),
+            ("Forwarded", "for=10.0.0.2"),
+            ("Via", "1.1 cdn, 1.1 lb"),
+        ])
+    );
+}
+
+#[test]
+fn collapse_duplicate_hops() {
+    for (collapse, expected) in
+        [(false, "Some(10.0.0.1) 3 None"), (true, "Some(10.0.0.1) 2 None")]
+    {
+        let resolver = Resolver::builder().collapse_duplicate_hops(collapse).build();
+        let client =
+            Client::untracked(rocket::build().manage(resolver).mount("/", routes![index])).unwrap();
+
+        let response = client
+            .get("/")
+            .remote("10.0.0.2:8000".parse().unwrap())
+            .header(Header::new("X-Forwarded-For", "10.0.0.1, 10.0.0.1, 203.0.113.5"))
+            .dispatch();
+
+        assert_eq!(expected, response.into_string().unwrap());
+    }
+}
+
+#[test]
+fn is_likely_shared() {
+    let info = |client_addr: &str, hops: usize| ClientAddrInfo {
+        client_addr: Some(ClientAddr { ip: client_addr.parse().unwrap() }),
+        hops,
+        via_hops: None,
+    };
+
+    assert!(info("100.64.0.1", 0).is_likely_shared());
+    assert!(info("100.64.0.1", 1).is_likely_shared());
+    assert!(info("93.184.216.34", 2).is_likely_shared());
+    assert!(!info("93.184.216.34", 1).is_likely_shared());
+    assert!(!info("93.184.216.34", 0).is_likely_shared());
+}
+
+#[test]
+fn distinct_hops() {
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    let get = |forwarded_for: &'static str| {
+        let request = client
+            .get("/")
+            .remote("10.0.0.1:8000".parse().unwrap())
+            .header(Header::new("X-Forwarded-For", forwarded_for));
+
+        let request = request.inner();
+
+        (ClientAddrInfo::chain(request).len(), ClientAddrInfo::distinct_hops(request))
+    };
+
+    assert_eq!((3, 3), get("93.184.216.34, 1.1.1.1, 10.0.0.2"));
+    assert_eq!((4, 2), get("1.1.1.1, 93.184.216.34, 1.1.1.1, 1.1.1.1"));
+    assert_eq!((3, 1), get("1.1.1.1, invalid, 1.1.1.1"));
+    assert_eq!((0, 0), get(""));
+}
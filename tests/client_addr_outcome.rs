@@ -0,0 +1,57 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+    request::{self, FromRequest, Request},
+};
+
+use rocket_client_addr::{client_addr_outcome, ClientAddrError, Resolver};
+
+struct Network(String);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Network {
+    type Error = ClientAddrError;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        client_addr_outcome(request)
+            .await
+            .map(|client_addr| Network(client_addr.enclosing_net(24).to_string()))
+    }
+}
+
+#[get("/")]
+fn index(network: Network) -> String {
+    network.0
+}
+
+#[test]
+fn delegate() {
+    let resolver = Resolver::builder().strict_parsing(true).build();
+    let client =
+        Client::untracked(rocket::build().manage(resolver).mount("/", routes![index])).unwrap();
+
+    let response = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "93.184.216.34"))
+        .dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!("93.184.216.0/24", response.into_string().unwrap());
+
+    let response = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "93.184.216.34, garbage"))
+        .dispatch();
+
+    assert_eq!(Status::BadRequest, response.status());
+
+    // forwarded with `Status::BadRequest` because there is no source
+    let response = client.get("/").dispatch();
+
+    assert_eq!(Status::BadRequest, response.status());
+}
@@ -0,0 +1,32 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+};
+use rocket_client_addr::ClientAddrString;
+
+#[get("/")]
+fn index(client_addr: ClientAddrString) -> String {
+    client_addr.0
+}
+
+#[test]
+fn canonical_string() {
+    let client = Client::untracked(rocket::build().mount("/", routes![index])).unwrap();
+
+    let request = |forwarded_for: &'static str| {
+        let response = client
+            .get("/")
+            .remote("10.0.0.1:8000".parse().unwrap())
+            .header(Header::new("X-Forwarded-For", forwarded_for))
+            .dispatch();
+
+        (response.status(), response.into_string().unwrap())
+    };
+
+    assert_eq!((Status::Ok, "93.184.216.34".to_string()), request("93.184.216.34"));
+    assert_eq!((Status::Ok, "93.184.216.34".to_string()), request("::FFFF:93.184.216.34"));
+    assert_eq!((Status::Ok, "2606:4700::1111".to_string()), request("2606:4700:0:0::1111"));
+}
@@ -0,0 +1,51 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{http::Header, local::blocking::Client};
+use rocket_client_addr::{ClientAddr, ClientRealAddr};
+
+#[get("/")]
+fn index(client_addr: &ClientRealAddr) -> String {
+    client_addr.ip.to_string()
+}
+
+fn client() -> Client {
+    Client::untracked(rocket::build().mount("/", routes![index])).unwrap()
+}
+
+fn request(client: &Client, forwarded_for: &str) -> String {
+    client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", forwarded_for.to_string()))
+        .dispatch()
+        .into_string()
+        .unwrap()
+}
+
+#[test]
+fn leftmost() {
+    let client = client();
+
+    assert_eq!("203.0.113.5", request(&client, "203.0.113.5, 10.0.0.2"));
+}
+
+#[test]
+fn empty_tokens() {
+    let client = client();
+
+    assert_eq!("203.0.113.5", request(&client, ", 203.0.113.5"));
+    assert_eq!("203.0.113.5", request(&client, " , ,203.0.113.5, 10.0.0.2"));
+    assert_eq!("203.0.113.5", request(&client, "203.0.113.5,"));
+    assert_eq!("10.0.0.1", request(&client, ", "));
+}
+
+#[test]
+fn conversion() {
+    let client_addr = ClientAddr { ip: "93.184.216.34".parse().unwrap() };
+
+    let real_addr = ClientRealAddr::from(client_addr);
+
+    assert_eq!(client_addr.ip, real_addr.ip);
+    assert_eq!(client_addr, ClientAddr::from(real_addr));
+}
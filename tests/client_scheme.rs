@@ -0,0 +1,111 @@
+use rocket::{http::Header, local::blocking::Client};
+use rocket_client_addr::{client_scheme, IpNetSet, Resolver, Scheme};
+
+fn scheme(
+    client: &Client,
+    remote: &str,
+    headers: &[(&'static str, &'static str)],
+) -> Option<Scheme> {
+    let mut request = client.get("/").remote(remote.parse().unwrap());
+
+    for (name, value) in headers {
+        request = request.header(Header::new(*name, *value));
+    }
+
+    client_scheme(request.inner())
+}
+
+#[test]
+fn forwarded_proto() {
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    let remote = "10.0.0.1:8000";
+
+    assert_eq!(Some(Scheme::Https), scheme(&client, remote, &[("X-Forwarded-Proto", "https")]));
+    assert_eq!(Some(Scheme::Http), scheme(&client, remote, &[("X-Forwarded-Proto", "HTTP")]));
+    // the entry added by the TCP peer, because the client can send the others
+    assert_eq!(
+        Some(Scheme::Http),
+        scheme(&client, remote, &[("X-Forwarded-Proto", "https, http")])
+    );
+    // the entry at the position of the client in `X-Forwarded-For`
+    assert_eq!(
+        Some(Scheme::Http),
+        scheme(&client, remote, &[
+            ("X-Forwarded-Proto", "https, http"),
+            ("X-Forwarded-For", "1.1.1.1, 93.184.216.34")
+        ])
+    );
+    assert_eq!(
+        Some(Scheme::Https),
+        scheme(&client, remote, &[
+            ("X-Forwarded-Proto", "https, http"),
+            ("X-Forwarded-For", "93.184.216.34, 10.0.0.2")
+        ])
+    );
+    assert_eq!(None, scheme(&client, remote, &[("X-Forwarded-Proto", "wss")]));
+    assert_eq!(None, scheme(&client, remote, &[]));
+}
+
+#[test]
+fn forwarded() {
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    let remote = "10.0.0.1:8000";
+
+    assert_eq!(
+        Some(Scheme::Https),
+        scheme(&client, remote, &[("Forwarded", "for=93.184.216.34;proto=https, for=10.0.0.2")])
+    );
+    // a client can prepend an element
+    assert_eq!(
+        Some(Scheme::Http),
+        scheme(&client, remote, &[(
+            "Forwarded",
+            "for=1.1.1.1;proto=https, for=93.184.216.34;proto=http"
+        )])
+    );
+    // the rightmost element has no address, so it is used
+    assert_eq!(
+        Some(Scheme::Http),
+        scheme(&client, remote, &[("Forwarded", "for=93.184.216.34;proto=https, proto=http")])
+    );
+    assert_eq!(None, scheme(&client, remote, &[("Forwarded", "for=93.184.216.34")]));
+
+    // X-Forwarded-Proto takes precedence
+    assert_eq!(
+        Some(Scheme::Http),
+        scheme(&client, remote, &[("X-Forwarded-Proto", "http"), ("Forwarded", "proto=https")])
+    );
+}
+
+#[test]
+fn untrusted_peer() {
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    assert_eq!(None, scheme(&client, "34.120.0.1:8000", &[("X-Forwarded-Proto", "https")]));
+
+    let resolver = Resolver::builder()
+        .trust_proxies("34.120.0.0/16".parse().into_iter().collect::<IpNetSet>())
+        .build();
+
+    let client = Client::untracked(rocket::build().manage(resolver)).unwrap();
+
+    assert_eq!(
+        Some(Scheme::Https),
+        scheme(&client, "34.120.0.1:8000", &[("X-Forwarded-Proto", "https")])
+    );
+}
+
+#[test]
+fn always_use_headers() {
+    let resolver = Resolver::builder().always_use_headers(true).build();
+
+    let client = Client::untracked(rocket::build().manage(resolver)).unwrap();
+
+    // the TCP peer is not checked
+    assert_eq!(
+        Some(Scheme::Https),
+        scheme(&client, "34.120.0.1:8000", &[("X-Forwarded-Proto", "https")])
+    );
+}
@@ -0,0 +1,57 @@
+#[macro_use]
+extern crate rocket;
+
+use std::net::{IpAddr, Ipv4Addr};
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+};
+use rocket_client_addr::ConnectionAddrs;
+
+#[get("/")]
+fn index(connection_addrs: ConnectionAddrs) -> String {
+    format!("{} {:?}", connection_addrs.client, connection_addrs.local)
+}
+
+fn client(address: IpAddr, port: u16) -> Client {
+    let config = rocket::Config {
+        address,
+        port,
+        ..rocket::Config::debug_default()
+    };
+
+    Client::untracked(rocket::custom(config).mount("/", routes![index])).unwrap()
+}
+
+#[test]
+fn bound_address() {
+    let client = client(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)), 8000);
+
+    let response = client
+        .get("/")
+        .remote("10.0.0.1:50000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "93.184.216.34"))
+        .dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!("10.0.0.1 Some(10.0.0.2:8000)", response.into_string().unwrap());
+}
+
+#[test]
+fn unspecified_address() {
+    let client = client(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 8000);
+
+    let response = client.get("/").remote("10.0.0.1:50000".parse().unwrap()).dispatch();
+
+    assert_eq!("10.0.0.1 None", response.into_string().unwrap());
+}
+
+#[test]
+fn absent() {
+    let client = client(IpAddr::V4(Ipv4Addr::LOCALHOST), 8000);
+
+    let response = client.get("/").dispatch();
+
+    assert_eq!(Status::BadRequest, response.status());
+}
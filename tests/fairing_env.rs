@@ -0,0 +1,74 @@
+use rocket::{error::ErrorKind, figment::Figment, local::blocking::Client};
+use rocket_client_addr::{CdnHeader, ClientAddrFairing, Resolver};
+
+fn figment() -> Figment {
+    rocket::Config::figment()
+        .merge(("client_addr.trusted_proxies", ["10.0.0.0/8"]))
+        .merge(("client_addr.trusted_headers", ["Fastly-Client-IP"]))
+}
+
+/// Ignite Rocket and get the managed `Resolver`, which is `None` if no `Resolver` is managed. `Err(())` if the ignition fails.
+fn ignite(figment: Figment) -> Result<Option<Resolver>, ()> {
+    let rocket = rocket::custom(figment).attach(ClientAddrFairing::new());
+
+    match Client::untracked(rocket) {
+        Ok(client) => Ok(client.rocket().state::<Resolver>().cloned()),
+        Err(error) => {
+            assert!(matches!(error.kind(), ErrorKind::FailedFairings(_)));
+
+            Err(())
+        },
+    }
+}
+
+// the environment is shared by every test of a process, so every case is in this one test
+#[test]
+fn environment_variables() {
+    std::env::remove_var("ROCKET_CLIENT_ADDR_TRUSTED_PROXIES");
+    std::env::remove_var("ROCKET_CLIENT_ADDR_TRUSTED_HEADERS");
+
+    // the configuration of Rocket
+    let resolver = ignite(figment()).unwrap().unwrap();
+
+    assert!(resolver.config().trusted_proxies.contains(&"10.1.2.3".parse().unwrap()));
+    assert_eq!(vec![CdnHeader::Fastly], resolver.config().trusted_cdn_headers);
+
+    // the environment variables override it
+    std::env::set_var("ROCKET_CLIENT_ADDR_TRUSTED_PROXIES", "34.120.0.0/16, 2606:4700::/32,");
+    std::env::set_var("ROCKET_CLIENT_ADDR_TRUSTED_HEADERS", "cf-connecting-ip");
+
+    for figment in [figment(), rocket::Config::figment()] {
+        let resolver = ignite(figment).unwrap().unwrap();
+        let trusted_proxies = &resolver.config().trusted_proxies;
+
+        assert_eq!(2, trusted_proxies.as_slice().len());
+        assert!(trusted_proxies.contains(&"34.120.5.6".parse().unwrap()));
+        assert!(trusted_proxies.contains(&"2606:4700::1111".parse().unwrap()));
+        assert!(!trusted_proxies.contains(&"10.1.2.3".parse().unwrap()));
+        assert_eq!(vec![CdnHeader::Cloudflare], resolver.config().trusted_cdn_headers);
+    }
+
+    // a blank variable is ignored
+    std::env::set_var("ROCKET_CLIENT_ADDR_TRUSTED_HEADERS", " ");
+
+    assert_eq!(
+        vec![CdnHeader::Fastly],
+        ignite(figment()).unwrap().unwrap().config().trusted_cdn_headers
+    );
+
+    // an invalid entry aborts the ignition
+    std::env::set_var("ROCKET_CLIENT_ADDR_TRUSTED_PROXIES", "34.120.0.0/33");
+
+    assert!(ignite(figment()).is_err());
+
+    std::env::set_var("ROCKET_CLIENT_ADDR_TRUSTED_PROXIES", "34.120.0.0/16");
+    std::env::set_var("ROCKET_CLIENT_ADDR_TRUSTED_HEADERS", "X-Client-IP");
+
+    assert!(ignite(figment()).is_err());
+
+    std::env::remove_var("ROCKET_CLIENT_ADDR_TRUSTED_PROXIES");
+    std::env::remove_var("ROCKET_CLIENT_ADDR_TRUSTED_HEADERS");
+
+    // neither is set, so the ignition succeeds without a managed `Resolver`
+    assert!(matches!(ignite(rocket::Config::figment()), Ok(None)));
+}
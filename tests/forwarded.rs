@@ -0,0 +1,388 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+    request::{FromRequest, Outcome, Request},
+};
+use rocket_client_addr::{
+    ClientAddr, ClientAddrError, ForwardedEntry, ForwardedFor, ParsedForwarded, Resolver,
+};
+
+struct Chain(Vec<ForwardedFor>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Chain {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(Chain(ForwardedFor::chain(request)))
+    }
+}
+
+#[get("/")]
+fn index(client_addr: &ClientAddr) -> String {
+    client_addr.to_string()
+}
+
+#[get("/parsed")]
+fn parsed(forwarded: ParsedForwarded) -> String {
+    format!("{:?}", forwarded.entries)
+}
+
+#[get("/chain")]
+fn chain(chain: Chain) -> String {
+    format!("{:?}", chain.0)
+}
+
+fn client() -> Client {
+    Client::untracked(rocket::build().mount("/", routes![index, chain, parsed])).unwrap()
+}
+
+fn get(uri: &'static str, forwarded: &'static str) -> (Status, String) {
+    let client = client();
+
+    let response = client
+        .get(uri)
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("Forwarded", forwarded))
+        .dispatch();
+
+    (response.status(), response.into_string().unwrap_or_default())
+}
+
+#[test]
+fn parse_nodes() {
+    let (_, chain) = get(
+        "/chain",
+        "for=93.184.216.34:4711;proto=https, For=\"[2606:4700::1111]:443\", for=unknown, \
+         for=_hidden;by=_proxy, for=\"_SEVKISEK\"",
+    );
+
+    let expected = vec![
+        ForwardedFor::Addr("93.184.216.34".parse().unwrap()),
+        ForwardedFor::Addr("2606:4700::1111".parse().unwrap()),
+        ForwardedFor::Unknown,
+        ForwardedFor::Obfuscated("_hidden".to_string()),
+        ForwardedFor::Obfuscated("_SEVKISEK".to_string()),
+    ];
+
+    assert_eq!(format!("{expected:?}"), chain);
+}
+
+#[test]
+fn invalid_nodes_are_omitted() {
+    let (_, chain) = get("/chain", "for=\"2606:4700::1111\", for=1.1.1.1:http, proto=https, for=_");
+
+    assert_eq!("[]", chain);
+}
+
+#[test]
+fn resolve() {
+    assert_eq!((Status::Ok, "93.184.216.34".to_string()), get("/", "for=93.184.216.34"));
+
+    assert_eq!(
+        (Status::Ok, "93.184.216.34".to_string()),
+        get("/", "for=1.1.1.1, for=\"93.184.216.34:4711\", for=10.0.0.2")
+    );
+}
+
+#[test]
+fn obfuscated_nodes_are_not_selected() {
+    // the walk stops at the hidden hop, so the nearest address is used
+    assert_eq!(
+        (Status::Ok, "10.0.0.2".to_string()),
+        get("/", "for=93.184.216.34, for=_hidden, for=10.0.0.2")
+    );
+
+    assert_eq!(
+        (Status::Ok, "93.184.216.34".to_string()),
+        get("/", "for=unknown, for=93.184.216.34, for=10.0.0.2")
+    );
+
+    // nothing to select, so the TCP peer is used
+    assert_eq!((Status::Ok, "10.0.0.1".to_string()), get("/", "for=unknown"));
+}
+
+#[test]
+fn forwarded_for_takes_precedence() {
+    let client = client();
+
+    let response = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "1.1.1.1"))
+        .header(Header::new("Forwarded", "for=93.184.216.34"))
+        .dispatch();
+
+    assert_eq!("1.1.1.1", response.into_string().unwrap());
+}
+
+fn addr(ip: &str) -> Option<ForwardedFor> {
+    Some(ForwardedFor::Addr(ip.parse().unwrap()))
+}
+
+#[test]
+fn parse_rfc_examples() {
+    assert_eq!(
+        vec![ForwardedEntry {
+            for_: Some(ForwardedFor::Obfuscated("_gazonk".to_string())),
+            ..ForwardedEntry::default()
+        }],
+        ParsedForwarded::parse(["for=\"_gazonk\""]).entries
+    );
+
+    assert_eq!(
+        vec![ForwardedEntry {
+            for_: addr("2001:db8:cafe::17"),
+            ..ForwardedEntry::default()
+        }],
+        ParsedForwarded::parse(["For=\"[2001:db8:cafe::17]:4711\""]).entries
+    );
+
+    assert_eq!(
+        vec![ForwardedEntry {
+            for_: addr("192.0.2.60"),
+            by: addr("203.0.113.43"),
+            proto: Some("http".to_string()),
+            ..ForwardedEntry::default()
+        }],
+        ParsedForwarded::parse(["for=192.0.2.60;proto=http;by=203.0.113.43"]).entries
+    );
+
+    assert_eq!(
+        vec![
+            ForwardedEntry {
+                for_: addr("192.0.2.43"),
+                ..ForwardedEntry::default()
+            },
+            ForwardedEntry {
+                for_: addr("198.51.100.17"),
+                ..ForwardedEntry::default()
+            },
+        ],
+        ParsedForwarded::parse(["for=192.0.2.43, for=198.51.100.17"]).entries
+    );
+
+    assert_eq!(
+        vec![
+            addr("192.0.2.43"),
+            addr("2001:db8:cafe::17"),
+            Some(ForwardedFor::Unknown),
+        ],
+        ParsedForwarded::parse(["for=192.0.2.43,for=\"[2001:db8:cafe::17]\",for=unknown"])
+            .entries
+            .into_iter()
+            .map(|entry| entry.for_)
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn parse_multiple_fields_and_headers() {
+    let client = client();
+
+    let response = client
+        .get("/parsed")
+        .header(Header::new(
+            "Forwarded",
+            "for=93.184.216.34:4711;host=\"example.com:8080\";proto=https, ,",
+        ))
+        .header(Header::new("Forwarded", "by=_proxy;for=10.0.0.2;Proto=HTTP;secret=1"))
+        .dispatch();
+
+    let expected = vec![
+        ForwardedEntry {
+            for_:  addr("93.184.216.34"),
+            by:    None,
+            host:  Some("example.com:8080".to_string()),
+            proto: Some("https".to_string()),
+        },
+        ForwardedEntry {
+            for_:  addr("10.0.0.2"),
+            by:    Some(ForwardedFor::Obfuscated("_proxy".to_string())),
+            host:  None,
+            proto: Some("HTTP".to_string()),
+        },
+    ];
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!(format!("{expected:?}"), response.into_string().unwrap());
+}
+
+#[test]
+fn by() {
+    assert_eq!(
+        Some("203.0.113.43".parse().unwrap()),
+        ParsedForwarded::parse(["for=192.0.2.60;proto=http;by=203.0.113.43"]).by()
+    );
+    assert_eq!(
+        Some("2001:db8::1".parse().unwrap()),
+        ParsedForwarded::parse([
+            "for=192.0.2.60;by=198.51.100.1",
+            "for=10.0.0.2;by=\"[2001:db8::1]:80\""
+        ])
+        .by()
+    );
+
+    assert_eq!(None, ParsedForwarded::parse(["for=192.0.2.60;by=unknown"]).by());
+    assert_eq!(None, ParsedForwarded::parse(["for=192.0.2.60;by=_proxy"]).by());
+    assert_eq!(None, ParsedForwarded::parse(["by=203.0.113.43, for=192.0.2.60"]).by());
+    assert_eq!(None, ParsedForwarded::parse(["for=192.0.2.60;by=invalid"]).by());
+    assert_eq!(None, ParsedForwarded::parse([]).by());
+}
+
+#[test]
+fn quoted_pairs() {
+    let parse = |value: &str| ParsedForwarded::parse([value]).entries;
+
+    assert_eq!(addr("2001:db8::1"), parse(r#"for="[2001:db8::1\]""#)[0].for_);
+    assert_eq!(addr("2001:db8::1"), parse(r#"for="\[2001:db8::1\]:80""#)[0].for_);
+    assert_eq!(addr("192.0.2.60"), parse(r#"for="\1\9\2.0.2.60""#)[0].for_);
+    assert_eq!(Some("exa\"mple.com".to_string()), parse(r#"host="exa\"mple.com""#)[0].host);
+
+    // an escaped quote or separator does not end the quoted string
+    let entries = parse(r#"host="a\",b;c";for=192.0.2.60, for=198.51.100.17"#);
+
+    assert_eq!(2, entries.len());
+    assert_eq!(Some("a\",b;c".to_string()), entries[0].host);
+    assert_eq!(addr("192.0.2.60"), entries[0].for_);
+    assert_eq!(addr("198.51.100.17"), entries[1].for_);
+}
+
+#[test]
+fn malformed_quoted_strings() {
+    let parse = |value: &str| ParsedForwarded::parse([value]).entries;
+
+    for value in [
+        r#"for="192.0.2.60"#,
+        r#"for="192.0.2.60\""#,
+        r#"for="192.0.2.60\"#,
+        r#"for="192.0.2"60""#,
+        r#"for=192.0.2.60""#,
+        r#"for="""#,
+        r#"for=""#,
+        r#"for=\"#,
+        "for=\"\\",
+    ] {
+        assert_eq!(None, parse(value).first().and_then(|entry| entry.for_.clone()), "{value}");
+    }
+
+    assert_eq!(None, parse(r#"proto="https;for=192.0.2.60"#)[0].proto);
+    assert_eq!(None, parse(r#"host=exa"mple.com"#)[0].host);
+}
+
+fn try_resolve(
+    resolver: &Resolver,
+    forwarded: &'static str,
+) -> Result<Option<String>, ClientAddrError> {
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    let request = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("Forwarded", forwarded))
+        .header(Header::new("X-Real-IP", "34.120.0.2"));
+
+    resolver
+        .try_resolve(request.inner())
+        .map(|client_addr| client_addr.map(|client_addr| client_addr.to_string()))
+}
+
+#[test]
+fn empty_headers() {
+    let lenient = Resolver::default();
+    let strict = Resolver::builder().strict_parsing(true).build();
+
+    // treated as absent, so the next source is used
+    for forwarded in ["", "   ", " , ;, "] {
+        assert_eq!(
+            Ok(Some("34.120.0.2".into())),
+            try_resolve(&lenient, forwarded),
+            "{forwarded:?}"
+        );
+        assert_eq!(Ok(Some("34.120.0.2".into())), try_resolve(&strict, forwarded), "{forwarded:?}");
+    }
+}
+
+#[test]
+fn malformed_headers() {
+    let lenient = Resolver::default();
+    let strict = Resolver::builder().strict_parsing(true).build();
+
+    for forwarded in [
+        "garbage",
+        "for",
+        "for=",
+        "=93.184.216.34",
+        "for=93.184.216.34;garbage",
+        "for=\"93.184.216.34",
+        "for=93.184.216.34\"",
+        "for=\"[2606:4700::1111\"",
+        "for=2606:4700::1111",
+        "for=93.184.216.34:http",
+        "for=93.184.216.34;proto=",
+        "for=93.184.216.34;pro to=https",
+        "for=93.184.216.34, by=garbage",
+        "for=93.184.216.34;host=\"unterminated",
+    ] {
+        assert!(try_resolve(&lenient, forwarded).is_ok(), "{forwarded:?}");
+        assert_eq!(
+            Err(ClientAddrError::MalformedHeader),
+            try_resolve(&strict, forwarded),
+            "{forwarded:?}"
+        );
+    }
+
+    // with no address to select, the lenient resolution skips to the next source
+    assert_eq!(Ok(Some("34.120.0.2".into())), try_resolve(&lenient, "garbage"));
+    assert_eq!(Ok(Some("34.120.0.2".into())), try_resolve(&lenient, "for=\"[2606:4700::1111\""));
+}
+
+#[test]
+fn well_formed_headers_are_accepted_by_strict_parsing() {
+    let strict = Resolver::builder().strict_parsing(true).build();
+
+    for forwarded in [
+        "for=93.184.216.34",
+        "For=\"93.184.216.34:4711\";proto=https;by=_proxy",
+        "for=\"[2606:4700::1111]:443\", for=93.184.216.34",
+        "proto=https;for=93.184.216.34;",
+        "for=unknown, , for=93.184.216.34",
+        "for=93.184.216.34;host=\"example.com:8080\";secret=\"a\\\"b\"",
+    ] {
+        assert_eq!(
+            Ok(Some("93.184.216.34".into())),
+            try_resolve(&strict, forwarded),
+            "{forwarded:?}"
+        );
+    }
+}
+
+#[test]
+fn garbage_does_not_panic() {
+    for forwarded in [
+        "\"",
+        "\\",
+        "=",
+        ";",
+        ",",
+        "=;=,=",
+        "for=\"\\",
+        "for=[",
+        "for=[]",
+        "for=[::1",
+        "for=[::1]:",
+        "for=\"[::1]:\"",
+        "for=_",
+        "for=\u{1F600}",
+        "\u{0}for=1.1.1.1",
+        "for=\"\"",
+        "for==1.1.1.1",
+    ] {
+        let _ = ParsedForwarded::parse([forwarded]);
+        let _ = try_resolve(&Resolver::default(), forwarded);
+        let _ = try_resolve(&Resolver::builder().strict_parsing(true).build(), forwarded);
+    }
+}
@@ -0,0 +1,47 @@
+use std::{cell::Cell, net::IpAddr};
+
+use rocket_client_addr::{ClientAddr, GeoDb, GeoInfo};
+
+struct Db {
+    lookups: Cell<usize>,
+}
+
+impl GeoDb for Db {
+    fn lookup(&self, ip: IpAddr) -> Option<GeoInfo> {
+        self.lookups.set(self.lookups.get() + 1);
+
+        if ip == "93.184.216.34".parse::<IpAddr>().unwrap() {
+            Some(GeoInfo {
+                country: Some("US".into()),
+                region:  Some("California".into()),
+                city:    Some("Los Angeles".into()),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+fn client_addr(ip: &str) -> ClientAddr {
+    ClientAddr { ip: ip.parse().unwrap() }
+}
+
+#[test]
+fn geo() {
+    let db = Db { lookups: Cell::new(0) };
+
+    let info = client_addr("93.184.216.34").geo(&db).unwrap();
+
+    assert_eq!(Some("US"), info.country.as_deref());
+    assert_eq!(Some("California"), info.region.as_deref());
+    assert_eq!(Some("Los Angeles"), info.city.as_deref());
+    assert_eq!(Some(info), client_addr("::ffff:93.184.216.34").geo(&db));
+    assert_eq!(None, client_addr("1.1.1.1").geo(&db));
+    assert_eq!(3, db.lookups.get());
+
+    for ip in ["10.0.0.1", "127.0.0.1", "192.168.1.1", "::1", "fd12:3456::1", "2001:db8::1"] {
+        assert_eq!(None, client_addr(ip).geo(&db), "{ip}");
+    }
+
+    assert_eq!(3, db.lookups.get());
+}
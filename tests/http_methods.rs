@@ -0,0 +1,92 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{
+    http::{Header, Method, Status},
+    local::blocking::Client,
+    Request,
+};
+use rocket_client_addr::{CachedClientAddr, ClientAddr, ClientAddrFairing, Resolver};
+
+#[route(GET, uri = "/")]
+fn get(client_addr: &ClientAddr) -> String {
+    client_addr.to_string()
+}
+
+#[options("/")]
+fn preflight(client_addr: &ClientAddr) -> String {
+    client_addr.to_string()
+}
+
+/// Rocket has no route for `CONNECT`, so such a request is caught, after `ClientAddrFairing` resolved it.
+#[catch(404)]
+fn not_found(request: &Request<'_>) -> String {
+    CachedClientAddr::get(request).map(|client_addr| client_addr.to_string()).unwrap_or_default()
+}
+
+fn client() -> Client {
+    Client::untracked(
+        rocket::build()
+            .attach(ClientAddrFairing::default())
+            .mount("/", routes![get, preflight])
+            .register("/", catchers![not_found]),
+    )
+    .unwrap()
+}
+
+#[test]
+fn every_method_resolves_the_same() {
+    let client = client();
+
+    for method in [Method::Get, Method::Options, Method::Connect] {
+        let response = client
+            .req(method, "/")
+            .remote("10.0.0.1:8000".parse().unwrap())
+            .header(Header::new("X-Forwarded-For", "93.184.216.34, 10.0.0.2"))
+            .dispatch();
+
+        assert_eq!("93.184.216.34", response.into_string().unwrap(), "{method}");
+    }
+}
+
+#[test]
+fn options_preflight() {
+    let client = client();
+
+    let response = client
+        .req(Method::Options, "/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("Origin", "https://example.com"))
+        .header(Header::new("Access-Control-Request-Method", "POST"))
+        .header(Header::new("X-Forwarded-For", "2606:4700::1111"))
+        .dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!("2606:4700::1111", response.into_string().unwrap());
+
+    // a preflight request without forwarding headers gets the TCP peer
+    let response =
+        client.req(Method::Options, "/").remote("10.0.0.1:8000".parse().unwrap()).dispatch();
+
+    assert_eq!("10.0.0.1", response.into_string().unwrap());
+}
+
+#[test]
+fn resolver_ignores_the_method() {
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    for method in [Method::Get, Method::Head, Method::Post, Method::Options, Method::Connect] {
+        let request = client
+            .req(method, "/")
+            .remote("10.0.0.1:8000".parse().unwrap())
+            .header(Header::new("X-Forwarded-For", "93.184.216.34"));
+
+        assert_eq!(
+            Some(ClientAddr {
+                ip: "93.184.216.34".parse().unwrap()
+            }),
+            Resolver::default().resolve(request.inner()),
+            "{method}"
+        );
+    }
+}
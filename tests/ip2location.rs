@@ -0,0 +1,151 @@
+#![cfg(feature = "ip2location")]
+
+use std::{
+    fs,
+    net::{Ipv4Addr, Ipv6Addr},
+    path::PathBuf,
+};
+
+use ip2location::LocationDB;
+use rocket_client_addr::{ClientAddr, GeoInfo, Ip2LocationDb};
+
+/// Write a minimal IP2Location DB3 (country, region and city) BIN file, whose rows are `(from, country, region, city)` for IPv4 and IPv6.
+fn write_db3(
+    name: &str,
+    ipv4_rows: &[(u32, &str, &str, &str)],
+    ipv6_rows: &[(Ipv6Addr, &str, &str, &str)],
+) -> PathBuf {
+    const HEADER_SIZE: usize = 64;
+    const IPV4_ROW_SIZE: usize = 16;
+    const IPV6_ROW_SIZE: usize = 28;
+
+    // two sentinel rows follow the rows, because a lookup reads the row after the matched one
+    let ipv4_addr = HEADER_SIZE;
+    let ipv6_addr = ipv4_addr + (ipv4_rows.len() + 2) * IPV4_ROW_SIZE;
+    let strings_addr = ipv6_addr + (ipv6_rows.len() + 2) * IPV6_ROW_SIZE;
+
+    let mut strings = Vec::new();
+
+    let mut string = |value: &str, size: usize| {
+        let offset = (strings_addr + strings.len()) as u32;
+
+        strings.push(value.len() as u8);
+        strings.extend_from_slice(value.as_bytes());
+        strings.resize(strings.len().max(offset as usize - strings_addr + size), 0);
+
+        offset
+    };
+
+    let mut fields = |country: &str, region: &str, city: &str| {
+        // the short name of a country takes 3 bytes and is followed by its long name
+        let country_offset = string(country, 3);
+        string(country, 0);
+
+        [country_offset, string(region, 0), string(city, 0)]
+    };
+
+    let mut ipv4 = Vec::new();
+
+    for &(from, country, region, city) in ipv4_rows {
+        ipv4.extend_from_slice(&from.to_le_bytes());
+
+        for offset in fields(country, region, city) {
+            ipv4.extend_from_slice(&offset.to_le_bytes());
+        }
+    }
+
+    let mut ipv6 = Vec::new();
+
+    for &(from, country, region, city) in ipv6_rows {
+        ipv6.extend_from_slice(&u128::from(from).to_le_bytes());
+
+        for offset in fields(country, region, city) {
+            ipv6.extend_from_slice(&offset.to_le_bytes());
+        }
+    }
+
+    for _ in 0..2 {
+        ipv4.extend_from_slice(&u32::MAX.to_le_bytes());
+        ipv4.extend_from_slice(&[0; IPV4_ROW_SIZE - 4]);
+        ipv6.extend_from_slice(&u128::MAX.to_le_bytes());
+        ipv6.extend_from_slice(&[0; IPV6_ROW_SIZE - 16]);
+    }
+
+    let mut header = vec![0u8; HEADER_SIZE];
+
+    header[0] = 3; // DB3
+    header[1] = 4; // columns
+    header[2..5].copy_from_slice(&[24, 1, 1]); // date
+    header[5..9].copy_from_slice(&(ipv4_rows.len() as u32).to_le_bytes());
+    header[9..13].copy_from_slice(&(ipv4_addr as u32 + 1).to_le_bytes());
+    header[13..17].copy_from_slice(&(ipv6_rows.len() as u32).to_le_bytes());
+    header[17..21].copy_from_slice(&(ipv6_addr as u32 + 1).to_le_bytes());
+    header[29] = 1; // product code
+
+    let path =
+        std::env::temp_dir().join(format!("rocket-client-addr-{}-{name}.BIN", std::process::id()));
+
+    fs::write(&path, [header, ipv4, ipv6, strings].concat()).unwrap();
+
+    path
+}
+
+fn client_addr(ip: &str) -> ClientAddr {
+    ClientAddr {
+        ip: ip.parse().unwrap()
+    }
+}
+
+#[test]
+fn geo() {
+    let ipv4 = |ip: &str| u32::from(ip.parse::<Ipv4Addr>().unwrap());
+
+    let path = write_db3(
+        "geo",
+        &[
+            (0, "-", "-", "-"),
+            (ipv4("1.1.1.0"), "AU", "Queensland", "-"),
+            (ipv4("1.1.2.0"), "-", "-", "-"),
+            (ipv4("93.184.216.0"), "US", "California", "Los Angeles"),
+            (ipv4("93.184.217.0"), "-", "-", "-"),
+        ],
+        &[
+            (Ipv6Addr::UNSPECIFIED, "-", "-", "-"),
+            ("2606:4700::".parse().unwrap(), "US", "-", "-"),
+            ("2606:4701::".parse().unwrap(), "-", "-", "-"),
+        ],
+    );
+
+    let db = Ip2LocationDb::new(LocationDB::from_file(&path).unwrap());
+
+    fs::remove_file(path).unwrap();
+
+    let los_angeles = GeoInfo {
+        country: Some("US".into()),
+        region:  Some("California".into()),
+        city:    Some("Los Angeles".into()),
+    };
+
+    assert_eq!(Some(los_angeles.clone()), client_addr("93.184.216.34").geo(&db));
+    assert_eq!(Some(los_angeles), client_addr("::ffff:93.184.216.34").geo(&db));
+    assert_eq!(
+        Some(GeoInfo {
+            country: Some("AU".into()),
+            region:  Some("Queensland".into()),
+            city:    None,
+        }),
+        client_addr("1.1.1.1").geo(&db)
+    );
+    assert_eq!(
+        Some(GeoInfo {
+            country: Some("US".into()),
+            ..GeoInfo::default()
+        }),
+        client_addr("2606:4700::1111").geo(&db)
+    );
+    assert_eq!(None, client_addr("93.184.217.1").geo(&db));
+    assert_eq!(None, client_addr("2001:4860::8888").geo(&db));
+
+    // a local address is never looked up, even if the database has it
+    assert_eq!(None, client_addr("10.0.0.1").geo(&db));
+}
@@ -0,0 +1,159 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use rocket_client_addr::{IpNet, IpNetError, IpNetSet};
+
+#[test]
+fn const_construction() {
+    const NET_V4: IpNet = IpNet::new_v4(Ipv4Addr::new(192, 168, 1, 77), 24);
+    const NET_V6: IpNet = IpNet::new_v6(Ipv6Addr::new(0x2001, 0xDB8, 0, 0, 0, 0, 0, 1), 32);
+
+    assert_eq!(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 0)), NET_V4.addr());
+    assert_eq!(24, NET_V4.prefix_len());
+    assert_eq!("2001:db8::/32", NET_V6.to_string());
+}
+
+#[test]
+fn contains() {
+    let net: IpNet = "10.0.0.0/8".parse().unwrap();
+
+    assert!(net.contains(&"10.255.255.255".parse().unwrap()));
+    assert!(net.contains(&"::ffff:10.0.0.1".parse().unwrap()));
+    assert!(!net.contains(&"11.0.0.0".parse().unwrap()));
+
+    let net: IpNet = "2001:db8::/32".parse().unwrap();
+
+    assert!(net.contains(&"2001:db8:ffff::1".parse().unwrap()));
+    assert!(!net.contains(&"2001:db9::1".parse().unwrap()));
+    assert!(!net.contains(&"32.1.13.184".parse().unwrap()));
+
+    let net: IpNet = "0.0.0.0/0".parse().unwrap();
+
+    assert!(net.contains(&"203.0.113.5".parse().unwrap()));
+}
+
+#[test]
+fn parse() {
+    assert_eq!(IpNet::new_v4(Ipv4Addr::new(203, 0, 113, 5), 32), "203.0.113.5".parse().unwrap());
+    assert_eq!(Err(IpNetError::InvalidAddr), "203.0.113/24".parse::<IpNet>());
+    assert_eq!(Err(IpNetError::InvalidPrefixLen), "203.0.113.0/33".parse::<IpNet>());
+    assert_eq!(Err(IpNetError::InvalidPrefixLen), "2001:db8::/x".parse::<IpNet>());
+}
+
+#[test]
+fn set() {
+    let mut set: IpNetSet =
+        ["10.0.0.0/8", "2001:db8::/32"].iter().map(|s| s.parse().unwrap()).collect();
+
+    assert!(set.contains(&"10.1.1.1".parse().unwrap()));
+    assert!(!set.contains(&"192.168.1.1".parse().unwrap()));
+
+    set.push("192.168.0.0/16".parse().unwrap());
+
+    assert!(set.contains(&"192.168.1.1".parse().unwrap()));
+    assert!(IpNetSet::new().is_empty());
+}
+
+#[test]
+fn parts() {
+    let net = IpNet::try_from((IpAddr::V6("2001:db8::1".parse().unwrap()), 32)).unwrap();
+
+    assert_eq!("2001:db8::/32", net.to_string());
+    assert_eq!((IpAddr::V6("2001:db8::".parse().unwrap()), 32), <(IpAddr, u8)>::from(net));
+
+    assert_eq!(
+        Err(IpNetError::InvalidPrefixLen),
+        IpNet::try_from((IpAddr::V4(Ipv4Addr::LOCALHOST), 33))
+    );
+}
+
+#[test]
+fn hosts() {
+    let net: IpNet = "10.0.0.0/28".parse().unwrap();
+    let hosts: Vec<IpAddr> = net.hosts().collect();
+
+    assert_eq!(16, hosts.len());
+    assert_eq!(Some(&"10.0.0.0".parse().unwrap()), hosts.first());
+    assert_eq!(Some(&"10.0.0.15".parse().unwrap()), hosts.last());
+    assert!(hosts.iter().all(|ip| net.contains(ip)));
+
+    assert_eq!(
+        vec!["93.184.216.34".parse::<IpAddr>().unwrap()],
+        IpNet::from("93.184.216.34".parse::<IpAddr>().unwrap()).hosts().collect::<Vec<_>>()
+    );
+    assert_eq!(65536, "10.1.0.0/16".parse::<IpNet>().unwrap().hosts().count());
+    assert_eq!(
+        vec!["2001:db8::", "2001:db8::1", "2001:db8::2", "2001:db8::3"],
+        "2001:db8::/126"
+            .parse::<IpNet>()
+            .unwrap()
+            .hosts()
+            .map(|ip| ip.to_string())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        Some("ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff".parse().unwrap()),
+        "ffff:ffff:ffff:ffff:ffff:ffff:ffff:0/112".parse::<IpNet>().unwrap().hosts().last()
+    );
+
+    // too large
+    assert_eq!(0, "10.0.0.0/15".parse::<IpNet>().unwrap().hosts().count());
+    assert_eq!(0, "2001:db8::/64".parse::<IpNet>().unwrap().hosts().count());
+    assert_eq!(0, "::/0".parse::<IpNet>().unwrap().hosts().count());
+}
+
+fn nets(nets: &[&str]) -> Vec<IpNet> {
+    nets.iter().map(|net| net.parse().unwrap()).collect()
+}
+
+#[test]
+fn aggregate() {
+    // four contiguous /26s in any order
+    assert_eq!(
+        nets(&["10.0.0.0/24"]),
+        IpNet::aggregate(&nets(&["10.0.0.128/26", "10.0.0.0/26", "10.0.0.192/26", "10.0.0.64/26"]))
+    );
+
+    // adjacent but not the halves of one network
+    assert_eq!(
+        nets(&["10.0.0.64/26", "10.0.0.128/26"]),
+        IpNet::aggregate(&nets(&["10.0.0.128/26", "10.0.0.64/26"]))
+    );
+
+    // contained and duplicate networks are dropped
+    assert_eq!(
+        nets(&["10.0.0.0/24", "93.184.216.34/32"]),
+        IpNet::aggregate(&nets(&[
+            "93.184.216.34/32",
+            "10.0.0.7/32",
+            "10.0.0.0/24",
+            "10.0.0.128/25",
+            "93.184.216.34/32"
+        ]))
+    );
+
+    // a run of /32s
+    let hosts: Vec<IpNet> =
+        (0..=255).map(|i| format!("93.184.216.{i}/32").parse().unwrap()).collect();
+
+    assert_eq!(nets(&["93.184.216.0/24"]), IpNet::aggregate(&hosts));
+    assert_eq!(
+        nets(&["93.184.216.0/25", "93.184.216.128/26", "93.184.216.192/27"]),
+        IpNet::aggregate(&hosts[..224])
+    );
+
+    // the halves of the whole space
+    assert_eq!(nets(&["0.0.0.0/0"]), IpNet::aggregate(&nets(&["0.0.0.0/1", "128.0.0.0/1"])));
+
+    // the families are kept apart
+    assert_eq!(
+        nets(&["10.0.0.0/8", "::ffff:10.0.0.0/120", "2001:db8::/32"]),
+        IpNet::aggregate(&nets(&[
+            "::ffff:10.0.0.0/120",
+            "2001:db8:8000::/33",
+            "10.0.0.0/8",
+            "2001:db8::/33"
+        ]))
+    );
+
+    assert_eq!(Vec::<IpNet>::new(), IpNet::aggregate(&[]));
+}
@@ -0,0 +1,23 @@
+#![cfg(feature = "ipnetwork")]
+
+use ipnetwork::IpNetwork;
+use rocket_client_addr::IpNet;
+
+#[test]
+fn conversion() {
+    for (network, net) in [
+        ("10.0.0.0/8", "10.0.0.0/8"),
+        ("10.1.2.3/8", "10.0.0.0/8"),
+        ("93.184.216.34/32", "93.184.216.34/32"),
+        ("0.0.0.0/0", "0.0.0.0/0"),
+        ("2001:db8::1/32", "2001:db8::/32"),
+        ("::ffff:10.0.0.1/128", "::ffff:10.0.0.1/128"),
+    ] {
+        let network = network.parse::<IpNetwork>().unwrap();
+        let net = net.parse::<IpNet>().unwrap();
+
+        assert_eq!(net, IpNet::from(network), "{network}");
+        assert_eq!(net, IpNet::from(IpNetwork::from(net)), "{net}");
+        assert_eq!(net.to_string(), IpNetwork::from(net).to_string());
+    }
+}
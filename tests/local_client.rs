@@ -0,0 +1,161 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+};
+use rocket_client_addr::{CdnHeader, ClientAddr, CustomSource, Resolver, Strategy};
+
+#[get("/")]
+fn index(client_addr: &ClientAddr) -> String {
+    client_addr.to_string()
+}
+
+fn client(resolver: Resolver) -> Client {
+    Client::untracked(rocket::build().manage(resolver).mount("/", routes![index])).unwrap()
+}
+
+/// Send a request and get the resolved IP address, or the status if the request guard fails.
+fn get(
+    client: &Client,
+    remote: Option<&str>,
+    headers: &[(&'static str, &'static str)],
+) -> Result<String, Status> {
+    let mut request = client.get("/");
+
+    if let Some(remote) = remote {
+        request = request.remote(format!("{remote}:8000").parse().unwrap());
+    }
+
+    for (name, value) in headers {
+        request = request.header(Header::new(*name, *value));
+    }
+
+    let response = request.dispatch();
+
+    if response.status() == Status::Ok {
+        Ok(response.into_string().unwrap())
+    } else {
+        Err(response.status())
+    }
+}
+
+/// The TCP peer, the headers and the expected result of a request.
+type Case =
+    (Option<&'static str>, &'static [(&'static str, &'static str)], Result<&'static str, Status>);
+
+fn check(client: &Client, cases: &[Case]) {
+    for (remote, headers, expected) in cases {
+        assert_eq!(
+            expected.map(String::from),
+            get(client, *remote, headers),
+            "{remote:?} {headers:?}"
+        );
+    }
+}
+
+#[test]
+fn default_resolver() {
+    let client = client(Resolver::default());
+
+    let cases: &[Case] = &[
+        // TCP peer
+        (Some("93.184.216.34"), &[], Ok("93.184.216.34")),
+        (Some("10.0.0.1"), &[], Ok("10.0.0.1")),
+        // an untrusted TCP peer wins over every header
+        (Some("34.120.0.9"), &[("X-Forwarded-For", "93.184.216.34")], Ok("34.120.0.9")),
+        (Some("34.120.0.9"), &[("X-Real-IP", "93.184.216.34")], Ok("34.120.0.9")),
+        // X-Forwarded-For, the rightmost untrusted entry
+        (
+            Some("10.0.0.1"),
+            &[("X-Forwarded-For", "1.1.1.1, 93.184.216.34, 10.0.0.2")],
+            Ok("93.184.216.34"),
+        ),
+        // every entry is trusted, so the leftmost one is used
+        (Some("10.0.0.1"), &[("X-Forwarded-For", "10.0.0.3, 10.0.0.2")], Ok("10.0.0.3")),
+        // the walk stops at an invalid entry
+        (
+            Some("10.0.0.1"),
+            &[("X-Forwarded-For", "93.184.216.34, invalid, 10.0.0.2")],
+            Ok("10.0.0.2"),
+        ),
+        // Forwarded, only without X-Forwarded-For
+        (Some("10.0.0.1"), &[("Forwarded", "for=93.184.216.34;proto=https")], Ok("93.184.216.34")),
+        (
+            Some("10.0.0.1"),
+            &[("Forwarded", "for=1.1.1.1"), ("X-Forwarded-For", "93.184.216.34")],
+            Ok("93.184.216.34"),
+        ),
+        // X-Real-IP, after the forwarded chain
+        (Some("10.0.0.1"), &[("X-Real-IP", "93.184.216.34")], Ok("93.184.216.34")),
+        (
+            Some("10.0.0.1"),
+            &[("X-Real-IP", "1.1.1.1"), ("X-Forwarded-For", "93.184.216.34")],
+            Ok("93.184.216.34"),
+        ),
+        // CDN headers are not trusted by default
+        (Some("10.0.0.1"), &[("CF-Connecting-IP", "1.1.1.1")], Ok("10.0.0.1")),
+        // without a TCP peer, the headers are the only sources
+        (None, &[("X-Forwarded-For", "93.184.216.34")], Ok("93.184.216.34")),
+        (None, &[("X-Real-IP", "93.184.216.34")], Ok("93.184.216.34")),
+        (None, &[("X-Forwarded-For", "invalid")], Err(Status::BadRequest)),
+        (None, &[], Err(Status::BadRequest)),
+    ];
+
+    check(&client, cases);
+}
+
+#[test]
+fn configured_resolver() {
+    let client = client(
+        Resolver::builder()
+            .trust_cdn_header(CdnHeader::Cloudflare)
+            .trust_proxies("34.120.0.0/16".parse().into_iter().collect())
+            .custom_source(CustomSource::Header("X-Client-IP".into()))
+            .strategy(Strategy::Leftmost {
+                verify_trusted_suffix: true
+            })
+            .strict_parsing(true)
+            .build(),
+    );
+
+    let cases: &[Case] = &[
+        // a trusted CDN header wins over everything, but only from a trusted TCP peer
+        (Some("34.120.0.9"), &[("CF-Connecting-IP", "93.184.216.34")], Ok("93.184.216.34")),
+        (
+            Some("34.120.0.9"),
+            &[("CF-Connecting-IP", "93.184.216.34"), ("X-Client-IP", "1.1.1.1")],
+            Ok("93.184.216.34"),
+        ),
+        (Some("1.0.0.1"), &[("CF-Connecting-IP", "93.184.216.34")], Ok("1.0.0.1")),
+        (Some("1.0.0.1"), &[("X-Forwarded-For", "93.184.216.34")], Ok("1.0.0.1")),
+        // the custom source, before X-Forwarded-For
+        (
+            Some("34.120.0.9"),
+            &[("X-Client-IP", "1.1.1.1"), ("X-Forwarded-For", "93.184.216.34")],
+            Ok("1.1.1.1"),
+        ),
+        // the leftmost entry, if every other entry is trusted
+        (
+            Some("34.120.0.9"),
+            &[("X-Forwarded-For", "93.184.216.34, 34.120.0.1, 10.0.0.2")],
+            Ok("93.184.216.34"),
+        ),
+        (
+            Some("34.120.0.9"),
+            &[("X-Forwarded-For", "93.184.216.34, 1.1.1.1"), ("X-Real-IP", "93.184.216.34")],
+            Err(Status::BadRequest),
+        ),
+        // a malformed X-Forwarded-For is an error
+        (
+            Some("34.120.0.9"),
+            &[("X-Forwarded-For", "93.184.216.34, , 10.0.0.2")],
+            Err(Status::BadRequest),
+        ),
+        (Some("34.120.0.9"), &[("X-Real-IP", "93.184.216.34")], Ok("93.184.216.34")),
+        (Some("34.120.0.9"), &[], Ok("34.120.0.9")),
+    ];
+
+    check(&client, cases);
+}
@@ -0,0 +1,60 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+};
+use rocket_client_addr::LocalhostOnly;
+
+#[get("/")]
+fn index(localhost: LocalhostOnly) -> String {
+    localhost.ip.to_string()
+}
+
+fn client() -> Client {
+    Client::untracked(rocket::build().mount("/", routes![index])).unwrap()
+}
+
+#[test]
+fn loopback_v4() {
+    let client = client();
+
+    let response = client.get("/").remote("127.0.0.1:8000".parse().unwrap()).dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!("127.0.0.1", response.into_string().unwrap());
+}
+
+#[test]
+fn loopback_v6() {
+    let client = client();
+
+    let response = client.get("/").remote("[::1]:8000".parse().unwrap()).dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!("::1", response.into_string().unwrap());
+}
+
+#[test]
+fn non_loopback() {
+    let client = client();
+
+    let response = client
+        .get("/")
+        .remote("203.0.113.5:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "127.0.0.1"))
+        .header(Header::new("X-Real-IP", "127.0.0.1"))
+        .dispatch();
+
+    assert_eq!(Status::Forbidden, response.status());
+}
+
+#[test]
+fn no_remote() {
+    let client = client();
+
+    let response = client.get("/").header(Header::new("X-Real-IP", "127.0.0.1")).dispatch();
+
+    assert_eq!(Status::Forbidden, response.status());
+}
@@ -0,0 +1,368 @@
+use std::net::{IpAddr, Ipv6Addr};
+
+use rocket_client_addr::{ClientAddr, IpNetSet, Ipv6Scope, Octets};
+
+fn client_addr(ip: &str) -> ClientAddr {
+    ClientAddr { ip: ip.parse().unwrap() }
+}
+
+#[test]
+fn ipv6_scope() {
+    assert_eq!(None, client_addr("93.184.216.34").ipv6_scope());
+    assert_eq!(Some(Ipv6Scope::Loopback), client_addr("::1").ipv6_scope());
+    assert_eq!(Some(Ipv6Scope::Unspecified), client_addr("::").ipv6_scope());
+    assert_eq!(Some(Ipv6Scope::LinkLocal), client_addr("fe80::1").ipv6_scope());
+    assert_eq!(Some(Ipv6Scope::LinkLocal), client_addr("ff02::1").ipv6_scope());
+    assert_eq!(Some(Ipv6Scope::SiteLocal), client_addr("fec0::1").ipv6_scope());
+    assert_eq!(Some(Ipv6Scope::UniqueLocal), client_addr("fd12:3456::1").ipv6_scope());
+    assert_eq!(Some(Ipv6Scope::Multicast), client_addr("ff08::1").ipv6_scope());
+    assert_eq!(Some(Ipv6Scope::Global), client_addr("ff0e::1").ipv6_scope());
+    assert_eq!(Some(Ipv6Scope::Global), client_addr("2001:4860::8888").ipv6_scope());
+}
+
+#[test]
+fn octets() {
+    let ipv4 = client_addr("93.184.216.34");
+
+    assert_eq!(Octets::V4([93, 184, 216, 34]), ipv4.octets());
+    assert_eq!(ipv4.ip, IpAddr::from(ipv4.octets()));
+    assert_eq!([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xFF, 0xFF, 93, 184, 216, 34], ipv4.to_bytes_16());
+    assert_eq!(Some(ipv4.get_ipv4().unwrap()), Ipv6Addr::from(ipv4.to_bytes_16()).to_ipv4_mapped());
+
+    let ipv6 = client_addr("2001:4860::8888");
+
+    assert_eq!(16, ipv6.octets().as_slice().len());
+    assert_eq!(ipv6.ip, IpAddr::from(ipv6.octets()));
+    assert_eq!(ipv6.ip, IpAddr::from(ipv6.to_bytes_16()));
+}
+
+#[test]
+fn enclosing_net() {
+    let ipv4 = client_addr("93.184.216.34");
+    let net = ipv4.enclosing_net(24);
+
+    assert_eq!("93.184.216.0/24", net.to_string());
+    assert!(net.contains(&ipv4.ip));
+    assert!(net.contains(&"93.184.216.255".parse().unwrap()));
+    assert!(!net.contains(&"93.184.217.0".parse().unwrap()));
+
+    let ipv6 = client_addr("2001:4860:1234::8888");
+    let net = ipv6.enclosing_net(48);
+
+    assert_eq!("2001:4860:1234::/48", net.to_string());
+    assert!(net.contains(&ipv6.ip));
+    assert!(ipv6.enclosing_net(128).contains(&ipv6.ip));
+}
+
+#[test]
+fn lowercase_strings() {
+    let ipv6 = client_addr("2001:DB8::ABCD");
+
+    assert_eq!("2001:db8::abcd", ipv6.get_ipv6_string());
+    assert_eq!("2001:db8::abcd", ipv6.to_canonical_string());
+    assert_eq!("2001:db8::abcd", ipv6.to_string());
+
+    let mapped = client_addr("::FFFF:93.184.216.34");
+
+    assert_eq!("::ffff:93.184.216.34", mapped.get_ipv6_string());
+    assert_eq!("93.184.216.34", mapped.to_canonical_string());
+}
+
+#[test]
+fn u128_round_trip() {
+    let ipv4 = client_addr("93.184.216.34");
+
+    assert_eq!(0xFFFF_5DB8_D822, ipv4.to_u128());
+    assert_eq!(ipv4, ClientAddr::from_u128(ipv4.to_u128(), true));
+    assert_eq!(client_addr("::ffff:93.184.216.34"), ClientAddr::from_u128(ipv4.to_u128(), false));
+
+    let ipv6 = client_addr("2606:4700::1111");
+
+    assert_eq!(0x2606_4700_0000_0000_0000_0000_0000_1111, ipv6.to_u128());
+    assert_eq!(ipv6, ClientAddr::from_u128(ipv6.to_u128(), false));
+    assert_eq!(ipv6, ClientAddr::from_u128(ipv6.to_u128(), true));
+
+    assert!(client_addr("1.1.1.1").to_u128() < ipv4.to_u128());
+}
+
+#[test]
+fn same_prefix() {
+    let a = client_addr("2606:4700:1:2:a1b2:c3d4:e5f6:7788");
+    let b = client_addr("2606:4700:1:2:1122:3344:5566:7788");
+    let c = client_addr("2606:4700:1:3:a1b2:c3d4:e5f6:7788");
+
+    assert!(a.same_prefix(&b, 64));
+    assert!(!a.same_prefix(&b, 65));
+    assert!(!a.same_prefix(&c, 64));
+    assert!(a.same_prefix(&c, 48));
+    assert!(a.same_prefix(&a, 128));
+    assert!(a.same_prefix(&a, 200));
+
+    let ipv4 = client_addr("93.184.216.34");
+
+    assert!(ipv4.same_prefix(&client_addr("93.184.216.200"), 24));
+    assert!(!ipv4.same_prefix(&client_addr("93.184.217.34"), 24));
+    assert!(ipv4.same_prefix(&client_addr("::ffff:93.184.216.200"), 24));
+    assert!(client_addr("::ffff:93.184.216.200").same_prefix(&ipv4, 24));
+    assert!(ipv4.same_prefix(&ipv4, 64));
+    assert!(!ipv4.same_prefix(&a, 0));
+}
+
+#[test]
+fn matches_published() {
+    let ranges: IpNetSet =
+        ["93.184.216.0/24", "2606:4700::/32"].iter().map(|net| net.parse().unwrap()).collect();
+
+    assert!(client_addr("93.184.216.34").matches_published(&ranges));
+    assert!(client_addr("::ffff:93.184.216.34").matches_published(&ranges));
+    assert!(client_addr("2606:4700::1111").matches_published(&ranges));
+
+    assert!(!client_addr("93.184.217.34").matches_published(&ranges));
+    assert!(!client_addr("1.1.1.1").matches_published(&ranges));
+    assert!(!client_addr("2606:4701::1111").matches_published(&ranges));
+    assert!(!client_addr("93.184.216.34").matches_published(&IpNetSet::new()));
+}
+
+#[test]
+fn cef_field() {
+    assert_eq!("src=93.184.216.34", client_addr("93.184.216.34").to_cef_field());
+    assert_eq!("src=93.184.216.34", client_addr("::ffff:93.184.216.34").to_cef_field());
+    assert_eq!("src=2606:4700::1111", client_addr("2606:4700::1111").to_cef_field());
+    assert_eq!("src=2001:db8::abcd", client_addr("2001:DB8::ABCD").to_cef_field());
+}
+
+#[test]
+fn nat64_ipv4() {
+    let nat64 = client_addr("64:ff9b::93.184.216.34");
+
+    assert_eq!(client_addr("64:ff9b::5db8:d822"), nat64);
+    assert_eq!(Some("93.184.216.34".parse().unwrap()), nat64.nat64_ipv4());
+    assert_eq!(nat64.ip, nat64.canonical_ip());
+
+    assert_eq!(None, client_addr("93.184.216.34").nat64_ipv4());
+    assert_eq!(None, client_addr("::ffff:93.184.216.34").nat64_ipv4());
+    assert_eq!(None, client_addr("64:ff9b:1::5db8:d822").nat64_ipv4());
+    assert_eq!(None, client_addr("2606:4700::1111").nat64_ipv4());
+}
+
+#[test]
+fn is_likely_shared() {
+    assert!(client_addr("100.64.0.1").is_likely_shared());
+    assert!(client_addr("100.127.255.255").is_likely_shared());
+    assert!(client_addr("::ffff:100.100.1.1").is_likely_shared());
+    assert!(client_addr("192.0.0.2").is_likely_shared());
+
+    assert!(!client_addr("100.63.255.255").is_likely_shared());
+    assert!(!client_addr("100.128.0.0").is_likely_shared());
+    assert!(!client_addr("192.0.0.8").is_likely_shared());
+    assert!(!client_addr("93.184.216.34").is_likely_shared());
+    assert!(!client_addr("2606:4700::1111").is_likely_shared());
+}
+
+#[test]
+fn scoped_key() {
+    assert_eq!("93.184.216.34:order-1", client_addr("93.184.216.34").scoped_key("order-1"));
+    assert_eq!(
+        client_addr("93.184.216.34").scoped_key("order-1"),
+        client_addr("::ffff:93.184.216.34").scoped_key("order-1")
+    );
+    assert_eq!("[2606:4700::1111]:order-1", client_addr("2606:4700::1111").scoped_key("order-1"));
+    assert_eq!(
+        client_addr("2606:4700::1111").scoped_key("order-1"),
+        client_addr("2606:4700:0:0:0:0:0:1111").scoped_key("order-1")
+    );
+    assert_ne!(
+        client_addr("93.184.216.34").scoped_key("order-1"),
+        client_addr("93.184.216.35").scoped_key("order-1")
+    );
+
+    // an address and a key with colons cannot be confused with another pair
+    assert_ne!(client_addr("::1").scoped_key("2:order"), client_addr("::1:2").scoped_key("order"));
+    assert_ne!(
+        client_addr("2606:4700::").scoped_key("1111:order-1"),
+        client_addr("2606:4700::1111").scoped_key("order-1")
+    );
+}
+
+#[test]
+fn effective_ipv4() {
+    let ipv4 = Some("93.184.216.34".parse().unwrap());
+
+    // native
+    assert_eq!(ipv4, client_addr("93.184.216.34").effective_ipv4());
+    // IPv4-mapped
+    assert_eq!(ipv4, client_addr("::ffff:93.184.216.34").effective_ipv4());
+    // NAT64
+    assert_eq!(ipv4, client_addr("64:ff9b::93.184.216.34").effective_ipv4());
+    // 6to4
+    assert_eq!(ipv4, client_addr("2002:5db8:d822::1").effective_ipv4());
+    assert_eq!(ipv4, client_addr("2002:5db8:d822:1:2:3:4:5").effective_ipv4());
+    // Teredo, whose client address is inverted
+    assert_eq!(ipv4, client_addr("2001:0:4136:e378:8000:63bf:a247:27dd").effective_ipv4());
+    assert_eq!(
+        Some("192.0.2.45".parse().unwrap()),
+        client_addr("2001:0:4136:e378:8000:63bf:3fff:fdd2").effective_ipv4()
+    );
+
+    assert_eq!(None, client_addr("2606:4700::1111").effective_ipv4());
+    assert_eq!(None, client_addr("2001:db8::1").effective_ipv4());
+    assert_eq!(None, client_addr("2001:1::1").effective_ipv4());
+    assert_eq!(None, client_addr("64:ff9b:1::5db8:d822").effective_ipv4());
+    assert_eq!(None, client_addr("::1").effective_ipv4());
+    assert_eq!(None, client_addr("::93.184.216.34").effective_ipv4());
+}
+
+#[test]
+fn write_canonical() {
+    let mut buffer = String::new();
+
+    for ip in ["93.184.216.34", "::ffff:93.184.216.34", "2001:DB8::ABCD"] {
+        let client_addr = client_addr(ip);
+
+        buffer.clear();
+        client_addr.write_canonical(&mut buffer).unwrap();
+
+        assert_eq!(client_addr.to_canonical_string(), buffer);
+    }
+}
+
+#[test]
+fn broadcast_for() {
+    let broadcast = Some("192.168.1.255".parse().unwrap());
+
+    assert_eq!(broadcast, client_addr("192.168.1.20").broadcast_for(24));
+    assert_eq!(broadcast, client_addr("192.168.1.255").broadcast_for(24));
+    assert_eq!(broadcast, client_addr("::ffff:192.168.1.20").broadcast_for(24));
+
+    assert_eq!(Some("10.255.255.255".parse().unwrap()), client_addr("10.1.2.3").broadcast_for(8));
+    assert_eq!(
+        Some("192.168.1.20".parse().unwrap()),
+        client_addr("192.168.1.20").broadcast_for(32)
+    );
+    assert_eq!(
+        Some("192.168.1.20".parse().unwrap()),
+        client_addr("192.168.1.20").broadcast_for(64)
+    );
+    assert_eq!(
+        Some("255.255.255.255".parse().unwrap()),
+        client_addr("192.168.1.20").broadcast_for(0)
+    );
+
+    assert_eq!(None, client_addr("2606:4700::1111").broadcast_for(64));
+}
+
+#[test]
+fn is_nat_space() {
+    for ip in [
+        "10.0.0.0",
+        "10.255.255.255",
+        "172.16.0.0",
+        "172.31.255.255",
+        "192.168.0.0",
+        "192.168.255.255",
+        "100.64.0.0",
+        "100.127.255.255",
+        "::ffff:10.0.0.1",
+    ] {
+        assert!(client_addr(ip).is_nat_space(), "{ip}");
+    }
+
+    for ip in [
+        "9.255.255.255",
+        "11.0.0.0",
+        "172.15.255.255",
+        "172.32.0.0",
+        "192.167.255.255",
+        "192.169.0.0",
+        "100.63.255.255",
+        "100.128.0.0",
+        "192.0.0.1",
+        "127.0.0.1",
+        "93.184.216.34",
+        "fd12:3456::1",
+    ] {
+        assert!(!client_addr(ip).is_nat_space(), "{ip}");
+    }
+}
+
+#[test]
+fn to_net() {
+    let ipv4 = client_addr("93.184.216.34");
+    let net = ipv4.to_net(24);
+
+    assert_eq!("93.184.216.0/24", net.to_string());
+    assert!(net.contains(&ipv4.ip));
+    assert!(net.contains(&"93.184.216.0".parse().unwrap()));
+    assert!(net.contains(&"93.184.216.255".parse().unwrap()));
+    assert!(!net.contains(&"93.184.215.255".parse().unwrap()));
+    assert!(!net.contains(&"93.184.217.0".parse().unwrap()));
+
+    // an IPv4-mapped IPv6 address is treated as its IPv4 address
+    assert_eq!(net, client_addr("::ffff:93.184.216.34").to_net(24));
+
+    // the prefix is clamped
+    assert_eq!("93.184.216.34/32", ipv4.to_net(64).to_string());
+
+    let ipv6 = client_addr("2606:4700:1:2:a1b2:c3d4:e5f6:7788");
+    let net = ipv6.to_net(64);
+
+    assert_eq!("2606:4700:1:2::/64", net.to_string());
+    assert!(net.contains(&ipv6.ip));
+    assert!(net.contains(&"2606:4700:1:2:1122:3344:5566:7788".parse().unwrap()));
+    assert!(!net.contains(&"2606:4700:1:3::1".parse().unwrap()));
+    assert_eq!(ipv6.enclosing_net(64), net);
+    assert_eq!(128, ipv6.to_net(200).prefix_len());
+}
+
+#[test]
+fn is_documentation() {
+    for ip in [
+        "192.0.2.0",
+        "192.0.2.255",
+        "198.51.100.0",
+        "198.51.100.255",
+        "203.0.113.0",
+        "203.0.113.255",
+        "::ffff:203.0.113.5",
+        "2001:db8::",
+        "2001:db8:ffff:ffff:ffff:ffff:ffff:ffff",
+        "3fff::1",
+        "3fff:fff:ffff::1",
+    ] {
+        assert!(client_addr(ip).is_documentation(), "{ip}");
+    }
+
+    for ip in [
+        "192.0.1.255",
+        "192.0.3.0",
+        "198.51.99.255",
+        "198.51.101.0",
+        "203.0.112.255",
+        "203.0.114.0",
+        "93.184.216.34",
+        "10.0.0.1",
+        "2001:db7:ffff::1",
+        "2001:db9::",
+        "3fff:1000::",
+        "2606:4700::1111",
+    ] {
+        assert!(!client_addr(ip).is_documentation(), "{ip}");
+    }
+}
+
+#[test]
+fn eq_ignoring_scope() {
+    let ip = |ip: &str| ip.parse::<IpAddr>().unwrap();
+
+    assert!(client_addr("fe80::1").eq_ignoring_scope(&ip("fe80::1")));
+    assert!(client_addr("fe80::1").eq_ignoring_scope(&ip("fe80:2::1")));
+    assert!(client_addr("fe80:1::1").eq_ignoring_scope(&ip("fe80:2::1")));
+    assert!(client_addr("ff02:3::1").eq_ignoring_scope(&ip("ff02::1")));
+    assert!(client_addr("ff01:3::1").eq_ignoring_scope(&ip("ff01::1")));
+    assert!(client_addr("93.184.216.34").eq_ignoring_scope(&ip("::ffff:93.184.216.34")));
+
+    assert!(!client_addr("fe80::1").eq_ignoring_scope(&ip("fe80::2")));
+    assert!(!client_addr("2001:db8::1").eq_ignoring_scope(&ip("2001:db9::1")));
+    assert!(!client_addr("ff05:3::1").eq_ignoring_scope(&ip("ff05::1")));
+    assert!(!client_addr("fe80::1").eq_ignoring_scope(&ip("10.0.0.1")));
+}
@@ -0,0 +1,83 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+};
+use rocket_client_addr::{NativeIpv6ClientAddr, NativeIpv6ClientAddrError, Resolver};
+
+#[get("/")]
+fn index(client_addr: NativeIpv6ClientAddr) -> String {
+    client_addr.0.to_string()
+}
+
+#[get("/error")]
+fn error(client_addr: Result<NativeIpv6ClientAddr, NativeIpv6ClientAddrError>) -> String {
+    match client_addr {
+        Ok(client_addr) => client_addr.0.to_string(),
+        Err(error) => error.to_string(),
+    }
+}
+
+fn get(uri: &'static str, remote: &str, forwarded_for: Option<&'static str>) -> (Status, String) {
+    let client = Client::untracked(rocket::build().mount("/", routes![index, error])).unwrap();
+
+    let mut request = client.get(uri).remote(remote.parse().unwrap());
+
+    if let Some(forwarded_for) = forwarded_for {
+        request = request.header(Header::new("X-Forwarded-For", forwarded_for));
+    }
+
+    let response = request.dispatch();
+
+    (response.status(), response.into_string().unwrap_or_default())
+}
+
+#[test]
+fn native_ipv6() {
+    assert_eq!(
+        (Status::Ok, "2606:4700::1111".to_string()),
+        get("/", "[2606:4700::1111]:8000", None)
+    );
+    assert_eq!(
+        (Status::Ok, "2606:4700::1111".to_string()),
+        get("/", "10.0.0.1:8000", Some("2606:4700::1111"))
+    );
+    assert_eq!(
+        (Status::Ok, "64:ff9b::5db8:d822".to_string()),
+        get("/", "[64:ff9b::5db8:d822]:8000", None)
+    );
+}
+
+#[test]
+fn mapped_ipv6() {
+    assert_eq!(Status::Forbidden, get("/", "[::ffff:93.184.216.34]:8000", None).0);
+    assert_eq!(Status::Forbidden, get("/", "10.0.0.1:8000", Some("::ffff:93.184.216.34")).0);
+    assert_eq!(
+        (Status::Ok, "the client is not a native IPv6 client: ::ffff:93.184.216.34".to_string()),
+        get("/error", "[::ffff:93.184.216.34]:8000", None)
+    );
+}
+
+#[test]
+fn ipv4() {
+    assert_eq!(Status::Forbidden, get("/", "93.184.216.34:8000", None).0);
+    assert_eq!(Status::Forbidden, get("/", "10.0.0.1:8000", Some("93.184.216.34")).0);
+    assert_eq!(
+        (Status::Ok, "the client is not a native IPv6 client: 93.184.216.34".to_string()),
+        get("/error", "93.184.216.34:8000", None)
+    );
+}
+
+#[test]
+fn failure_status() {
+    let resolver = Resolver::builder().failure_status(Status::UnavailableForLegalReasons).build();
+
+    let client =
+        Client::untracked(rocket::build().manage(resolver).mount("/", routes![index])).unwrap();
+
+    let response = client.get("/").remote("93.184.216.34:8000".parse().unwrap()).dispatch();
+
+    assert_eq!(Status::UnavailableForLegalReasons, response.status());
+}
@@ -0,0 +1,180 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+    Request,
+};
+use rocket_client_addr::{
+    CachedClientAddr, ClientAddr, ClientAddrFairing, ClientAddrInfo, NoHeaderTrust, Resolver,
+};
+
+#[get("/")]
+fn index(client_addr: &ClientAddr) -> String {
+    client_addr.to_string()
+}
+
+#[post("/webhook")]
+fn webhook(_no_header_trust: NoHeaderTrust, client_addr: &ClientAddr) -> String {
+    client_addr.to_string()
+}
+
+#[post("/webhook/owned")]
+fn webhook_owned(_no_header_trust: NoHeaderTrust, client_addr: ClientAddr) -> String {
+    client_addr.to_string()
+}
+
+#[post("/webhook/misordered")]
+fn webhook_misordered(client_addr: &ClientAddr, _no_header_trust: NoHeaderTrust) -> String {
+    client_addr.to_string()
+}
+
+#[get("/health")]
+fn health(_no_header_trust: NoHeaderTrust, client_addr: &ClientAddr) -> String {
+    client_addr.to_string()
+}
+
+#[post("/webhook/info")]
+fn webhook_info(_no_header_trust: NoHeaderTrust, info: ClientAddrInfo) -> String {
+    format!("{:?}", info.client_addr)
+}
+
+/// Read the address by every way other than the `ClientAddr` request guards.
+struct Others(String);
+
+#[rocket::async_trait]
+impl<'r> rocket::request::FromRequest<'r> for Others {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> rocket::request::Outcome<Self, Self::Error> {
+        let others = [
+            CachedClientAddr::get(request).copied(),
+            CachedClientAddr::get_async(request).await.copied(),
+            CachedClientAddr::resolve(request).client_addr(),
+            CachedClientAddr::resolve_async(request).await.client_addr(),
+            ClientAddr::resolve(request).await,
+        ];
+
+        rocket::outcome::Outcome::Success(Others(format!("{others:?}")))
+    }
+}
+
+#[post("/webhook/others")]
+fn webhook_others(_no_header_trust: NoHeaderTrust, others: Others) -> String {
+    others.0
+}
+
+fn client() -> Client {
+    let rocket = rocket::build().mount("/", routes![
+        index,
+        webhook,
+        webhook_owned,
+        webhook_misordered,
+        webhook_info,
+        webhook_others
+    ]);
+
+    Client::untracked(rocket).unwrap()
+}
+
+#[test]
+fn override_disables_header_trust() {
+    let client = client();
+
+    let response = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "93.184.216.34"))
+        .dispatch();
+
+    assert_eq!("93.184.216.34", response.into_string().unwrap());
+
+    for uri in ["/webhook", "/webhook/owned"] {
+        let response = client
+            .post(uri)
+            .remote("10.0.0.1:8000".parse().unwrap())
+            .header(Header::new("X-Forwarded-For", "93.184.216.34"))
+            .header(Header::new("X-Real-IP", "93.184.216.34"))
+            .dispatch();
+
+        assert_eq!(Status::Ok, response.status());
+        assert_eq!("10.0.0.1", response.into_string().unwrap());
+    }
+}
+
+#[test]
+fn misordered() {
+    let client = client();
+
+    let response = client
+        .post("/webhook/misordered")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "93.184.216.34"))
+        .dispatch();
+
+    assert_eq!(Status::InternalServerError, response.status());
+}
+
+#[test]
+fn no_remote() {
+    let client = client();
+
+    let response =
+        client.post("/webhook").header(Header::new("X-Forwarded-For", "93.184.216.34")).dispatch();
+
+    assert_eq!(Status::BadRequest, response.status());
+}
+
+#[test]
+fn health_check() {
+    let resolver = Resolver::builder().strict_parsing(true).build();
+    let client =
+        Client::untracked(rocket::build().manage(resolver).mount("/", routes![index, health]))
+            .unwrap();
+
+    let response = client.get("/health").remote("10.0.0.1:8000".parse().unwrap()).dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!("10.0.0.1", response.into_string().unwrap());
+
+    let request = |uri: &'static str| {
+        client
+            .get(uri)
+            .remote("10.0.0.1:8000".parse().unwrap())
+            .header(Header::new("X-Forwarded-For", "93.184.216.34, garbage"))
+            .dispatch()
+    };
+
+    assert_eq!(Status::BadRequest, request("/").status());
+    assert_eq!("10.0.0.1", request("/health").into_string().unwrap());
+}
+
+#[test]
+fn others() {
+    let expected = ["Some(10.0.0.1)"; 5].join(", ");
+
+    let fairing_client =
+        Client::untracked(rocket::build().attach(ClientAddrFairing::new()).mount("/", routes![
+            webhook,
+            webhook_info,
+            webhook_others
+        ]))
+        .unwrap();
+
+    for client in [client(), fairing_client] {
+        let request = |uri: &'static str| {
+            client
+                .post(uri)
+                .remote("10.0.0.1:8000".parse().unwrap())
+                .header(Header::new("X-Forwarded-For", "93.184.216.34"))
+                .dispatch()
+                .into_string()
+                .unwrap()
+        };
+
+        assert_eq!("10.0.0.1", request("/webhook"));
+        assert_eq!("Some(10.0.0.1)", request("/webhook/info"));
+        assert_eq!(format!("[{expected}]"), request("/webhook/others"));
+    }
+}
@@ -0,0 +1,135 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+};
+use rocket_client_addr::{
+    CdnHeader, ClientAddr, ClientAddrFairing, ClientRealAddr, CustomSource, RealIpMode, Resolver,
+};
+
+#[get("/addr")]
+fn addr(client_addr: &ClientAddr) -> String {
+    client_addr.to_string()
+}
+
+#[get("/real")]
+fn real(client_addr: &ClientRealAddr) -> String {
+    client_addr.ip.to_string()
+}
+
+/// The header sources, from the highest precedence to the lowest, with the address each one holds.
+const HEADERS: [(&str, &str, &str); 5] = [
+    ("CF-Connecting-IP", "1.1.1.1", "1.1.1.1"),
+    ("X-Client-IP", "1.0.0.1", "1.0.0.1"),
+    ("X-Forwarded-For", "93.184.216.34, 10.0.0.2", "93.184.216.34"),
+    ("Forwarded", "for=34.120.0.1;proto=https", "34.120.0.1"),
+    ("X-Real-IP", "34.120.0.2", "34.120.0.2"),
+];
+
+/// The number of leading sources which are configured by the `Resolver`, so they are not used by `ClientRealAddr`.
+const CONFIGURED: usize = 2;
+
+const UNTRUSTED_PEER: &str = "34.120.0.9";
+
+fn client() -> Client {
+    let resolver = Resolver::builder()
+        .trust_cdn_header(CdnHeader::Cloudflare)
+        .custom_source(CustomSource::Header("X-Client-IP".into()))
+        .build();
+
+    Client::untracked(rocket::build().manage(resolver).mount("/", routes![addr, real])).unwrap()
+}
+
+fn get(client: &Client, uri: &str, remote: Option<&str>, headers: &[usize]) -> Option<String> {
+    let mut request = client.get(uri.to_string());
+
+    if let Some(remote) = remote {
+        request = request.remote(format!("{remote}:8000").parse().unwrap());
+    }
+
+    for &i in headers {
+        request = request.header(Header::new(HEADERS[i].0, HEADERS[i].1));
+    }
+
+    let response = request.dispatch();
+
+    if response.status() == Status::Ok {
+        response.into_string()
+    } else {
+        None
+    }
+}
+
+/// The expected address: a trusted CDN header first, then an untrusted TCP peer, then the other headers in order, then the TCP peer.
+fn expected(remote: Option<&str>, headers: &[usize], real: bool) -> Option<String> {
+    let is_untrusted_peer = !real && remote == Some(UNTRUSTED_PEER);
+
+    headers
+        .iter()
+        .copied()
+        .find(|&i| match (real, is_untrusted_peer) {
+            (true, _) => i >= CONFIGURED,
+            (false, true) => i == 0,
+            (false, false) => true,
+        })
+        .map(|i| HEADERS[i].2.to_string())
+        .or_else(|| remote.map(|remote| remote.to_string()))
+}
+
+#[test]
+fn every_combination() {
+    let client = client();
+
+    for remote in [None, Some("10.0.0.1"), Some(UNTRUSTED_PEER)] {
+        for mask in 0..(1 << HEADERS.len()) {
+            let headers: Vec<usize> = (0..HEADERS.len()).filter(|i| mask & (1 << i) != 0).collect();
+
+            for (uri, real) in [("/addr", false), ("/real", true)] {
+                assert_eq!(
+                    expected(remote, &headers, real),
+                    get(&client, uri, remote, &headers),
+                    "{uri} {remote:?} {headers:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn auto_detect_keeps_precedence() {
+    let client_with = |resolver: Resolver| {
+        let rocket = rocket::build()
+            .manage(resolver)
+            .attach(ClientAddrFairing::new().auto_detect(true))
+            .mount("/", routes![addr]);
+
+        Client::untracked(rocket).unwrap()
+    };
+
+    let get = |client: &Client, headers: &[(&'static str, &'static str)]| {
+        let mut request = client.get("/addr").remote("10.0.0.1:8000".parse().unwrap());
+
+        for (name, value) in headers {
+            request = request.header(Header::new(*name, *value));
+        }
+
+        request.dispatch().into_string().unwrap()
+    };
+
+    // a sentinel takes precedence over the detected CDN header
+    let client = client_with(Resolver::builder().remote_sentinel("X-Direct", "1").build());
+
+    assert_eq!("1.1.1.1", get(&client, &[("CF-Connecting-IP", "1.1.1.1")]));
+    assert_eq!("10.0.0.1", get(&client, &[("CF-Connecting-IP", "1.1.1.1"), ("X-Direct", "1")]));
+
+    // so does `RealIpMode::Defer`
+    let client = client_with(Resolver::builder().real_ip_mode(RealIpMode::Defer).build());
+
+    assert_eq!("10.0.0.1", get(&client, &[("CF-Connecting-IP", "1.1.1.1")]));
+    assert_eq!(
+        "34.120.0.2",
+        get(&client, &[("CF-Connecting-IP", "1.1.1.1"), ("X-Real-IP", "34.120.0.2")])
+    );
+}
@@ -0,0 +1,147 @@
+use rocket::{http::Header, local::blocking::Client};
+use rocket_client_addr::{ClientAddr, Preset, Resolver};
+
+fn resolve(
+    preset: Preset,
+    remote: &str,
+    headers: &[(&'static str, &'static str)],
+) -> Option<String> {
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    let mut request = client.get("/").remote(format!("{remote}:8000").parse().unwrap());
+
+    for (name, value) in headers {
+        request = request.header(Header::new(*name, *value));
+    }
+
+    Resolver::from(preset)
+        .resolve(request.inner())
+        .map(|client_addr: ClientAddr| client_addr.to_string())
+}
+
+#[test]
+fn aws_alb() {
+    // X-Forwarded-For: <supplied>, <client>, from the private address of the load balancer
+    let alb = "10.0.1.23";
+
+    assert_eq!(
+        Some("93.184.216.34".into()),
+        resolve(Preset::AwsAlb, alb, &[("X-Forwarded-For", "93.184.216.34")])
+    );
+    assert_eq!(
+        Some("93.184.216.34".into()),
+        resolve(Preset::AwsAlb, alb, &[("X-Forwarded-For", "1.1.1.1, 93.184.216.34")])
+    );
+
+    // a client in the VPC behind an internal load balancer is not skipped
+    assert_eq!(
+        Some("10.0.2.7".into()),
+        resolve(Preset::AwsAlb, alb, &[("X-Forwarded-For", "1.1.1.1, 10.0.2.7")])
+    );
+
+    // a forged X-Real-IP is ignored
+    assert_eq!(
+        Some("93.184.216.34".into()),
+        resolve(Preset::AwsAlb, alb, &[
+            ("X-Forwarded-For", "93.184.216.34"),
+            ("X-Real-IP", "1.1.1.1")
+        ])
+    );
+    assert_eq!(Some(alb.into()), resolve(Preset::AwsAlb, alb, &[("X-Real-IP", "1.1.1.1")]));
+
+    // not through the load balancer
+    assert_eq!(
+        Some("34.120.0.9".into()),
+        resolve(Preset::AwsAlb, "34.120.0.9", &[("X-Forwarded-For", "1.1.1.1")])
+    );
+}
+
+#[test]
+fn gcp_http_lb() {
+    // X-Forwarded-For: <supplied>, <client>, <load balancer>, from a Google Front End
+    let gfe = "35.191.10.20";
+
+    assert_eq!(
+        Some("93.184.216.34".into()),
+        resolve(Preset::GcpHttpLb, gfe, &[("X-Forwarded-For", "93.184.216.34, 34.120.0.1")])
+    );
+    assert_eq!(
+        Some("93.184.216.34".into()),
+        resolve(Preset::GcpHttpLb, "130.211.0.5", &[(
+            "X-Forwarded-For",
+            "1.1.1.1, 10.0.0.2, 93.184.216.34, 34.120.0.1"
+        )])
+    );
+    assert_eq!(
+        Some("2606:4700::1111".into()),
+        resolve(Preset::GcpHttpLb, gfe, &[("X-Forwarded-For", "2606:4700::1111, 34.120.0.1")])
+    );
+
+    // too short for the layout of the load balancer
+    assert_eq!(
+        Some(gfe.into()),
+        resolve(Preset::GcpHttpLb, gfe, &[("X-Forwarded-For", "93.184.216.34")])
+    );
+
+    // not through the load balancer
+    assert_eq!(
+        Some("34.120.0.9".into()),
+        resolve(Preset::GcpHttpLb, "34.120.0.9", &[(
+            "X-Forwarded-For",
+            "93.184.216.34, 34.120.0.1"
+        )])
+    );
+}
+
+#[test]
+fn single_proxy() {
+    // nginx on the same host, which overwrites X-Forwarded-For with the address of its peer
+    let nginx = "127.0.0.1";
+
+    assert_eq!(
+        Some("93.184.216.34".into()),
+        resolve(Preset::SingleProxy, nginx, &[("X-Forwarded-For", "93.184.216.34")])
+    );
+    assert_eq!(
+        Some("10.0.2.7".into()),
+        resolve(Preset::SingleProxy, "10.0.0.2", &[("X-Forwarded-For", "10.0.2.7")])
+    );
+    assert_eq!(Some(nginx.into()), resolve(Preset::SingleProxy, nginx, &[]));
+
+    // not through the proxy
+    assert_eq!(
+        Some("34.120.0.9".into()),
+        resolve(Preset::SingleProxy, "34.120.0.9", &[("X-Forwarded-For", "1.1.1.1")])
+    );
+}
+
+#[test]
+fn single_proxy_appending_caveat() {
+    // nginx with `$proxy_add_x_forwarded_for` appends the client to the address it supplied
+    let headers = [("X-Forwarded-For", "1.1.1.1, 93.184.216.34")];
+
+    assert_eq!(Some("1.1.1.1".into()), resolve(Preset::SingleProxy, "127.0.0.1", &headers));
+
+    // which the default strategy is not fooled by
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    let mut request = client.get("/").remote("127.0.0.1:8000".parse().unwrap());
+
+    for (name, value) in headers {
+        request = request.header(Header::new(name, value));
+    }
+
+    assert_eq!(
+        Some("93.184.216.34".into()),
+        Resolver::default().resolve(request.inner()).map(|client_addr| client_addr.to_string())
+    );
+}
+
+#[test]
+fn builder() {
+    let resolver = Preset::GcpHttpLb.builder().canonicalize_mapped(true).build();
+
+    assert!(resolver.config().canonicalize_mapped);
+    assert_eq!(Preset::GcpHttpLb.config().strategy, resolver.config().strategy);
+    assert!(resolver.config().trusted_proxies.contains(&"35.191.0.1".parse().unwrap()));
+}
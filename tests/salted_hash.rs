@@ -0,0 +1,42 @@
+#![cfg(feature = "salted-hash")]
+
+use rocket_client_addr::ClientAddr;
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[test]
+fn deterministic() {
+    let client_addr = ClientAddr { ip: "93.184.216.34".parse().unwrap() };
+
+    assert_eq!(client_addr.salted_hash(b"secret"), client_addr.salted_hash(b"secret"));
+    assert_ne!(client_addr.salted_hash(b"secret"), client_addr.salted_hash(b"another secret"));
+
+    assert_eq!(
+        "1729f735e2a5f445b02fb1fa56e1c4518c6feb8a3441c94724f43c05d203b1a5",
+        hex(&client_addr.salted_hash(b"secret"))
+    );
+    assert_eq!(
+        "7c78b231598551fedcec2217420cf4c666e380279cdaaabb16c4e44a875f9bc2",
+        hex(&client_addr.salted_hash(&[b'k'; 100]))
+    );
+}
+
+#[test]
+fn ipv6() {
+    let client_addr = ClientAddr { ip: "2001:4860::8888".parse().unwrap() };
+
+    assert_eq!(
+        "7d7aecdfc9f46be8df782e1c4748cd79e83ec84f75b09f68dbeb6b320db85ff2",
+        hex(&client_addr.salted_hash(b"another secret"))
+    );
+}
+
+#[test]
+fn ipv4_mapped() {
+    let ipv4 = ClientAddr { ip: "93.184.216.34".parse().unwrap() };
+    let mapped = ClientAddr { ip: "::ffff:93.184.216.34".parse().unwrap() };
+
+    assert_eq!(ipv4.salted_hash(b"secret"), mapped.salted_hash(b"secret"));
+}
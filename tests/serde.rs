@@ -0,0 +1,37 @@
+#![cfg(feature = "serde")]
+
+use rocket::{
+    figment::{providers::Serialized, Figment},
+    serde::{Deserialize, Serialize},
+};
+use rocket_client_addr::ClientAddr;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct Context {
+    client_ip: ClientAddr,
+}
+
+#[test]
+fn serialize() {
+    let context = Context { client_ip: ClientAddr { ip: "2606:4700::1111".parse().unwrap() } };
+
+    let figment = Figment::from(Serialized::defaults(&context));
+
+    assert_eq!("2606:4700::1111", figment.extract_inner::<String>("client_ip").unwrap());
+    assert_eq!(context, figment.extract::<Context>().unwrap());
+}
+
+#[test]
+fn deserialize() {
+    let figment = Figment::new().merge(("client_ip", "93.184.216.34"));
+
+    assert_eq!(
+        Context { client_ip: ClientAddr { ip: "93.184.216.34".parse().unwrap() } },
+        figment.extract::<Context>().unwrap()
+    );
+
+    let figment = Figment::new().merge(("client_ip", "93.184.216"));
+
+    assert!(figment.extract::<Context>().is_err());
+}
@@ -0,0 +1,129 @@
+#![cfg(feature = "session-binding")]
+
+#[macro_use]
+extern crate rocket;
+
+use rocket::{
+    http::{Cookie, CookieJar, Header, Status},
+    local::blocking::Client,
+};
+use rocket_client_addr::{ClientAddr, SessionBinding, SessionBoundAddr};
+
+#[get("/")]
+fn index(session_bound_addr: SessionBoundAddr) -> String {
+    session_bound_addr.0.to_string()
+}
+
+#[post("/login")]
+fn login(client_addr: &ClientAddr, cookies: &CookieJar<'_>) {
+    cookies.add_private(SessionBinding::default().cookie(client_addr));
+}
+
+fn get(client: &Client, forwarded_for: &str, cookie: Option<&str>) -> (Status, Option<String>) {
+    let mut request = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", forwarded_for.to_string()));
+
+    if let Some(cookie) = cookie {
+        request = request.private_cookie(Cookie::new("client_addr_binding", cookie.to_string()));
+    }
+
+    let response = request.dispatch();
+
+    (response.status(), response.into_string())
+}
+
+#[test]
+fn normalize() {
+    let binding = SessionBinding::default();
+    let client_addr = |ip: &str| ClientAddr {
+        ip: ip.parse().unwrap()
+    };
+
+    assert_eq!(
+        "93.184.216.34/32",
+        binding.normalize(&client_addr("::ffff:93.184.216.34")).to_string()
+    );
+    assert_eq!(
+        "2606:4700:1:2::/64",
+        binding.normalize(&client_addr("2606:4700:1:2:a1b2:c3d4:e5f6:7788")).to_string()
+    );
+
+    let cookie = binding.cookie(&client_addr("2606:4700:1:2:a1b2:c3d4:e5f6:7788"));
+
+    assert_eq!("client_addr_binding", cookie.name());
+    assert_eq!("2606:4700:1:2::/64", cookie.value());
+    assert_eq!(Some(true), cookie.http_only());
+}
+
+#[test]
+fn session_bound_addr() {
+    let client = Client::untracked(rocket::build().mount("/", routes![index])).unwrap();
+
+    let binding = Some("2606:4700:1:2::/64");
+
+    // the privacy extensions rotate the low 64 bits
+    for forwarded_for in ["2606:4700:1:2:a1b2:c3d4:e5f6:7788", "2606:4700:1:2::1"] {
+        let expected = (Status::Ok, Some(forwarded_for.to_string()));
+
+        assert_eq!(expected, get(&client, forwarded_for, binding));
+    }
+
+    assert_eq!(Status::Forbidden, get(&client, "2606:4700:1:3::1", binding).0);
+    assert_eq!(Status::Forbidden, get(&client, "93.184.216.34", binding).0);
+    assert_eq!(Status::Forbidden, get(&client, "2606:4700:1:2::1", Some("garbage")).0);
+    assert_eq!(Status::Unauthorized, get(&client, "2606:4700:1:2::1", None).0);
+}
+
+#[test]
+fn custom_binding() {
+    let binding = SessionBinding {
+        ipv4_prefix: 24,
+        ..SessionBinding::default()
+    };
+    let client =
+        Client::untracked(rocket::build().manage(binding).mount("/", routes![index])).unwrap();
+
+    let binding = Some("93.184.216.0/24");
+
+    assert_eq!(Status::Ok, get(&client, "93.184.216.34", binding).0);
+    assert_eq!(Status::Ok, get(&client, "::ffff:93.184.216.200", binding).0);
+    assert_eq!(Status::Forbidden, get(&client, "93.184.217.34", binding).0);
+}
+
+#[test]
+fn tampered_cookie() {
+    let client = Client::untracked(rocket::build().mount("/", routes![index, login])).unwrap();
+
+    let request = |value: &str| {
+        client
+            .get("/")
+            .remote("10.0.0.1:8000".parse().unwrap())
+            .header(Header::new("X-Forwarded-For", "2606:4700:1:2::1"))
+            .cookie(Cookie::new("client_addr_binding", value.to_string()))
+            .dispatch()
+            .status()
+    };
+
+    let response = client
+        .post("/login")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "2606:4700:1:2::1"))
+        .dispatch();
+
+    let encrypted = response.cookies().get("client_addr_binding").unwrap().value().to_string();
+
+    assert_eq!(Status::Ok, request(&encrypted));
+
+    // a plain cookie rewritten to the network of the client
+    assert_eq!(Status::Forbidden, request("2606:4700:1:2::/64"));
+
+    // a private cookie whose ciphertext is modified
+    let mut tampered = encrypted.into_bytes();
+    let last = tampered.len() - 2;
+
+    tampered[last] = if tampered[last] == b'A' { b'B' } else { b'A' };
+
+    assert_eq!(Status::Forbidden, request(&String::from_utf8(tampered).unwrap()));
+}
@@ -0,0 +1,81 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{
+    http::{Cookie, Header, Status},
+    local::blocking::Client,
+};
+use rocket_client_addr::{ClientAddr, CustomSource, Resolver, SourceKind};
+
+#[get("/")]
+fn index(client_addr: &ClientAddr) -> String {
+    client_addr.to_string()
+}
+
+fn get(sources: Option<Vec<SourceKind>>, remote: &str) -> (Status, Option<String>) {
+    let mut resolver = Resolver::builder();
+
+    if let Some(sources) = sources {
+        resolver = resolver.sources(sources);
+    }
+
+    let rocket = rocket::build().manage(resolver.build()).mount("/", routes![index]);
+    let client = Client::untracked(rocket).unwrap();
+
+    let response = client
+        .get("/")
+        .remote(format!("{remote}:8000").parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "93.184.216.34"))
+        .header(Header::new("X-Real-IP", "1.1.1.1"))
+        .cookie(Cookie::new("client_ip", "34.120.0.1"))
+        .dispatch();
+
+    (response.status(), response.into_string())
+}
+
+fn ok(ip: &str) -> (Status, Option<String>) {
+    (Status::Ok, Some(ip.to_string()))
+}
+
+#[test]
+fn default_order() {
+    assert_eq!(ok("93.184.216.34"), get(None, "10.0.0.1"));
+    assert_eq!(ok("93.184.216.34"), get(Some(SourceKind::DEFAULT_ORDER.to_vec()), "10.0.0.1"));
+    assert_eq!(ok("34.120.0.9"), get(None, "34.120.0.9"));
+}
+
+#[test]
+fn reordered() {
+    let real_ip_first = vec![
+        SourceKind::UntrustedRemote,
+        SourceKind::RealIp,
+        SourceKind::ForwardedChain,
+        SourceKind::Remote,
+    ];
+
+    assert_eq!(ok("1.1.1.1"), get(Some(real_ip_first), "10.0.0.1"));
+
+    let cookie_first = vec![
+        SourceKind::UntrustedRemote,
+        SourceKind::Custom(CustomSource::Cookie("client_ip".into())),
+        SourceKind::ForwardedChain,
+    ];
+
+    assert_eq!(ok("34.120.0.1"), get(Some(cookie_first.clone()), "10.0.0.1"));
+    assert_eq!(ok("34.120.0.9"), get(Some(cookie_first), "34.120.0.9"));
+
+    assert_eq!(ok("10.0.0.1"), get(Some(vec![SourceKind::Remote]), "10.0.0.1"));
+}
+
+#[test]
+fn headers_before_untrusted_remote() {
+    // the header is used even if the TCP peer is not trusted
+    let sources = vec![SourceKind::RealIp, SourceKind::UntrustedRemote, SourceKind::Remote];
+
+    assert_eq!(ok("1.1.1.1"), get(Some(sources), "34.120.0.9"));
+}
+
+#[test]
+fn no_sources() {
+    assert_eq!(Status::BadRequest, get(Some(Vec::new()), "10.0.0.1").0);
+}
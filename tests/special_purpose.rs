@@ -0,0 +1,100 @@
+use rocket_client_addr::ClientAddr;
+
+/// The first and the last address of every block of the IANA special-purpose address registries.
+const REGISTRY: [(&str, &str); 47] = [
+    // IPv4
+    ("0.0.0.0", "0.255.255.255"),
+    ("0.0.0.0", "0.0.0.0"),
+    ("10.0.0.0", "10.255.255.255"),
+    ("100.64.0.0", "100.127.255.255"),
+    ("127.0.0.0", "127.255.255.255"),
+    ("169.254.0.0", "169.254.255.255"),
+    ("172.16.0.0", "172.31.255.255"),
+    ("192.0.0.0", "192.0.0.255"),
+    ("192.0.0.0", "192.0.0.7"),
+    ("192.0.0.8", "192.0.0.8"),
+    ("192.0.0.9", "192.0.0.9"),
+    ("192.0.0.10", "192.0.0.10"),
+    ("192.0.0.170", "192.0.0.171"),
+    ("192.0.2.0", "192.0.2.255"),
+    ("192.31.196.0", "192.31.196.255"),
+    ("192.52.193.0", "192.52.193.255"),
+    ("192.88.99.0", "192.88.99.255"),
+    ("192.168.0.0", "192.168.255.255"),
+    ("192.175.48.0", "192.175.48.255"),
+    ("198.18.0.0", "198.19.255.255"),
+    ("198.51.100.0", "198.51.100.255"),
+    ("203.0.113.0", "203.0.113.255"),
+    ("240.0.0.0", "255.255.255.254"),
+    ("255.255.255.255", "255.255.255.255"),
+    // IPv6
+    ("::1", "::1"),
+    ("::", "::"),
+    ("::ffff:0:0", "::ffff:ffff:ffff"),
+    ("64:ff9b::", "64:ff9b::ffff:ffff"),
+    ("64:ff9b:1::", "64:ff9b:1:ffff:ffff:ffff:ffff:ffff"),
+    ("100::", "100::ffff:ffff:ffff:ffff"),
+    ("2001::", "2001:1ff:ffff:ffff:ffff:ffff:ffff:ffff"),
+    ("2001::", "2001:0:ffff:ffff:ffff:ffff:ffff:ffff"),
+    ("2001:1::1", "2001:1::1"),
+    ("2001:1::2", "2001:1::2"),
+    ("2001:2::", "2001:2:0:ffff:ffff:ffff:ffff:ffff"),
+    ("2001:3::", "2001:3:ffff:ffff:ffff:ffff:ffff:ffff"),
+    ("2001:4:112::", "2001:4:112:ffff:ffff:ffff:ffff:ffff"),
+    ("2001:10::", "2001:1f:ffff:ffff:ffff:ffff:ffff:ffff"),
+    ("2001:20::", "2001:2f:ffff:ffff:ffff:ffff:ffff:ffff"),
+    ("2001:30::", "2001:3f:ffff:ffff:ffff:ffff:ffff:ffff"),
+    ("2001:db8::", "2001:db8:ffff:ffff:ffff:ffff:ffff:ffff"),
+    ("2002::", "2002:ffff:ffff:ffff:ffff:ffff:ffff:ffff"),
+    ("2620:4f:8000::", "2620:4f:8000:ffff:ffff:ffff:ffff:ffff"),
+    ("3fff::", "3fff:fff:ffff:ffff:ffff:ffff:ffff:ffff"),
+    ("5f00::", "5f00:ffff:ffff:ffff:ffff:ffff:ffff:ffff"),
+    ("fc00::", "fdff:ffff:ffff:ffff:ffff:ffff:ffff:ffff"),
+    ("fe80::", "febf:ffff:ffff:ffff:ffff:ffff:ffff:ffff"),
+];
+
+fn is_special_purpose(ip: &str) -> bool {
+    ClientAddr { ip: ip.parse().unwrap() }.is_special_purpose()
+}
+
+#[test]
+fn registry() {
+    for (first, last) in REGISTRY {
+        assert!(is_special_purpose(first), "{first}");
+        assert!(is_special_purpose(last), "{last}");
+    }
+}
+
+#[test]
+fn outside_registry() {
+    for ip in [
+        "1.0.0.0",
+        "9.255.255.255",
+        "11.0.0.0",
+        "93.184.216.34",
+        "100.63.255.255",
+        "100.128.0.0",
+        "172.15.255.255",
+        "172.32.0.0",
+        "192.0.1.0",
+        "192.0.3.0",
+        "192.167.255.255",
+        "192.169.0.0",
+        "198.17.255.255",
+        "198.20.0.0",
+        "223.255.255.255",
+        "224.0.0.1",
+        "::2",
+        "100:0:0:1::",
+        "2001:200::",
+        "2001:db9::",
+        "2003::",
+        "2606:4700::1111",
+        "3fff:1000::",
+        "fe00::",
+        "fec0::1",
+        "ff02::1",
+    ] {
+        assert!(!is_special_purpose(ip), "{ip}");
+    }
+}
@@ -0,0 +1,61 @@
+#![cfg(feature = "tls")]
+
+#[macro_use]
+extern crate rocket;
+
+use rocket::{config::TlsConfig, http::Header, local::blocking::Client};
+use rocket_client_addr::{ClientAddr, Resolver};
+
+#[get("/")]
+fn index(client_addr: &ClientAddr) -> String {
+    client_addr.to_string()
+}
+
+fn client(tls: bool, prefer_tls_peer: bool) -> Client {
+    let tls = tls.then(|| {
+        TlsConfig::from_paths(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/tls/cert.pem"),
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/tls/key.pem"),
+        )
+    });
+
+    let config = rocket::Config {
+        tls,
+        ..rocket::Config::debug_default()
+    };
+
+    let resolver = Resolver::builder().prefer_tls_peer(prefer_tls_peer).build();
+
+    Client::untracked(rocket::custom(config).manage(resolver).mount("/", routes![index])).unwrap()
+}
+
+fn request(client: &Client) -> String {
+    client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "93.184.216.34"))
+        .header(Header::new("X-Real-IP", "93.184.216.34"))
+        .dispatch()
+        .into_string()
+        .unwrap()
+}
+
+#[test]
+fn prefer_tls_peer() {
+    let client = client(true, true);
+
+    assert!(client.rocket().config().tls_enabled());
+
+    // Rocket terminates TLS, so every header is ignored
+    assert_eq!("10.0.0.1", request(&client));
+}
+
+#[test]
+fn prefer_tls_peer_without_tls() {
+    assert_eq!("93.184.216.34", request(&client(false, true)));
+}
+
+#[test]
+fn tls_without_prefer_tls_peer() {
+    assert_eq!("93.184.216.34", request(&client(true, false)));
+}
@@ -0,0 +1,157 @@
+#![cfg(feature = "tracing")]
+
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+
+use rocket::{http::Header, local::blocking::Client};
+use rocket_client_addr::{IpNetSet, Resolver};
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    Event, Level, Metadata, Subscriber,
+};
+
+/// The level and the fields of an event.
+type RecordedEvent = (Level, Vec<String>);
+
+/// A subscriber which records the fields of every event, and the name and the fields of every span, as `name=value` strings.
+#[derive(Default, Clone)]
+struct Recorder {
+    events: Arc<Mutex<Vec<RecordedEvent>>>,
+    spans:  Arc<Mutex<Vec<String>>>,
+}
+
+struct FieldVisitor(Vec<String>);
+
+impl Visit for FieldVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.push(format!("{}={value:?}", field.name()));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.push(format!("{}={value}", field.name()));
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        self.spans.lock().unwrap().push(format!("name={}", span.metadata().name()));
+
+        Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &Id, values: &Record<'_>) {
+        let mut visitor = FieldVisitor(Vec::new());
+
+        values.record(&mut visitor);
+
+        self.spans.lock().unwrap().extend(visitor.0);
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut visitor = FieldVisitor(Vec::new());
+
+        event.record(&mut visitor);
+
+        self.events.lock().unwrap().push((*event.metadata().level(), visitor.0));
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+fn resolve(resolver: &Resolver, remote: &str) -> Vec<RecordedEvent> {
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    let request = client
+        .get("/")
+        .remote(format!("{remote}:8000").parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "1.1.1.1"));
+
+    let recorder = Recorder::default();
+
+    tracing::subscriber::with_default(recorder.clone(), || {
+        assert!(resolver.resolve(request.inner()).is_some());
+    });
+
+    let events = recorder.events.lock().unwrap();
+
+    events.clone()
+}
+
+#[test]
+fn spoof_attempt() {
+    let resolver = Resolver::builder()
+        .trust_proxies(["34.120.0.0/24".parse().unwrap()].into_iter().collect::<IpNetSet>())
+        .build();
+
+    // an untrusted peer with a forwarding header
+    let events = resolve(&resolver, "93.184.216.34");
+
+    assert_eq!(1, events.len());
+
+    let (level, fields) = &events[0];
+
+    assert_eq!(Level::WARN, *level);
+    assert!(fields.contains(&"peer=93.184.216.34".to_string()), "{fields:?}");
+    assert!(fields.contains(&"header=X-Forwarded-For".to_string()), "{fields:?}");
+    assert!(fields.contains(&"value=1.1.1.1".to_string()), "{fields:?}");
+
+    // a trusted proxy
+    assert!(resolve(&resolver, "34.120.0.1").is_empty());
+}
+
+#[test]
+fn public_peer_hint() {
+    // no trusted proxies are configured, so the peer may be a proxy rather than a spoofer
+    let events = resolve(&Resolver::default(), "93.184.216.34");
+
+    assert_eq!(1, events.len());
+
+    let (level, fields) = &events[0];
+
+    assert_eq!(Level::WARN, *level);
+    assert!(fields.iter().any(|field| field.contains("always_use_headers")), "{fields:?}");
+    assert!(fields.contains(&"peer=93.184.216.34".to_string()), "{fields:?}");
+
+    // reported once
+    assert!(resolve(&Resolver::default(), "34.120.0.9").is_empty());
+
+    // nothing is ignored
+    let resolver = Resolver::builder().always_use_headers(true).build();
+
+    assert!(resolve(&resolver, "34.120.0.9").is_empty());
+}
+
+#[test]
+fn resolution_timing() {
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    let chain = vec!["10.0.0.2"; 1000].join(", ");
+
+    let request = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", format!("1.1.1.1, {chain}")));
+
+    let recorder = Recorder::default();
+
+    tracing::subscriber::with_default(recorder.clone(), || {
+        assert!(Resolver::default().resolve(request.inner()).is_some());
+    });
+
+    let spans = recorder.spans.lock().unwrap();
+
+    assert!(spans.contains(&"name=resolve_client_addr".to_string()), "{spans:?}");
+    assert!(spans.contains(&format!("header_bytes={}", chain.len() + 9)), "{spans:?}");
+    assert!(spans.iter().any(|field| field.starts_with("elapsed_us=")), "{spans:?}");
+}
@@ -0,0 +1,183 @@
+#[macro_use]
+extern crate rocket;
+
+use std::{
+    net::IpAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use rocket::{http::Header, local::blocking::Client};
+use rocket_client_addr::{
+    CdnHeader, ClientAddr, IpNetSet, RealIpMode, Resolver, SourceKind, Strategy, TrustChecker,
+    TrustedClientAddr,
+};
+
+#[get("/")]
+fn index(client_addr: TrustedClientAddr) -> String {
+    format!("{} {}", client_addr.ip, client_addr.trusted)
+}
+
+#[get("/both")]
+fn both(client_addr: &ClientAddr, trusted_client_addr: TrustedClientAddr) -> String {
+    format!("{} {}", client_addr, trusted_client_addr.trusted)
+}
+
+fn client_with(resolver: Resolver) -> Client {
+    Client::untracked(rocket::build().manage(resolver).mount("/", routes![index, both])).unwrap()
+}
+
+fn get(client: &Client, remote: Option<&str>, headers: &[(&'static str, &'static str)]) -> String {
+    let mut request = client.get("/");
+
+    if let Some(remote) = remote {
+        request = request.remote(format!("{remote}:8000").parse().unwrap());
+    }
+
+    for (name, value) in headers {
+        request = request.header(Header::new(*name, *value));
+    }
+
+    request.dispatch().into_string().unwrap()
+}
+
+#[test]
+fn trusted() {
+    let client = client_with(Resolver::default());
+
+    assert_eq!(
+        "93.184.216.34 true",
+        get(&client, Some("10.0.0.1"), &[("X-Forwarded-For", "93.184.216.34")])
+    );
+    assert_eq!("34.120.0.9 true", get(&client, Some("34.120.0.9"), &[]));
+    assert_eq!(
+        "93.184.216.34 true",
+        get(&client, Some("10.0.0.1"), &[("X-Real-IP", "93.184.216.34")])
+    );
+
+    let client = client_with(
+        Resolver::builder()
+            .trust_proxies("34.120.0.0/24".parse().into_iter().collect::<IpNetSet>())
+            .strategy(Strategy::Leftmost {
+                verify_trusted_suffix: true
+            })
+            .trust_cdn_header(CdnHeader::Cloudflare)
+            .build(),
+    );
+
+    assert_eq!(
+        "93.184.216.34 true",
+        get(&client, Some("34.120.0.1"), &[("X-Forwarded-For", "93.184.216.34, 10.0.0.2")])
+    );
+    assert_eq!(
+        "93.184.216.34 true",
+        get(&client, Some("34.120.0.1"), &[("CF-Connecting-IP", "93.184.216.34")])
+    );
+}
+
+#[test]
+fn forgeable() {
+    let forged = [("X-Forwarded-For", "93.184.216.34, 1.1.1.1"), ("X-Real-IP", "93.184.216.34")];
+
+    // no TCP peer
+    let client = client_with(Resolver::default());
+
+    assert_eq!("93.184.216.34 false", get(&client, None, &[("X-Real-IP", "93.184.216.34")]));
+    assert_eq!("1.1.1.1 false", get(&client, None, &forged));
+
+    // strategies which do not walk the trusted proxies
+    for strategy in [
+        Strategy::Leftmost {
+            verify_trusted_suffix: false
+        },
+        Strategy::Rightmost {
+            skip: 1
+        },
+        Strategy::FirstPublic,
+    ] {
+        let client = client_with(Resolver::builder().strategy(strategy).build());
+
+        assert_eq!("93.184.216.34 false", get(&client, Some("10.0.0.1"), &forged), "{strategy:?}");
+    }
+
+    // `RealIpMode::Defer`
+    let client = client_with(Resolver::builder().real_ip_mode(RealIpMode::Defer).build());
+
+    assert_eq!("93.184.216.34 false", get(&client, Some("10.0.0.1"), &forged));
+
+    // headers before the TCP peer
+    let client = client_with(
+        Resolver::builder()
+            .sources(vec![SourceKind::ForwardedChain, SourceKind::UntrustedRemote])
+            .build(),
+    );
+
+    assert_eq!("1.1.1.1 false", get(&client, Some("34.120.0.9"), &forged));
+    assert_eq!("1.1.1.1 true", get(&client, Some("10.0.0.1"), &forged));
+
+    // a public TCP peer which is trusted only by `always_use_headers`
+    let client = client_with(Resolver::builder().always_use_headers(true).build());
+
+    assert_eq!("1.1.1.1 false", get(&client, Some("34.120.0.9"), &forged));
+    assert_eq!("1.1.1.1 true", get(&client, Some("10.0.0.1"), &forged));
+
+    // an untrusted CDN header
+    let client = client_with(Resolver::builder().trust_cdn_header(CdnHeader::Cloudflare).build());
+
+    assert_eq!("93.184.216.34 false", get(&client, None, &[("CF-Connecting-IP", "93.184.216.34")]));
+}
+
+#[test]
+fn best_effort() {
+    let client = client_with(Resolver::builder().strict_parsing(true).build());
+
+    // the resolution fails, so the TCP peer is used
+    assert_eq!(
+        "10.0.0.1 false",
+        get(&client, Some("10.0.0.1"), &[("X-Forwarded-For", "93.184.216.34, invalid")])
+    );
+    assert_eq!(
+        "93.184.216.34 false",
+        get(&client, None, &[("X-Forwarded-For", "invalid"), ("X-Real-IP", "93.184.216.34")])
+    );
+    assert_eq!("0.0.0.0 false", get(&client, None, &[]));
+}
+
+#[test]
+fn fallback_is_not_trusted() {
+    let client = client_with(Resolver::builder().fallback("192.0.2.1".parse().unwrap()).build());
+
+    assert_eq!("0.0.0.0 false", get(&client, None, &[]));
+
+    // a genuine address which is the same as the fallback
+    assert_eq!("192.0.2.1 true", get(&client, Some("192.0.2.1"), &[]));
+}
+
+struct CountingChecker(AtomicUsize);
+
+#[rocket::async_trait]
+impl TrustChecker for CountingChecker {
+    async fn is_trusted(&self, _ip: IpAddr) -> bool {
+        self.0.fetch_add(1, Ordering::Relaxed);
+
+        true
+    }
+}
+
+#[test]
+fn trust_checker_is_awaited_once() {
+    let checker = Arc::new(CountingChecker(AtomicUsize::new(0)));
+
+    let client = client_with(Resolver::builder().trust_checker(checker.clone()).build());
+
+    let response = client
+        .get("/both")
+        .remote("34.120.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "93.184.216.34"))
+        .dispatch();
+
+    assert_eq!("93.184.216.34 true", response.into_string().unwrap());
+    assert_eq!(1, checker.0.load(Ordering::Relaxed));
+}
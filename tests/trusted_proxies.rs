@@ -0,0 +1,192 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{
+    error::ErrorKind,
+    figment::Figment,
+    http::{Header, Status},
+    local::blocking::Client,
+};
+use rocket_client_addr::{
+    ClientAddr, ClientAddrFairing, IpNetError, IpNetSet, IpNetSetError, Resolver,
+};
+
+#[get("/")]
+fn index(client_addr: &ClientAddr) -> String {
+    client_addr.to_string()
+}
+
+#[test]
+fn parse_valid() {
+    let trusted_proxies =
+        IpNetSet::parse(&["34.120.0.0/16", " 2606:4700::/32 ", "1.1.1.1"]).unwrap();
+
+    assert_eq!(3, trusted_proxies.as_slice().len());
+    assert!(trusted_proxies.contains(&"34.120.5.6".parse().unwrap()));
+    assert!(trusted_proxies.contains(&"2606:4700::1111".parse().unwrap()));
+    assert!(trusted_proxies.contains(&"1.1.1.1".parse().unwrap()));
+    assert!(!trusted_proxies.contains(&"1.1.1.2".parse().unwrap()));
+
+    assert!(IpNetSet::parse(&[]).unwrap().is_empty());
+}
+
+#[test]
+fn parse_invalid() {
+    let error = IpNetSet::parse(&[
+        "34.120.0.0/16",
+        "34.120.0.0/33",
+        "proxy.example.com",
+        "2606:4700::/32",
+        "10.0.0.0/x",
+    ])
+    .unwrap_err();
+
+    assert_eq!(
+        IpNetSetError::InvalidEntries(vec![
+            ("34.120.0.0/33".to_string(), IpNetError::InvalidPrefixLen),
+            ("proxy.example.com".to_string(), IpNetError::InvalidAddr),
+            ("10.0.0.0/x".to_string(), IpNetError::InvalidPrefixLen),
+        ]),
+        error
+    );
+
+    assert_eq!(
+        "invalid entries: \"34.120.0.0/33\" (invalid prefix length), \"proxy.example.com\" \
+         (invalid IP address), \"10.0.0.0/x\" (invalid prefix length)",
+        error.to_string()
+    );
+}
+
+fn figment(trusted_proxies: &[&str]) -> Figment {
+    rocket::Config::figment().merge(("client_addr.trusted_proxies", trusted_proxies))
+}
+
+#[test]
+fn fairing() {
+    let rocket = rocket::custom(figment(&["34.120.0.0/16"]))
+        .attach(ClientAddrFairing::new())
+        .mount("/", routes![index]);
+
+    let client = Client::untracked(rocket).unwrap();
+
+    let response = client
+        .get("/")
+        .remote("34.120.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "93.184.216.34"))
+        .dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!("93.184.216.34", response.into_string().unwrap());
+}
+
+#[test]
+fn fairing_aborts_ignition() {
+    let rocket = rocket::custom(figment(&["34.120.0.0/16", "34.120.0.0/33"]))
+        .attach(ClientAddrFairing::new())
+        .mount("/", routes![index]);
+
+    let error = Client::untracked(rocket).unwrap_err();
+
+    assert!(matches!(error.kind(), ErrorKind::FailedFairings(_)));
+
+    let rocket = rocket::custom(figment(&["34.120.0.0/16"]))
+        .manage(Resolver::default())
+        .attach(ClientAddrFairing::new())
+        .mount("/", routes![index]);
+
+    let error = Client::untracked(rocket).unwrap_err();
+
+    assert!(matches!(error.kind(), ErrorKind::FailedFairings(_)));
+}
+
+#[test]
+fn nested_cdns() {
+    // CDN A (e.g. 104.16.0.0/13) is in front of CDN B (e.g. 151.101.0.0/16), which connects to us
+    let trusted_proxies = IpNetSet::parse(&["104.16.0.0/13", "151.101.0.0/16"]).unwrap();
+
+    let rocket = rocket::build()
+        .manage(Resolver::builder().trust_proxies(trusted_proxies).build())
+        .mount("/", routes![index]);
+
+    let client = Client::untracked(rocket).unwrap();
+
+    let request = |forwarded_for: &'static str| {
+        client
+            .get("/")
+            .remote("151.101.0.2:8000".parse().unwrap())
+            .header(Header::new("X-Forwarded-For", forwarded_for))
+            .dispatch()
+            .into_string()
+            .unwrap()
+    };
+
+    // client, cdnA1, cdnA2, cdnB1
+    assert_eq!("93.184.216.34", request("93.184.216.34, 104.16.0.1, 104.23.255.2, 151.101.0.1"));
+
+    // an address prepended by the client is not used
+    assert_eq!(
+        "93.184.216.34",
+        request("1.1.1.1, 93.184.216.34, 104.16.0.1, 104.23.255.2, 151.101.0.1")
+    );
+
+    // an untrusted hop between the CDNs stops the walk
+    assert_eq!("34.120.0.1", request("93.184.216.34, 104.16.0.1, 34.120.0.1, 151.101.0.1"));
+}
+
+#[test]
+fn mixed_families() {
+    // an IPv6 load balancer (2606:4700::/32) in front of an IPv4 CDN (34.120.0.0/16)
+    let trusted_proxies = IpNetSet::parse(&["2606:4700::/32", "34.120.0.0/16"]).unwrap();
+
+    let rocket = rocket::build()
+        .manage(Resolver::builder().trust_proxies(trusted_proxies).build())
+        .mount("/", routes![index]);
+
+    let client = Client::untracked(rocket).unwrap();
+
+    let request = |remote: &str, forwarded_for: &'static str| {
+        client
+            .get("/")
+            .remote(remote.parse().unwrap())
+            .header(Header::new("X-Forwarded-For", forwarded_for))
+            .dispatch()
+            .into_string()
+            .unwrap()
+    };
+
+    // the IPv6 suffix is peeled before the IPv4 client
+    assert_eq!(
+        "93.184.216.34",
+        request("[2606:4700::3]:8000", "93.184.216.34, 2606:4700::1, 2606:4700::2")
+    );
+
+    // the families switch more than once along the chain
+    assert_eq!(
+        "2a00:1450::1",
+        request("[2606:4700::3]:8000", "2a00:1450::1, 34.120.0.1, 2606:4700::1")
+    );
+
+    // an IPv4-mapped entry is compared with the IPv4 range
+    assert_eq!(
+        "93.184.216.34",
+        request("[2606:4700::3]:8000", "93.184.216.34, ::ffff:34.120.0.1, 2606:4700::1")
+    );
+
+    // addresses just outside of the ranges
+    assert_eq!("34.121.0.1", request("34.120.0.2:8000", "93.184.216.34, 34.121.0.1"));
+    assert_eq!("2606:4701::1", request("34.120.0.2:8000", "93.184.216.34, 2606:4701::1"));
+}
+
+#[test]
+fn families_do_not_contain_each_other() {
+    let trusted_proxies = IpNetSet::parse(&["::/0"]).unwrap();
+
+    assert!(trusted_proxies.contains(&"2606:4700::1111".parse().unwrap()));
+    assert!(!trusted_proxies.contains(&"93.184.216.34".parse().unwrap()));
+
+    let trusted_proxies = IpNetSet::parse(&["0.0.0.0/0"]).unwrap();
+
+    assert!(trusted_proxies.contains(&"93.184.216.34".parse().unwrap()));
+    assert!(trusted_proxies.contains(&"::ffff:93.184.216.34".parse().unwrap()));
+    assert!(!trusted_proxies.contains(&"2606:4700::1111".parse().unwrap()));
+}
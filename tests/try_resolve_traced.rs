@@ -0,0 +1,114 @@
+use rocket::{http::Header, local::blocking::Client};
+use rocket_client_addr::{
+    AddrSource, CdnHeader, ClientAddr, CustomSource, ResolutionStep, Resolver, StepVerdict,
+};
+
+fn step(source: AddrSource, ip: Option<&str>, verdict: StepVerdict) -> ResolutionStep {
+    ResolutionStep {
+        source,
+        ip: ip.map(|ip| ip.parse().unwrap()),
+        verdict,
+    }
+}
+
+#[test]
+fn multi_source() {
+    let resolver = Resolver::builder()
+        .trust_cdn_header(CdnHeader::Cloudflare)
+        .custom_source(CustomSource::Header("X-Client-IP".into()))
+        .reject_nonroutable_from_headers(true)
+        .build();
+
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    let request = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "fe80::1, 10.0.0.2"))
+        .header(Header::new("X-Real-IP", "93.184.216.34"));
+
+    let (client_addr, steps) = resolver.try_resolve_traced(request.inner());
+
+    assert_eq!(Some(ClientAddr { ip: "93.184.216.34".parse().unwrap() }), client_addr);
+    assert_eq!(
+        vec![
+            step(AddrSource::CdnHeader(CdnHeader::Cloudflare), None, StepVerdict::Absent),
+            step(AddrSource::Remote, Some("10.0.0.1"), StepVerdict::Trusted),
+            step(AddrSource::CustomSource, None, StepVerdict::Absent),
+            step(AddrSource::ForwardedFor, Some("fe80::1"), StepVerdict::NonRoutable),
+            step(AddrSource::RealIp, Some("93.184.216.34"), StepVerdict::Accepted),
+        ],
+        steps
+    );
+}
+
+#[test]
+fn untrusted_peer() {
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    let request = client
+        .get("/")
+        .remote("34.120.0.9:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "93.184.216.34"));
+
+    let (client_addr, steps) = Resolver::default().try_resolve_traced(request.inner());
+
+    assert_eq!(Some(ClientAddr { ip: "34.120.0.9".parse().unwrap() }), client_addr);
+    assert_eq!(vec![step(AddrSource::Remote, Some("34.120.0.9"), StepVerdict::Accepted)], steps);
+}
+
+#[test]
+fn forwarded_and_malformed() {
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    let request = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("Forwarded", "for=93.184.216.34"));
+
+    let (client_addr, steps) = Resolver::default().try_resolve_traced(request.inner());
+
+    assert_eq!(Some(ClientAddr { ip: "93.184.216.34".parse().unwrap() }), client_addr);
+    assert_eq!(
+        vec![
+            step(AddrSource::Remote, Some("10.0.0.1"), StepVerdict::Trusted),
+            step(AddrSource::ForwardedFor, None, StepVerdict::Absent),
+            step(AddrSource::Forwarded, Some("93.184.216.34"), StepVerdict::Accepted),
+        ],
+        steps
+    );
+
+    let request = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "93.184.216.34, garbage"));
+
+    let resolver = Resolver::builder().strict_parsing(true).build();
+    let (client_addr, steps) = resolver.try_resolve_traced(request.inner());
+
+    assert_eq!(None, client_addr);
+    assert_eq!(Some(&step(AddrSource::ForwardedFor, None, StepVerdict::Malformed)), steps.last());
+}
+
+#[test]
+fn site_local() {
+    let client = Client::untracked(rocket::build()).unwrap();
+
+    let request = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "fec0::1"))
+        .header(Header::new("X-Real-IP", "93.184.216.34"));
+
+    let resolver = Resolver::builder().reject_site_local_from_headers(true).build();
+    let (client_addr, steps) = resolver.try_resolve_traced(request.inner());
+
+    assert_eq!(None, client_addr);
+    assert_eq!(
+        vec![
+            step(AddrSource::Remote, Some("10.0.0.1"), StepVerdict::Trusted),
+            step(AddrSource::ForwardedFor, Some("fec0::1"), StepVerdict::SiteLocal),
+        ],
+        steps
+    );
+}
@@ -0,0 +1,41 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+};
+use rocket_client_addr::UpstreamAddr;
+
+#[get("/")]
+fn index(upstream_addr: UpstreamAddr) -> String {
+    upstream_addr.ip.to_string()
+}
+
+fn client() -> Client {
+    Client::untracked(rocket::build().mount("/", routes![index])).unwrap()
+}
+
+#[test]
+fn present() {
+    let client = client();
+
+    let response = client
+        .get("/")
+        .remote("10.0.0.1:8000".parse().unwrap())
+        .header(Header::new("X-Forwarded-For", "93.184.216.34"))
+        .header(Header::new("X-Real-IP", "93.184.216.34"))
+        .dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!("10.0.0.1", response.into_string().unwrap());
+}
+
+#[test]
+fn absent() {
+    let client = client();
+
+    let response = client.get("/").header(Header::new("X-Real-IP", "93.184.216.34")).dispatch();
+
+    assert_eq!(Status::BadRequest, response.status());
+}